@@ -8,6 +8,7 @@ import {
   getPageCount,
   signPdfWithOptions,
   getPdfInfoBeforeSigning,
+  parsePageRange,
   SigningOptions,
 } from './platform-loader.js';
 import ora from 'ora';
@@ -25,32 +26,17 @@ function showProgress<T>(message: string, operation: () => T): T {
   }
 }
 
-// Function to parse page numbers from page option
+// Resolve the --pages option with the Rust page-range parser so every binding
+// accepts the same expressions (e.g. "1-3,7", "odd", "last", "r1", "!2").
+// An expression that does not resolve against this document fails the command
+// with the parser's message.
 function parsePageNumbers(pageOption: string, totalPages: number): number[] {
-  if (pageOption === 'all') {
-    return Array.from({ length: totalPages }, (_, i) => i + 1);
-  }
-
-  if (pageOption === 'even') {
-    return Array.from(
-      { length: Math.floor(totalPages / 2) },
-      (_, i) => (i + 1) * 2,
-    ).filter((p) => p <= totalPages);
-  }
-
-  if (pageOption === 'odd') {
-    return Array.from(
-      { length: Math.ceil(totalPages / 2) },
-      (_, i) => i * 2 + 1,
-    ).filter((p) => p <= totalPages);
+  try {
+    return parsePageRange(pageOption, totalPages).sort((a, b) => a - b);
+  } catch (error) {
+    const reason = error instanceof Error ? error.message : String(error);
+    throw new Error(`Invalid --pages value "${pageOption}": ${reason}`);
   }
-
-  // Parse comma-separated list like "1,2,5"
-  return pageOption
-    .split(',')
-    .map((p: string) => parseInt(p.trim()))
-    .filter((p: number) => !isNaN(p) && p > 0 && p <= totalPages)
-    .sort((a, b) => a - b);
 }
 
 // Get version from package.json with fallback
@@ -177,7 +163,7 @@ program
   )
   .option(
     '--pages <pages>',
-    'Pages to watermark: "even", "odd", "all", or comma-separated list (e.g., "1,3,5")',
+    'Pages to watermark: "all", "even", "odd", or a page-range expression (e.g., "1-3,7", "last", "r1", "!2")',
     'all',
   )
   .option(
@@ -139,6 +139,7 @@ export const {
   getKeyInfoFromJson,
   signPdfWithKey,
   verifyPdfSignature,
+  parsePageRange,
 } = nativeModule;
 
 // Export types if they exist
@@ -148,6 +149,7 @@ export type SigningOptions = {
   xPosition?: number;
   yPosition?: number;
   pages?: number[];
+  pageRange?: string;
   position?: string;
   rotation?: number;
   opacity?: number;
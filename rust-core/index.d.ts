@@ -1,27 +1,296 @@
 /* auto-generated by NAPI-RS */
 /* eslint-disable */
+/** Stamp running headers and footers with page number templates */
+export declare function addHeaderFooter(filePath: string, options: HeaderFooterOptions): number
+
+/** Crop pages to the area their content covers */
+export declare function autoCrop(inputPath: string, outputPath: string, pageRange?: string | undefined | null, margin?: number | undefined | null): number
+
+export interface BarcodeOptions {
+  symbology?: string
+  payload?: string
+  text?: string
+  signatureInfo?: string
+  page?: number
+  xPosition?: number
+  yPosition?: number
+  size?: number
+  height?: number
+  errorCorrection?: string
+}
+
+export interface BatesOptions {
+  prefix?: string
+  suffix?: string
+  startNumber?: number
+  digits?: number
+  position?: string
+  fontSize?: number
+  color?: string
+  margin?: number
+  outputDir?: string
+  outputSuffix?: string
+}
+
+export interface BlankPageOptions {
+  paperSize?: string
+  width?: number
+  height?: number
+  landscape?: boolean
+  count?: number
+}
+
+/** Lay pages out two per side in saddle-stitch booklet order */
+export declare function booklet(inputPath: string, outputPath: string, options?: ImposeOptions | undefined | null): number
+
+/** Interleave the pages of two PDFs, e.g. scanned fronts and backs */
+export declare function collate(firstPath: string, secondPath: string, outputPath: string, options?: CollateOptions | undefined | null): number
+
+export interface CollateOptions {
+  reverseSecond?: boolean
+}
+
+/** Remove the pages selected by a page-range expression */
+export declare function deletePages(inputPath: string, outputPath: string, pageRange: string): number
+
+/**
+ * Compare the structure of two PDFs (or two revisions of one) and return
+ * the changes as JSON
+ */
+export declare function diffPdfs(oldPath: string, newPath: string, options?: DiffOptions | undefined | null): string
+
+export interface DiffOptions {
+  oldRevision?: number
+  newRevision?: number
+  maxOperatorChanges?: number
+  compareObjects?: boolean
+}
+
+export interface DocumentMetadata {
+  title?: string
+  author?: string
+  subject?: string
+  keywords?: string
+  creator?: string
+  producer?: string
+  creationDate?: string
+  modDate?: string
+  custom?: Record<string, string>
+}
+
+/** Insert copies of the selected pages right after each of them */
+export declare function duplicatePages(inputPath: string, outputPath: string, pageRange: string, copies?: number | undefined | null): number
+
+/**
+ * Write the images used on the selected pages to a directory; returns the
+ * written and skipped images as JSON
+ */
+export declare function extractImages(inputPath: string, outputDir: string, pageRange?: string | undefined | null): string
+
+/** Copy the pages selected by a page-range expression into a new file */
+export declare function extractPages(inputPath: string, outputPath: string, pageRange: string): number
+
+/**
+ * Write the file exactly as it was at a revision (1 is the original);
+ * returns its page count
+ */
+export declare function extractRevision(filePath: string, revision: number, outputPath: string): number
+
 /** Generate a new cryptographic key pair for digital signing */
 export declare function generateSigningKeyPair(): string
 
 /** Get key information (public key and fingerprint) from a key pair JSON */
 export declare function getKeyInfoFromJson(keyPairJson: string): string
 
+/** Read Title, Author, dates and custom properties as JSON */
+export declare function getMetadata(filePath: string): string
+
 export declare function getPageCount(filePath: string): number
 
 /** Get file checksum for user verification */
 export declare function getPdfChecksum(filePath: string): string
 
+/**
+ * Describe a PDF (version, metadata, pages, security, forms, conformance
+ * claims) as JSON
+ */
+export declare function getPdfInfo(filePath: string): string
+
 export declare function getPdfInfoBeforeSigning(filePath: string): number
 
+export interface HeaderFooterOptions {
+  header?: RunningText
+  footer?: RunningText
+  fontSize?: number
+  color?: string
+  margin?: number
+}
+
+export interface ImposeOptions {
+  pageRange?: string
+  paperSize?: string
+  width?: number
+  height?: number
+  landscape?: boolean
+  margin?: number
+  gutter?: number
+  border?: boolean
+}
+
+/** Insert blank pages before the given 1-based positions */
+export declare function insertBlankPages(inputPath: string, outputPath: string, positions: Array<number>, options?: BlankPageOptions | undefined | null): number
+
+/** Insert pages from another PDF after the first `at_index` pages of the target */
+export declare function insertPages(targetPath: string, sourcePath: string, outputPath: string, atIndex: number, sourceRange?: string | undefined | null): number
+
+/** Dump the trailer, xref table, page tree or a single object as JSON or text */
+export declare function inspectPdf(filePath: string, target: string, options?: InspectOptions | undefined | null): string
+
+export interface InspectOptions {
+  format?: string
+  maxStreamBytes?: number
+  decodeStreams?: boolean
+}
+
+/**
+ * List the fonts used on each page with their type, embedding and encoding
+ * as JSON
+ */
+export declare function listFonts(filePath: string): string
+
+/** List the image XObjects used on each page as JSON */
+export declare function listImages(filePath: string): string
+
+/**
+ * List the original file and each incremental update with the objects it
+ * changed and the signatures covering it, as JSON
+ */
+export declare function listRevisions(filePath: string): string
+
+export interface MergeOptions {
+  pageRanges?: Array<string>
+  fileBookmarks?: boolean
+}
+
+/** Merge PDFs into one document and return a summary as JSON */
+export declare function mergePdfs(inputPaths: Array<string>, outputPath: string, options?: MergeOptions | undefined | null): string
+
+/** Bake page rotation into the content so every page has /Rotate 0 */
+export declare function normalizeRotation(inputPath: string, outputPath: string, pageRange?: string | undefined | null): number
+
+/** Place 2, 4, 6 or 9 pages on each sheet */
+export declare function nUp(inputPath: string, outputPath: string, perSheet: number, options?: ImposeOptions | undefined | null): number
+
+/** Draw a page of a template PDF over or under selected pages */
+export declare function overlayPdf(inputPath: string, templatePath: string, outputPath: string, options?: OverlayOptions | undefined | null): number
+
+export interface OverlayOptions {
+  templatePage?: number
+  pageRange?: string
+  placement?: string
+  scale?: string
+  opacity?: number
+  layerName?: string
+  layerVisibility?: string
+}
+
+export interface PageBoxOptions {
+  pageRange?: string
+  mediaBox?: Array<number>
+  cropBox?: Array<number>
+  bleedBox?: Array<number>
+  trimBox?: Array<number>
+  artBox?: Array<number>
+}
+
+/**
+ * Resolve a page-range expression (e.g. "1-3,7,10-", "odd", "r1", "!2")
+ * to 1-based page numbers
+ */
+export declare function parsePageRange(expression: string, totalPages: number): Array<number>
+
+/** Remove a watermark layer (optional content group) from every page */
+export declare function removeWatermark(filePath: string, layerName: string): number
+
+/** Rebuild a damaged PDF from the objects in its bytes and write a repaired copy; returns what was fixed as JSON */
+export declare function repairPdf(inputPath: string, outputPath: string): string
+
+/** Move the listed pages to the front in the given order */
+export declare function reorderPages(inputPath: string, outputPath: string, pageOrder: string): number
+
+/** Rotate pages clockwise by a multiple of 90 degrees (or set their rotation) */
+export declare function rotatePages(inputPath: string, outputPath: string, angle: number, options?: RotateOptions | undefined | null): number
+
+export interface RotateOptions {
+  pageRange?: string
+  mode?: string
+}
+
+/**
+ * Text for the left, center and right slots of a header or footer.
+ *
+ * Slots accept the template variables `{page}`, `{total}`, `{filename}`,
+ * `{date}` and `{title}`.
+ */
+export interface RunningText {
+  left?: string
+  center?: string
+  right?: string
+  pages?: Array<number>
+  pageRange?: string
+}
+
+/**
+ * Strip metadata, scripts, attachments, hidden layers and chosen annotation
+ * types; returns a report of what was removed as JSON
+ */
+export declare function sanitize(inputPath: string, outputPath: string, options?: SanitizeOptions | undefined | null): string
+
+export interface SanitizeOptions {
+  removeMetadata?: boolean
+  removeJavascript?: boolean
+  removeAttachments?: boolean
+  removeHiddenLayers?: boolean
+  removePieceInfo?: boolean
+  annotationTypes?: Array<string>
+}
+
+export interface ScaleOptions {
+  pageRange?: string
+  paperSize?: string
+  width?: number
+  height?: number
+  mode?: string
+  landscape?: boolean
+}
+
+/** Scale pages onto a new paper size without rasterizing them */
+export declare function scalePages(inputPath: string, outputPath: string, options?: ScaleOptions | undefined | null): number
+
+/**
+ * Update the Info dictionary and XMP metadata together; returns the
+ * resulting metadata as JSON
+ */
+export declare function setMetadata(inputPath: string, outputPath: string, metadata: DocumentMetadata): string
+
+/** Set MediaBox, CropBox, BleedBox, TrimBox or ArtBox on selected pages */
+export declare function setPageBoxes(inputPath: string, outputPath: string, options: PageBoxOptions): number
+
 export interface SigningOptions {
   fontSize?: number
   color?: string
   xPosition?: number
   yPosition?: number
   pages?: Array<number>
+  pageRange?: string
   position?: string
   rotation?: number
   opacity?: number
+  layout?: string
+  tileSpacing?: number
+  placement?: string
+  layerName?: string
+  layerVisibility?: string
 }
 
 export declare function signPdf(filePath: string, signatureText: string): void
@@ -35,5 +304,25 @@ export declare function signPdfWithOptions(filePath: string, signatureText: stri
 
 export declare function signPdfWithVisibleText(filePath: string, signatureText: string): void
 
+/** Split a PDF into several files and return the written parts as JSON */
+export declare function splitPdf(inputPath: string, options?: SplitOptions | undefined | null): string
+
+export interface SplitOptions {
+  mode?: string
+  every?: number
+  ranges?: Array<string>
+  outputDir?: string
+  pattern?: string
+}
+
+/** Draw a QR code or Code 128 barcode on a page and return the encoded payload */
+export declare function stampBarcode(inputPath: string, outputPath: string, options: BarcodeOptions): string
+
+/** Stamp Bates numbers across a set of PDFs and return the manifest as JSON */
+export declare function stampBatesNumbers(inputPaths: Array<string>, options?: BatesOptions | undefined | null): string
+
+/** Check the structure of a PDF and report findings with severities as JSON */
+export declare function validatePdf(filePath: string): string
+
 /** Verify a digital signature */
 export declare function verifyPdfSignature(filePath: string, signatureInfoJson: string, publicKeyB64: string): string
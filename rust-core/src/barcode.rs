@@ -0,0 +1,328 @@
+use lopdf::content::{Content, Operation};
+use lopdf::{Dictionary, Object, Stream};
+use napi_derive::napi;
+use qrcode::{Color, EcLevel, QrCode};
+
+use crate::sign::{append_page_content, page_box};
+
+/// Code 128 bar/space widths for symbol values 0..=105 (start codes included)
+#[rustfmt::skip]
+const CODE128_PATTERNS: [[u8; 6]; 106] = [
+    [2, 1, 2, 2, 2, 2], [2, 2, 2, 1, 2, 2], [2, 2, 2, 2, 2, 1], [1, 2, 1, 2, 2, 3],
+    [1, 2, 1, 3, 2, 2], [1, 3, 1, 2, 2, 2], [1, 2, 2, 2, 1, 3], [1, 2, 2, 3, 1, 2],
+    [1, 3, 2, 2, 1, 2], [2, 2, 1, 2, 1, 3], [2, 2, 1, 3, 1, 2], [2, 3, 1, 2, 1, 2],
+    [1, 1, 2, 2, 3, 2], [1, 2, 2, 1, 3, 2], [1, 2, 2, 2, 3, 1], [1, 1, 3, 2, 2, 2],
+    [1, 2, 3, 1, 2, 2], [1, 2, 3, 2, 2, 1], [2, 2, 3, 2, 1, 1], [2, 2, 1, 1, 3, 2],
+    [2, 2, 1, 2, 3, 1], [2, 1, 3, 2, 1, 2], [2, 2, 3, 1, 1, 2], [3, 1, 2, 1, 3, 1],
+    [3, 1, 1, 2, 2, 2], [3, 2, 1, 1, 2, 2], [3, 2, 1, 2, 2, 1], [3, 1, 2, 2, 1, 2],
+    [3, 2, 2, 1, 1, 2], [3, 2, 2, 2, 1, 1], [2, 1, 2, 1, 2, 3], [2, 1, 2, 3, 2, 1],
+    [2, 3, 2, 1, 2, 1], [1, 1, 1, 3, 2, 3], [1, 3, 1, 1, 2, 3], [1, 3, 1, 3, 2, 1],
+    [1, 1, 2, 3, 1, 3], [1, 3, 2, 1, 1, 3], [1, 3, 2, 3, 1, 1], [2, 1, 1, 3, 1, 3],
+    [2, 3, 1, 1, 1, 3], [2, 3, 1, 3, 1, 1], [1, 1, 2, 1, 3, 3], [1, 1, 2, 3, 3, 1],
+    [1, 3, 2, 1, 3, 1], [1, 1, 3, 1, 2, 3], [1, 1, 3, 3, 2, 1], [1, 3, 3, 1, 2, 1],
+    [3, 1, 3, 1, 2, 1], [2, 1, 1, 3, 3, 1], [2, 3, 1, 1, 3, 1], [2, 1, 3, 1, 1, 3],
+    [2, 1, 3, 3, 1, 1], [2, 1, 3, 1, 3, 1], [3, 1, 1, 1, 2, 3], [3, 1, 1, 3, 2, 1],
+    [3, 3, 1, 1, 2, 1], [3, 1, 2, 1, 1, 3], [3, 1, 2, 3, 1, 1], [3, 3, 2, 1, 1, 1],
+    [3, 1, 4, 1, 1, 1], [2, 2, 1, 4, 1, 1], [4, 3, 1, 1, 1, 1], [1, 1, 1, 2, 2, 4],
+    [1, 1, 1, 4, 2, 2], [1, 2, 1, 1, 2, 4], [1, 2, 1, 4, 2, 1], [1, 4, 1, 1, 2, 2],
+    [1, 4, 1, 2, 2, 1], [1, 1, 2, 2, 1, 4], [1, 1, 2, 4, 1, 2], [1, 2, 2, 1, 1, 4],
+    [1, 2, 2, 4, 1, 1], [1, 4, 2, 1, 1, 2], [1, 4, 2, 2, 1, 1], [2, 4, 1, 2, 1, 1],
+    [2, 2, 1, 1, 1, 4], [4, 1, 3, 1, 1, 1], [2, 4, 1, 1, 1, 2], [1, 3, 4, 1, 1, 1],
+    [1, 1, 1, 2, 4, 2], [1, 2, 1, 1, 4, 2], [1, 2, 1, 2, 4, 1], [1, 1, 4, 2, 1, 2],
+    [1, 2, 4, 1, 1, 2], [1, 2, 4, 2, 1, 1], [4, 1, 1, 2, 1, 2], [4, 2, 1, 1, 1, 2],
+    [4, 2, 1, 2, 1, 1], [2, 1, 2, 1, 4, 1], [2, 1, 4, 1, 2, 1], [4, 1, 2, 1, 2, 1],
+    [1, 1, 1, 1, 4, 3], [1, 1, 1, 3, 4, 1], [1, 3, 1, 1, 4, 1], [1, 1, 4, 1, 1, 3],
+    [1, 1, 4, 3, 1, 1], [4, 1, 1, 1, 1, 3], [4, 1, 1, 3, 1, 1], [1, 1, 3, 1, 4, 1],
+    [1, 1, 4, 1, 3, 1], [3, 1, 1, 1, 4, 1], [4, 1, 1, 1, 3, 1], [2, 1, 1, 4, 1, 2],
+    [2, 1, 1, 2, 1, 4], [2, 1, 1, 2, 3, 2],
+];
+
+/// Code 128 stop pattern, including the final termination bar
+const CODE128_STOP: [u8; 7] = [2, 3, 3, 1, 1, 1, 2];
+
+const CODE128_START_B: usize = 104;
+
+#[derive(Debug, Clone, Default)]
+#[napi(object)]
+pub struct BarcodeOptions {
+    pub symbology: Option<String>,      // "qr" (default), "code128"
+    pub payload: Option<String>,        // "text" (default), "checksum", "signer"
+    pub text: Option<String>,           // Payload text when `payload` is "text"
+    pub signature_info: Option<String>, // SignatureInfo JSON when `payload` is "signer"
+    pub page: Option<u32>,              // 1-based page number (default: 1)
+    pub x_position: Option<f64>,
+    pub y_position: Option<f64>,
+    pub size: Option<f64>, // QR side length or Code 128 width in points (default: 72 / 180)
+    pub height: Option<f64>, // Code 128 bar height in points (default: 36)
+    pub error_correction: Option<String>, // QR level: "L", "M" (default), "Q", "H"
+}
+
+/// Encodes `data` as a QR code and returns the module grid, row by row from
+/// the top, with `true` for dark modules.
+pub fn encode_qr(data: &str, level: &str) -> Result<Vec<Vec<bool>>, Box<dyn std::error::Error>> {
+    let ec_level = match level.to_uppercase().as_str() {
+        "L" => EcLevel::L,
+        "M" => EcLevel::M,
+        "Q" => EcLevel::Q,
+        "H" => EcLevel::H,
+        other => return Err(format!("Unknown error correction level: {other}").into()),
+    };
+    let code = QrCode::with_error_correction_level(data.as_bytes(), ec_level)
+        .map_err(|e| format!("Failed to encode QR code: {e}"))?;
+
+    let width = code.width();
+    Ok(code
+        .to_colors()
+        .chunks(width)
+        .map(|row| row.iter().map(|color| *color == Color::Dark).collect())
+        .collect())
+}
+
+/// Encodes `data` as Code 128 (code set B) and returns the alternating
+/// bar/space module widths, starting with a bar.
+pub fn encode_code128(data: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut values = vec![CODE128_START_B];
+    for c in data.chars() {
+        match c as u32 {
+            code @ 32..=127 => values.push((code - 32) as usize),
+            _ => return Err(format!("Character '{c}' cannot be encoded in Code 128").into()),
+        }
+    }
+
+    let checksum = values
+        .iter()
+        .enumerate()
+        .map(|(position, value)| position.max(1) * value)
+        .sum::<usize>()
+        % 103;
+    values.push(checksum);
+
+    let mut widths: Vec<u8> = values
+        .iter()
+        .flat_map(|value| CODE128_PATTERNS[*value])
+        .collect();
+    widths.extend(CODE128_STOP);
+    Ok(widths)
+}
+
+/// Resolves the barcode payload: arbitrary text, the document checksum from
+/// `get_file_checksum`, or the signer fingerprint from a `SignatureInfo`.
+pub fn barcode_payload(
+    input_path: &str,
+    options: &BarcodeOptions,
+) -> Result<String, Box<dyn std::error::Error>> {
+    match options.payload.as_deref().unwrap_or("text") {
+        "text" => options
+            .text
+            .clone()
+            .ok_or_else(|| "Barcode text is required when payload is 'text'".into()),
+        #[cfg(feature = "crypto")]
+        "checksum" => crate::crypto::get_file_checksum(input_path),
+        #[cfg(feature = "crypto")]
+        "signer" => {
+            let json = options
+                .signature_info
+                .as_deref()
+                .ok_or("Signature info is required when payload is 'signer'")?;
+            let info: crate::crypto::SignatureInfo = serde_json::from_str(json)?;
+            Ok(info.signer_fingerprint)
+        }
+        #[cfg(not(feature = "crypto"))]
+        "checksum" | "signer" => {
+            let _ = input_path;
+            Err("Checksum and signer payloads require the crypto feature".into())
+        }
+        other => Err(format!("Unknown barcode payload: {other}").into()),
+    }
+}
+
+/// Draws a QR code or Code 128 barcode as vector paths on one page and saves
+/// the result to `output_path`.
+///
+/// # Returns
+/// The encoded payload, so callers can record what the printout will scan as.
+pub fn stamp_barcode(
+    input_path: &str,
+    output_path: &str,
+    options: &BarcodeOptions,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let payload = barcode_payload(input_path, options)?;
+    let mut document = lopdf::Document::load(input_path)?;
+
+    let page_number = options.page.unwrap_or(1);
+    let page_id = *document
+        .get_pages()
+        .get(&page_number)
+        .ok_or_else(|| format!("Page {page_number} does not exist"))?;
+    let [llx, lly, _, _] = page_box(&document, page_id);
+    let x = options.x_position.unwrap_or(llx + 36.0);
+    let y = options.y_position.unwrap_or(lly + 36.0);
+
+    let operations = match options.symbology.as_deref().unwrap_or("qr") {
+        "qr" => {
+            let level = options.error_correction.as_deref().unwrap_or("M");
+            qr_operations(
+                &encode_qr(&payload, level)?,
+                x,
+                y,
+                options.size.unwrap_or(72.0),
+            )
+        }
+        "code128" => code128_operations(
+            &encode_code128(&payload)?,
+            x,
+            y,
+            options.size.unwrap_or(180.0),
+            options.height.unwrap_or(36.0),
+        ),
+        other => return Err(format!("Unsupported symbology: {other}").into()),
+    };
+
+    let content_data = Content { operations }.encode()?;
+    let content_id = document.add_object(Stream::new(Dictionary::new(), content_data));
+    append_page_content(&mut document, page_id, content_id);
+
+    document.save(output_path)?;
+    Ok(payload)
+}
+
+/// Builds path operations for a QR code whose lower-left corner (including
+/// the four-module quiet zone) sits at `(x, y)`.
+fn qr_operations(modules: &[Vec<bool>], x: f64, y: f64, size: f64) -> Vec<Operation> {
+    const QUIET_ZONE: usize = 4;
+    let count = modules.len();
+    let module = size / (count + 2 * QUIET_ZONE) as f64;
+
+    let mut operations = vec![Operation::new("q", vec![])];
+    operations.extend(filled_rect(x, y, size, size, 1.0));
+    operations.push(Operation::new("g", vec![Object::Real(0.0)]));
+
+    // Merge horizontal runs of dark modules into single rectangles
+    for (row_index, row) in modules.iter().enumerate() {
+        let row_y = y + (count + QUIET_ZONE - 1 - row_index) as f64 * module;
+        let mut column = 0;
+        while column < count {
+            if !row[column] {
+                column += 1;
+                continue;
+            }
+            let start = column;
+            while column < count && row[column] {
+                column += 1;
+            }
+            operations.push(rect(
+                x + (start + QUIET_ZONE) as f64 * module,
+                row_y,
+                (column - start) as f64 * module,
+                module,
+            ));
+        }
+    }
+    operations.push(Operation::new("f", vec![]));
+    operations.push(Operation::new("Q", vec![]));
+    operations
+}
+
+/// Builds path operations for Code 128 bars, with a ten-module quiet zone on
+/// each side inside the given width.
+fn code128_operations(widths: &[u8], x: f64, y: f64, width: f64, height: f64) -> Vec<Operation> {
+    const QUIET_ZONE: u32 = 10;
+    let total_modules: u32 = widths.iter().map(|w| *w as u32).sum::<u32>() + 2 * QUIET_ZONE;
+    let module = width / total_modules as f64;
+
+    let mut operations = vec![Operation::new("q", vec![])];
+    operations.extend(filled_rect(x, y, width, height, 1.0));
+    operations.push(Operation::new("g", vec![Object::Real(0.0)]));
+
+    let mut cursor = x + QUIET_ZONE as f64 * module;
+    for (index, bar_width) in widths.iter().enumerate() {
+        let bar = *bar_width as f64 * module;
+        if index % 2 == 0 {
+            operations.push(rect(cursor, y, bar, height));
+        }
+        cursor += bar;
+    }
+    operations.push(Operation::new("f", vec![]));
+    operations.push(Operation::new("Q", vec![]));
+    operations
+}
+
+fn rect(x: f64, y: f64, width: f64, height: f64) -> Operation {
+    Operation::new(
+        "re",
+        vec![
+            Object::Real(x as f32),
+            Object::Real(y as f32),
+            Object::Real(width as f32),
+            Object::Real(height as f32),
+        ],
+    )
+}
+
+/// A gray-filled rectangle, used as the light background behind a symbol
+fn filled_rect(x: f64, y: f64, width: f64, height: f64, gray: f64) -> Vec<Operation> {
+    vec![
+        Operation::new("g", vec![Object::Real(gray as f32)]),
+        rect(x, y, width, height),
+        Operation::new("f", vec![]),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_pdf;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_code128_patterns_are_eleven_modules() {
+        for pattern in CODE128_PATTERNS {
+            assert_eq!(pattern.iter().map(|w| *w as u32).sum::<u32>(), 11);
+        }
+        assert_eq!(CODE128_STOP.iter().map(|w| *w as u32).sum::<u32>(), 13);
+    }
+
+    #[test]
+    fn test_code128_checksum() {
+        // 104 + 48*1 + 42*2 + 42*3 + 17*4 + 18*5 + 19*6 + 35*7 = 879, and 879 % 103 = 55
+        let widths = encode_code128("PJJ123C").unwrap();
+        let check = &widths[widths.len() - 13..widths.len() - 7];
+        assert_eq!(check, CODE128_PATTERNS[55]);
+        assert!(encode_code128("naïve€").is_err());
+    }
+
+    #[test]
+    fn test_qr_has_finder_patterns() {
+        let modules = encode_qr("https://example.com/verify", "M").unwrap();
+        let size = modules.len();
+        assert_eq!((size - 17) % 4, 0);
+        for (row, column) in [(0, 0), (0, size - 7), (size - 7, 0)] {
+            assert!(modules[row][column]);
+            assert!(modules[row + 3][column + 3]);
+            assert!(!modules[row + 1][column + 1]);
+        }
+    }
+
+    #[test]
+    fn test_stamp_qr_code() {
+        let mut doc = create_test_pdf(2, "test", "content").unwrap();
+        let input = NamedTempFile::new().unwrap();
+        let output = NamedTempFile::new().unwrap();
+        let input_path = input.path().to_string_lossy().to_string();
+        let output_path = output.path().to_string_lossy().to_string();
+        doc.save(&input_path).unwrap();
+
+        let options = BarcodeOptions {
+            text: Some("verify me".to_string()),
+            page: Some(2),
+            ..Default::default()
+        };
+        let payload = stamp_barcode(&input_path, &output_path, &options).unwrap();
+        assert_eq!(payload, "verify me");
+
+        let doc = lopdf::Document::load(&output_path).unwrap();
+        let page_id = doc.get_pages()[&2];
+        let content = doc.get_page_content(page_id).unwrap();
+        assert!(String::from_utf8_lossy(&content).contains(" re"));
+    }
+}
@@ -0,0 +1,284 @@
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::sign::page_box;
+use crate::stamp::{stamp_text, TextPlacement};
+use crate::watermark::{parse_color, text_width};
+
+#[derive(Debug, Clone, Default)]
+#[napi(object)]
+pub struct BatesOptions {
+    pub prefix: Option<String>,
+    pub suffix: Option<String>,
+    pub start_number: Option<u32>, // default: 1
+    pub digits: Option<u32>,       // Zero-padded width of the counter (default: 6)
+    pub position: Option<String>, // "bottom-right" (default), "bottom-left", "bottom-center", "top-left", "top-center", "top-right"
+    pub font_size: Option<f64>,
+    pub color: Option<String>,
+    pub margin: Option<f64>,
+    pub output_dir: Option<String>, // Defaults to the directory of each input
+    pub output_suffix: Option<String>, // Appended to each file stem (default: "_bates")
+}
+
+/// The Bates range assigned to one stamped file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatesEntry {
+    pub input_file: String,
+    pub output_file: String,
+    pub page_count: u32,
+    pub first_number: String,
+    pub last_number: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatesManifest {
+    pub files: Vec<BatesEntry>,
+    pub next_number: u64,
+}
+
+/// Formats a Bates label such as `ACME000123-CONF`.
+pub fn format_bates_number(prefix: &str, number: u64, digits: u32, suffix: &str) -> String {
+    format!("{prefix}{number:0width$}{suffix}", width = digits as usize)
+}
+
+/// Stamps Bates numbers across a set of PDFs, continuing the counter from
+/// one file to the next in the order given.
+///
+/// Inputs are left untouched; each stamped copy is written next to its input
+/// (or into `output_dir`) with `output_suffix` appended to the file stem.
+/// Nothing is written if an input cannot be loaded or has no pages, or if an
+/// output would replace an input or another output.
+pub fn stamp_bates_numbers(
+    input_paths: &[String],
+    options: &BatesOptions,
+) -> Result<BatesManifest, Box<dyn std::error::Error>> {
+    if input_paths.is_empty() {
+        return Err("No input files given".into());
+    }
+
+    let prefix = options.prefix.as_deref().unwrap_or("");
+    let suffix = options.suffix.as_deref().unwrap_or("");
+    let digits = options.digits.unwrap_or(6);
+    let font_size = options.font_size.unwrap_or(10.0);
+    let margin = options.margin.unwrap_or(36.0);
+    let color = options
+        .color
+        .as_deref()
+        .and_then(parse_color)
+        .unwrap_or((0.0, 0.0, 0.0));
+    let position = options.position.as_deref().unwrap_or("bottom-right");
+
+    let output_files: Vec<String> = input_paths
+        .iter()
+        .map(|input_path| output_path(input_path, options))
+        .collect();
+    let inputs: Vec<PathBuf> = input_paths.iter().map(|path| file_key(path)).collect();
+    let mut outputs: HashMap<PathBuf, &str> = HashMap::new();
+    for (input_path, output_file) in input_paths.iter().zip(&output_files) {
+        let key = file_key(output_file);
+        if let Some(index) = inputs.iter().position(|input| *input == key) {
+            return Err(format!(
+                "Output '{output_file}' would overwrite input '{}'",
+                input_paths[index]
+            )
+            .into());
+        }
+        if let Some(other) = outputs.insert(key, input_path) {
+            return Err(format!(
+                "'{other}' and '{input_path}' would both be written to '{output_file}'"
+            )
+            .into());
+        }
+    }
+
+    let mut documents = Vec::with_capacity(input_paths.len());
+    for input_path in input_paths {
+        let document = lopdf::Document::load(input_path)
+            .map_err(|e| format!("Failed to load PDF '{input_path}': {e}"))?;
+        if document.get_pages().is_empty() {
+            return Err(format!("PDF '{input_path}' has no pages").into());
+        }
+        documents.push(document);
+    }
+
+    // Every file is stamped before the first one is saved
+    let mut next_number = options.start_number.unwrap_or(1) as u64;
+    let mut files = Vec::with_capacity(input_paths.len());
+    for ((input_path, output_file), document) in
+        input_paths.iter().zip(output_files).zip(&mut documents)
+    {
+        let pages = document.get_pages();
+        let first_number = next_number;
+        for page_id in pages.values() {
+            let label = format_bates_number(prefix, next_number, digits, suffix);
+            let [llx, lly, urx, ury] = page_box(document, *page_id);
+            let width = text_width(&label, font_size);
+
+            let (vertical, horizontal) = position.split_once('-').unwrap_or(("bottom", "right"));
+            let x = match horizontal {
+                "left" => llx + margin,
+                "center" => (llx + urx - width) / 2.0,
+                _ => urx - margin - width,
+            };
+            let y = match vertical {
+                "top" => ury - margin - font_size,
+                _ => lly + margin,
+            };
+
+            let placement = TextPlacement { text: label, x, y };
+            stamp_text(document, *page_id, &[placement], font_size, color)?;
+            next_number += 1;
+        }
+
+        files.push(BatesEntry {
+            input_file: input_path.clone(),
+            output_file,
+            page_count: pages.len() as u32,
+            first_number: format_bates_number(prefix, first_number, digits, suffix),
+            last_number: format_bates_number(prefix, next_number - 1, digits, suffix),
+        });
+    }
+
+    for (document, entry) in documents.iter_mut().zip(&files) {
+        document.save(&entry.output_file)?;
+    }
+
+    Ok(BatesManifest { files, next_number })
+}
+
+fn output_path(input_path: &str, options: &BatesOptions) -> String {
+    let input = Path::new(input_path);
+    let stem = input
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "document".to_string());
+    let file_name = format!(
+        "{stem}{}.pdf",
+        options.output_suffix.as_deref().unwrap_or("_bates")
+    );
+
+    match &options.output_dir {
+        Some(dir) => Path::new(dir).join(file_name),
+        None => input.with_file_name(file_name),
+    }
+    .to_string_lossy()
+    .to_string()
+}
+
+/// Identifies a file by its absolute path, resolving symbolic links when the
+/// file exists.
+pub(crate) fn file_key(path: &str) -> PathBuf {
+    std::fs::canonicalize(path)
+        .or_else(|_| std::path::absolute(path))
+        .unwrap_or_else(|_| PathBuf::from(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_pdf;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_format_bates_number() {
+        assert_eq!(format_bates_number("ACME", 42, 6, ""), "ACME000042");
+        assert_eq!(format_bates_number("", 1234567, 4, "-C"), "1234567-C");
+    }
+
+    #[test]
+    fn test_numbers_continue_across_files() {
+        let dir = TempDir::new().unwrap();
+        let mut inputs = Vec::new();
+        for (name, pages) in [("a.pdf", 2), ("b.pdf", 3)] {
+            let path = dir.path().join(name).to_string_lossy().to_string();
+            create_test_pdf(pages, "test", "content")
+                .unwrap()
+                .save(&path)
+                .unwrap();
+            inputs.push(path);
+        }
+
+        let options = BatesOptions {
+            prefix: Some("ACME".to_string()),
+            start_number: Some(100),
+            ..Default::default()
+        };
+        let manifest = stamp_bates_numbers(&inputs, &options).unwrap();
+
+        assert_eq!(manifest.files[0].first_number, "ACME000100");
+        assert_eq!(manifest.files[0].last_number, "ACME000101");
+        assert_eq!(manifest.files[1].first_number, "ACME000102");
+        assert_eq!(manifest.files[1].last_number, "ACME000104");
+        assert_eq!(manifest.next_number, 105);
+
+        let stamped = lopdf::Document::load(&manifest.files[1].output_file).unwrap();
+        let last_page = stamped.get_pages()[&3];
+        let content = stamped.get_page_content(last_page).unwrap();
+        assert!(String::from_utf8_lossy(&content).contains("(ACME000104) Tj"));
+    }
+
+    #[test]
+    fn test_rejects_outputs_that_clash() {
+        let dir = TempDir::new().unwrap();
+        let mut inputs = Vec::new();
+        for folder in ["first", "second"] {
+            std::fs::create_dir(dir.path().join(folder)).unwrap();
+            let path = dir
+                .path()
+                .join(folder)
+                .join("exhibit.pdf")
+                .to_string_lossy()
+                .to_string();
+            create_test_pdf(1, "test", "content")
+                .unwrap()
+                .save(&path)
+                .unwrap();
+            inputs.push(path);
+        }
+        let output_dir = dir.path().join("out").to_string_lossy().to_string();
+        std::fs::create_dir(&output_dir).unwrap();
+
+        // Same file name from two folders
+        let options = BatesOptions {
+            output_dir: Some(output_dir.clone()),
+            ..Default::default()
+        };
+        let error = stamp_bates_numbers(&inputs, &options)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("would both be written"), "{error}");
+        assert_eq!(std::fs::read_dir(&output_dir).unwrap().count(), 0);
+
+        // An empty suffix writes over the input
+        let before = std::fs::read(&inputs[0]).unwrap();
+        let options = BatesOptions {
+            output_suffix: Some(String::new()),
+            ..Default::default()
+        };
+        let error = stamp_bates_numbers(&inputs[..1], &options)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("would overwrite input"), "{error}");
+        assert_eq!(std::fs::read(&inputs[0]).unwrap(), before);
+    }
+
+    #[test]
+    fn test_writes_nothing_when_a_later_input_fails() {
+        let dir = TempDir::new().unwrap();
+        let good = dir.path().join("good.pdf").to_string_lossy().to_string();
+        create_test_pdf(2, "test", "content")
+            .unwrap()
+            .save(&good)
+            .unwrap();
+        let broken = dir.path().join("broken.pdf").to_string_lossy().to_string();
+        std::fs::write(&broken, b"not a pdf").unwrap();
+
+        let error = stamp_bates_numbers(&[good, broken], &BatesOptions::default())
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("broken.pdf"), "{error}");
+        assert!(!dir.path().join("good_bates.pdf").exists());
+    }
+}
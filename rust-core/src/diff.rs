@@ -0,0 +1,780 @@
+use lopdf::content::{Content, Operation};
+use lopdf::{Document, Object, ObjectId};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+
+use crate::info::{field_attribute, form_fields, object_text};
+use crate::inspect::{reference, string_syntax};
+use crate::merge::{resolve_array, resolve_dictionary};
+use crate::metadata::read_metadata;
+use crate::revisions::load_revision;
+
+/// Operator changes listed per page by default.
+const DEFAULT_MAX_OPERATOR_CHANGES: u32 = 100;
+
+/// Largest page alignment table built before falling back to comparing
+/// pages by position.
+const MAX_ALIGNMENT_CELLS: usize = 4_000_000;
+
+/// Largest run of changed pages (old times new) paired by shared operators.
+const MAX_GAP_CELLS: usize = 2_500;
+
+#[derive(Debug, Clone, Default)]
+#[napi(object)]
+pub struct DiffOptions {
+    pub old_revision: Option<u32>, // Revision of the old file, 1 being the original (default: latest)
+    pub new_revision: Option<u32>, // Revision of the new file (default: latest)
+    pub max_operator_changes: Option<u32>, // Operator changes listed per page (default: 100, 0 for no limit)
+    pub compare_objects: Option<bool>, // Report added, removed and modified objects (default: true)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Change {
+    pub kind: String,
+    pub page: Option<u32>, // Page in the new document; in the old one for removed pages
+    pub object: Option<String>, // e.g. "12 0 R"
+    pub name: Option<String>, // Metadata key, form field name or annotation subtype
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PdfDiff {
+    pub old_file: String,
+    pub new_file: String,
+    pub old_page_count: u32,
+    pub new_page_count: u32,
+    pub identical: bool,
+    pub truncated: bool, // Some pages had more operator changes than were listed
+    pub changes: Vec<Change>,
+}
+
+impl Change {
+    fn new(kind: &str) -> Self {
+        Change {
+            kind: kind.to_string(),
+            page: None,
+            object: None,
+            name: None,
+            old: None,
+            new: None,
+        }
+    }
+}
+
+/// Compares the structure of two PDFs without rendering them.
+///
+/// Pages are aligned by their content, so inserting or deleting a page is
+/// reported as `page_added`/`page_removed` rather than as a change to every
+/// following page. For aligned pages whose content differs, the operators
+/// only in one version are listed as `operator_added`/`operator_removed`
+/// (in order, after skipping the operators both share at the start and end).
+/// Annotations are matched by subtype, rectangle and contents. Also reported
+/// are `metadata_changed`, `form_value_changed`, `form_field_added`,
+/// `form_field_removed` and, by object number, `object_added`,
+/// `object_removed` and `object_modified`; object numbers are only
+/// meaningful between versions of the same file.
+///
+/// Both paths may name the same file to compare two of its revisions.
+pub fn diff_pdfs(
+    old_path: &str,
+    new_path: &str,
+    options: &DiffOptions,
+) -> Result<PdfDiff, Box<dyn std::error::Error>> {
+    let old = load_revision(old_path, options.old_revision)?;
+    let new = load_revision(new_path, options.new_revision)?;
+    let max_operator_changes = options
+        .max_operator_changes
+        .unwrap_or(DEFAULT_MAX_OPERATOR_CHANGES) as usize;
+
+    let old_pages: Vec<ObjectId> = old.get_pages().into_values().collect();
+    let new_pages: Vec<ObjectId> = new.get_pages().into_values().collect();
+    let mut diff = PdfDiff {
+        old_file: old_path.to_string(),
+        new_file: new_path.to_string(),
+        old_page_count: old_pages.len() as u32,
+        new_page_count: new_pages.len() as u32,
+        identical: false,
+        truncated: false,
+        changes: Vec::new(),
+    };
+
+    let old_fingerprints: Vec<u64> = old_pages
+        .iter()
+        .map(|id| page_fingerprint(&old, *id))
+        .collect();
+    let new_fingerprints: Vec<u64> = new_pages
+        .iter()
+        .map(|id| page_fingerprint(&new, *id))
+        .collect();
+    let mut old_operations: Vec<Option<Vec<String>>> = vec![None; old_pages.len()];
+    let mut new_operations: Vec<Option<Vec<String>>> = vec![None; new_pages.len()];
+    let operations = |document: &Document,
+                      pages: &[ObjectId],
+                      cache: &mut Vec<Option<Vec<String>>>,
+                      index: usize| {
+        cache[index]
+            .get_or_insert_with(|| page_operations(document, pages[index]))
+            .clone()
+    };
+    let pairs = align(&old_fingerprints, &new_fingerprints, &mut |o, n| {
+        let old_page = operations(&old, &old_pages, &mut old_operations, o);
+        let new_page = operations(&new, &new_pages, &mut new_operations, n);
+        old_page.len() - operator_changes(&old_page, &new_page).0.len()
+    });
+    for pair in pairs {
+        match pair {
+            (Some(o), None) => diff.changes.push(Change {
+                page: Some(o as u32 + 1),
+                object: Some(reference(old_pages[o])),
+                ..Change::new("page_removed")
+            }),
+            (None, Some(n)) => diff.changes.push(Change {
+                page: Some(n as u32 + 1),
+                object: Some(reference(new_pages[n])),
+                ..Change::new("page_added")
+            }),
+            (Some(o), Some(n)) => {
+                let page = n as u32 + 1;
+                if old_fingerprints[o] != new_fingerprints[n] {
+                    let (removed, added) = operator_changes(
+                        &operations(&old, &old_pages, &mut old_operations, o),
+                        &operations(&new, &new_pages, &mut new_operations, n),
+                    );
+                    let mut listed = 0;
+                    for (kind, operations, is_new) in [
+                        ("operator_removed", removed, false),
+                        ("operator_added", added, true),
+                    ] {
+                        for operation in operations {
+                            if max_operator_changes > 0 && listed == max_operator_changes {
+                                diff.truncated = true;
+                                break;
+                            }
+                            listed += 1;
+                            let mut change = Change {
+                                page: Some(page),
+                                ..Change::new(kind)
+                            };
+                            if is_new {
+                                change.new = Some(operation);
+                            } else {
+                                change.old = Some(operation);
+                            }
+                            diff.changes.push(change);
+                        }
+                    }
+                }
+                diff_annotations(&old, old_pages[o], &new, new_pages[n], page, &mut diff);
+            }
+            (None, None) => {}
+        }
+    }
+
+    diff_metadata(&old, &new, &mut diff);
+    diff_form_values(&old, &new, &mut diff);
+    if options.compare_objects.unwrap_or(true) {
+        diff.changes.extend(object_changes(&old, &new));
+    }
+    diff.identical = diff.changes.is_empty();
+    Ok(diff)
+}
+
+fn page_fingerprint(document: &Document, page_id: ObjectId) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    document
+        .get_page_content(page_id)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Pairs up old and new pages: pages with identical content along the
+/// longest common sequence, then the remaining pages between them (see
+/// `pair_gap`). Unpaired pages were added or removed.
+fn align(
+    old: &[u64],
+    new: &[u64],
+    shared_operations: &mut impl FnMut(usize, usize) -> usize,
+) -> Vec<(Option<usize>, Option<usize>)> {
+    let mut matches = Vec::new();
+    if old.len() * new.len() <= MAX_ALIGNMENT_CELLS {
+        // lengths[i][j]: longest common sequence of old[i..] and new[j..]
+        let width = new.len() + 1;
+        let mut lengths = vec![0u32; (old.len() + 1) * width];
+        for i in (0..old.len()).rev() {
+            for j in (0..new.len()).rev() {
+                lengths[i * width + j] = if old[i] == new[j] {
+                    lengths[(i + 1) * width + j + 1] + 1
+                } else {
+                    lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < old.len() && j < new.len() {
+            if old[i] == new[j] {
+                matches.push((i, j));
+                i += 1;
+                j += 1;
+            } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+    }
+    matches.push((old.len(), new.len()));
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for (next_i, next_j) in matches {
+        pair_gap(i..next_i, j..next_j, shared_operations, &mut pairs);
+        if next_i < old.len() {
+            pairs.push((Some(next_i), Some(next_j)));
+        }
+        (i, j) = (next_i + 1, next_j + 1);
+    }
+    pairs
+}
+
+/// Pairs the changed pages between two identical ones. With the same number
+/// on each side they are paired by position; otherwise the pairing keeps
+/// the most operators in common, so a deleted page next to an edited one is
+/// told apart from it.
+fn pair_gap(
+    old: Range<usize>,
+    new: Range<usize>,
+    shared_operations: &mut impl FnMut(usize, usize) -> usize,
+    pairs: &mut Vec<(Option<usize>, Option<usize>)>,
+) {
+    let (rows, columns) = (old.len(), new.len());
+    if rows == columns || rows * columns > MAX_GAP_CELLS {
+        pairs.extend(
+            old.clone()
+                .zip(new.clone())
+                .map(|(o, n)| (Some(o), Some(n))),
+        );
+        pairs.extend(old.skip(columns).map(|o| (Some(o), None)));
+        pairs.extend(new.skip(rows).map(|n| (None, Some(n))));
+        return;
+    }
+
+    let shared: Vec<usize> = old
+        .clone()
+        .flat_map(|o| new.clone().map(move |n| (o, n)))
+        .map(|(o, n)| shared_operations(o, n))
+        .collect();
+    // best[a][b]: most operators shared by pairing old[a..] with new[b..]
+    let width = columns + 1;
+    let mut best = vec![0usize; (rows + 1) * width];
+    for a in (0..rows).rev() {
+        for b in (0..columns).rev() {
+            let mut score = best[(a + 1) * width + b].max(best[a * width + b + 1]);
+            if shared[a * columns + b] > 0 {
+                score = score.max(shared[a * columns + b] + best[(a + 1) * width + b + 1]);
+            }
+            best[a * width + b] = score;
+        }
+    }
+    let (mut a, mut b) = (0, 0);
+    while a < rows || b < columns {
+        if a < rows
+            && b < columns
+            && shared[a * columns + b] > 0
+            && best[a * width + b] == shared[a * columns + b] + best[(a + 1) * width + b + 1]
+        {
+            pairs.push((Some(old.start + a), Some(new.start + b)));
+            a += 1;
+            b += 1;
+        } else if a < rows && (b == columns || best[a * width + b] == best[(a + 1) * width + b]) {
+            pairs.push((Some(old.start + a), None));
+            a += 1;
+        } else {
+            pairs.push((None, Some(new.start + b)));
+            b += 1;
+        }
+    }
+}
+
+/// Decodes each content stream of a page on its own, since lopdf joins them
+/// without the whitespace that separates them.
+fn page_operations(document: &Document, page_id: ObjectId) -> Vec<String> {
+    document
+        .get_page_contents(page_id)
+        .into_iter()
+        .filter_map(|id| document.get_object(id).and_then(Object::as_stream).ok())
+        .filter_map(|stream| {
+            let data = stream
+                .decompressed_content()
+                .unwrap_or_else(|_| stream.content.clone());
+            Content::decode(&data).ok()
+        })
+        .flat_map(|content| content.operations)
+        .map(|operation| operation_text(&operation))
+        .collect()
+}
+
+fn operation_text(operation: &Operation) -> String {
+    let mut parts: Vec<String> = operation.operands.iter().map(operand_syntax).collect();
+    parts.push(operation.operator.clone());
+    parts.join(" ")
+}
+
+fn operand_syntax(object: &Object) -> String {
+    match object {
+        Object::Null => "null".to_string(),
+        Object::Boolean(value) => value.to_string(),
+        Object::Integer(value) => value.to_string(),
+        Object::Real(value) => value.to_string(),
+        Object::Name(name) => format!("/{}", String::from_utf8_lossy(name)),
+        Object::String(bytes, format) => string_syntax(bytes, format),
+        Object::Reference(id) => reference(*id),
+        Object::Array(items) => format!(
+            "[{}]",
+            items
+                .iter()
+                .map(operand_syntax)
+                .collect::<Vec<_>>()
+                .join(" ")
+        ),
+        Object::Dictionary(dict) => format!(
+            "<<{}>>",
+            dict.iter()
+                .map(|(key, value)| format!(
+                    " /{} {}",
+                    String::from_utf8_lossy(key),
+                    operand_syntax(value)
+                ))
+                .collect::<String>()
+                + " "
+        ),
+        Object::Stream(stream) => operand_syntax(&Object::Dictionary(stream.dict.clone())),
+    }
+}
+
+/// Returns the operators only in the old and only in the new content, after
+/// trimming the common start and end.
+fn operator_changes(old: &[String], new: &[String]) -> (Vec<String>, Vec<String>) {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old = &old[prefix..old.len() - suffix];
+    let new = &new[prefix..new.len() - suffix];
+
+    let only_in = |ours: &[String], theirs: &[String]| {
+        let mut counts: HashMap<&String, usize> = HashMap::new();
+        for operation in theirs {
+            *counts.entry(operation).or_default() += 1;
+        }
+        ours.iter()
+            .filter(|operation| match counts.get_mut(operation) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    false
+                }
+                _ => true,
+            })
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    (only_in(old, new), only_in(new, old))
+}
+
+/// Describes each annotation on a page, e.g. `Link [72 700 200 720]`, with
+/// its object if it is indirect.
+fn page_annotations(document: &Document, page_id: ObjectId) -> Vec<(String, Option<ObjectId>)> {
+    let annots = document
+        .get_dictionary(page_id)
+        .ok()
+        .and_then(|page| resolve_array(document, page.get(b"Annots").ok()));
+    annots
+        .into_iter()
+        .flatten()
+        .filter_map(|annot| {
+            let dict = resolve_dictionary(document, Some(annot))?;
+            let subtype = dict
+                .get(b"Subtype")
+                .and_then(Object::as_name)
+                .map(|name| String::from_utf8_lossy(name).to_string())
+                .unwrap_or_default();
+            let rect: Vec<String> = resolve_array(document, dict.get(b"Rect").ok())
+                .into_iter()
+                .flatten()
+                .filter_map(|value| value.as_float().ok())
+                .map(|value| format!("{}", value.round()))
+                .collect();
+            let mut description = format!("{subtype} [{}]", rect.join(" "));
+            if let Some(contents) = dict.get(b"Contents").ok().and_then(object_text) {
+                description.push_str(&format!(" \"{contents}\""));
+            }
+            Some((description, annot.as_reference().ok()))
+        })
+        .collect()
+}
+
+fn diff_annotations(
+    old: &Document,
+    old_page: ObjectId,
+    new: &Document,
+    new_page: ObjectId,
+    page: u32,
+    diff: &mut PdfDiff,
+) {
+    let old_annotations = page_annotations(old, old_page);
+    let new_annotations = page_annotations(new, new_page);
+    let descriptions = |annotations: &[(String, Option<ObjectId>)]| {
+        annotations
+            .iter()
+            .map(|(description, _)| description.clone())
+            .collect::<Vec<_>>()
+    };
+    let (removed, added) = operator_changes(
+        &descriptions(&old_annotations),
+        &descriptions(&new_annotations),
+    );
+
+    for (kind, descriptions, annotations) in [
+        ("annotation_removed", removed, &old_annotations),
+        ("annotation_added", added, &new_annotations),
+    ] {
+        for description in descriptions {
+            let object = annotations
+                .iter()
+                .find(|(candidate, _)| *candidate == description)
+                .and_then(|(_, id)| *id);
+            let mut change = Change {
+                page: Some(page),
+                object: object.map(reference),
+                name: description.split(' ').next().map(str::to_string),
+                ..Change::new(kind)
+            };
+            if kind == "annotation_added" {
+                change.new = Some(description);
+            } else {
+                change.old = Some(description);
+            }
+            diff.changes.push(change);
+        }
+    }
+}
+
+/// Flattens the metadata to `title`, `author`, ... and `custom.<key>`.
+fn metadata_fields(document: &Document) -> BTreeMap<String, String> {
+    let mut fields = BTreeMap::new();
+    let Ok(serde_json::Value::Object(metadata)) = serde_json::to_value(read_metadata(document))
+    else {
+        return fields;
+    };
+    for (key, value) in metadata {
+        match value {
+            serde_json::Value::String(text) => {
+                fields.insert(key, text);
+            }
+            serde_json::Value::Object(custom) => {
+                for (custom_key, custom_value) in custom {
+                    if let serde_json::Value::String(text) = custom_value {
+                        fields.insert(format!("{key}.{custom_key}"), text);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    fields
+}
+
+fn diff_metadata(old: &Document, new: &Document, diff: &mut PdfDiff) {
+    let old_fields = metadata_fields(old);
+    let new_fields = metadata_fields(new);
+    for (name, old_value, new_value) in changed_entries(&old_fields, &new_fields) {
+        diff.changes.push(Change {
+            name: Some(name),
+            old: old_value,
+            new: new_value,
+            ..Change::new("metadata_changed")
+        });
+    }
+}
+
+/// Maps fully qualified field names (`parent.child`) to each field and its
+/// value.
+fn form_values(document: &Document) -> BTreeMap<String, (ObjectId, Option<String>)> {
+    form_fields(document)
+        .into_iter()
+        .map(|id| {
+            let mut parts = Vec::new();
+            let mut node_id = Some(id);
+            while let Some(node) = node_id.and_then(|id| document.get_dictionary(id).ok()) {
+                if let Some(name) = node.get(b"T").ok().and_then(object_text) {
+                    parts.push(name);
+                }
+                node_id = node.get(b"Parent").and_then(Object::as_reference).ok();
+                if parts.len() > 32 {
+                    break;
+                }
+            }
+            parts.reverse();
+            (parts.join("."), (id, field_value(document, id)))
+        })
+        .collect()
+}
+
+fn field_value(document: &Document, field_id: ObjectId) -> Option<String> {
+    let value = document
+        .dereference(field_attribute(document, field_id, b"V")?)
+        .ok()?
+        .1;
+    match value {
+        Object::Array(items) => Some(
+            items
+                .iter()
+                .filter_map(object_text)
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        // Signature values are dictionaries
+        Object::Dictionary(dict) if dict.has(b"ByteRange") => Some("signed".to_string()),
+        _ => object_text(value),
+    }
+}
+
+fn diff_form_values(old: &Document, new: &Document, diff: &mut PdfDiff) {
+    let old_values = form_values(old);
+    let new_values = form_values(new);
+    for (name, (old_id, old_value)) in &old_values {
+        match new_values.get(name) {
+            None => diff.changes.push(Change {
+                object: Some(reference(*old_id)),
+                name: Some(name.clone()),
+                old: old_value.clone(),
+                ..Change::new("form_field_removed")
+            }),
+            Some((new_id, new_value)) if new_value != old_value => diff.changes.push(Change {
+                object: Some(reference(*new_id)),
+                name: Some(name.clone()),
+                old: old_value.clone(),
+                new: new_value.clone(),
+                ..Change::new("form_value_changed")
+            }),
+            Some(_) => {}
+        }
+    }
+    for (name, (new_id, new_value)) in &new_values {
+        if !old_values.contains_key(name) {
+            diff.changes.push(Change {
+                object: Some(reference(*new_id)),
+                name: Some(name.clone()),
+                new: new_value.clone(),
+                ..Change::new("form_field_added")
+            });
+        }
+    }
+}
+
+/// Lists the keys whose values differ, with the old and new value of each.
+fn changed_entries(
+    old: &BTreeMap<String, String>,
+    new: &BTreeMap<String, String>,
+) -> Vec<(String, Option<String>, Option<String>)> {
+    let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    keys.into_iter()
+        .filter(|key| old.get(*key) != new.get(*key))
+        .map(|key| (key.clone(), old.get(key).cloned(), new.get(key).cloned()))
+        .collect()
+}
+
+/// Lists the objects added, removed or modified between two documents.
+pub(crate) fn object_changes(old: &Document, new: &Document) -> Vec<Change> {
+    let mut changes = Vec::new();
+    // Cross-reference and object streams are containers, not content
+    let is_container = |object: &Object| {
+        object.as_stream().is_ok_and(|stream| {
+            stream
+                .dict
+                .get(b"Type")
+                .and_then(Object::as_name)
+                .is_ok_and(|name| name == b"XRef" || name == b"ObjStm")
+        })
+    };
+    for (id, object) in &old.objects {
+        if is_container(object) {
+            continue;
+        }
+        match new.objects.get(id) {
+            None => changes.push(Change {
+                object: Some(reference(*id)),
+                ..Change::new("object_removed")
+            }),
+            Some(new_object) if !same_object(object, new_object) => changes.push(Change {
+                object: Some(reference(*id)),
+                ..Change::new("object_modified")
+            }),
+            Some(_) => {}
+        }
+    }
+    for (id, object) in &new.objects {
+        if !old.objects.contains_key(id) && !is_container(object) {
+            changes.push(Change {
+                object: Some(reference(*id)),
+                ..Change::new("object_added")
+            });
+        }
+    }
+    changes
+}
+
+/// Compares objects, ignoring where a stream's data sat in the file.
+fn same_object(old: &Object, new: &Object) -> bool {
+    match (old, new) {
+        (Object::Stream(old), Object::Stream(new)) => {
+            old.dict == new.dict && old.content == new.content
+        }
+        _ => old == new,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::revisions::revision_ends;
+    use crate::test_utils::create_test_pdf;
+    use lopdf::{Dictionary, IncrementalDocument, Stream};
+    use tempfile::TempDir;
+
+    fn kinds(diff: &PdfDiff, kind: &str) -> Vec<Change> {
+        diff.changes
+            .iter()
+            .filter(|change| change.kind == kind)
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_pages_content_and_annotations() {
+        let dir = TempDir::new().unwrap();
+        let old_path = dir.path().join("old.pdf").to_string_lossy().to_string();
+        let new_path = dir.path().join("new.pdf").to_string_lossy().to_string();
+        let mut document = create_test_pdf(3, "Diff", "text").unwrap();
+        document.save(&old_path).unwrap();
+
+        // Drop page 2, stamp page 3 and add a link to page 1
+        document.delete_pages(&[2]);
+        let pages = document.get_pages();
+        let stamp_id = document.add_object(Stream::new(Dictionary::new(), b"q 0.5 g Q".to_vec()));
+        crate::sign::append_page_content(&mut document, pages[&2], stamp_id);
+        let mut link = Dictionary::new();
+        link.set("Type", Object::Name(b"Annot".to_vec()));
+        link.set("Subtype", Object::Name(b"Link".to_vec()));
+        link.set(
+            "Rect",
+            Object::Array(vec![72.into(), 700.into(), 200.into(), 720.into()]),
+        );
+        let link_id = document.add_object(link);
+        document
+            .get_dictionary_mut(pages[&1])
+            .unwrap()
+            .set("Annots", Object::Array(vec![Object::Reference(link_id)]));
+        document.save(&new_path).unwrap();
+
+        let diff = diff_pdfs(&old_path, &new_path, &DiffOptions::default()).unwrap();
+        assert!(!diff.identical);
+        assert_eq!((diff.old_page_count, diff.new_page_count), (3, 2));
+
+        let removed = kinds(&diff, "page_removed");
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].page, Some(2));
+        assert!(kinds(&diff, "page_added").is_empty());
+
+        let added: Vec<Option<String>> = kinds(&diff, "operator_added")
+            .into_iter()
+            .map(|change| {
+                assert_eq!(change.page, Some(2));
+                change.new
+            })
+            .collect();
+        assert_eq!(
+            added,
+            ["q", "0.5 g", "Q"].map(|text| Some(text.to_string()))
+        );
+        assert!(kinds(&diff, "operator_removed").is_empty());
+
+        let annotations = kinds(&diff, "annotation_added");
+        assert_eq!(annotations.len(), 1);
+        assert_eq!(annotations[0].page, Some(1));
+        assert_eq!(annotations[0].name.as_deref(), Some("Link"));
+        assert_eq!(annotations[0].new.as_deref(), Some("Link [72 700 200 720]"));
+        assert_eq!(annotations[0].object, Some(reference(link_id)));
+        assert!(kinds(&diff, "object_added")
+            .iter()
+            .any(|change| change.object == Some(reference(link_id))));
+
+        let same = diff_pdfs(&old_path, &old_path, &DiffOptions::default()).unwrap();
+        assert!(same.identical);
+    }
+
+    #[test]
+    fn test_diff_revisions_of_incremental_update() {
+        let dir = TempDir::new().unwrap();
+        let base_path = dir.path().join("base.pdf").to_string_lossy().to_string();
+        let updated_path = dir.path().join("updated.pdf").to_string_lossy().to_string();
+        let mut document = create_test_pdf(1, "Revision", "text").unwrap();
+        let mut info = Dictionary::new();
+        info.set("Title", Object::string_literal("Draft"));
+        let info_id = document.add_object(info);
+        document.trailer.set("Info", Object::Reference(info_id));
+        document.save(&base_path).unwrap();
+
+        // Append an update retitling the document and rewriting page 1
+        let mut incremental = IncrementalDocument::load(&base_path).unwrap();
+        let page_id = incremental.get_prev_documents().get_pages()[&1];
+        let content_id = incremental.get_prev_documents().get_page_contents(page_id)[0];
+        incremental.new_document.set_object(
+            content_id,
+            Stream::new(Dictionary::new(), b"BT ET".to_vec()),
+        );
+        let mut info = Dictionary::new();
+        info.set("Title", Object::string_literal("Final"));
+        incremental.new_document.set_object(info_id, info);
+        incremental.save(&updated_path).unwrap();
+
+        let bytes = std::fs::read(&updated_path).unwrap();
+        assert_eq!(revision_ends(&bytes).len(), 2);
+        let options = DiffOptions {
+            old_revision: Some(1),
+            ..Default::default()
+        };
+        let diff = diff_pdfs(&updated_path, &updated_path, &options).unwrap();
+
+        let title = kinds(&diff, "metadata_changed");
+        assert_eq!(title.len(), 1);
+        assert_eq!(title[0].name.as_deref(), Some("title"));
+        assert_eq!(title[0].old.as_deref(), Some("Draft"));
+        assert_eq!(title[0].new.as_deref(), Some("Final"));
+        assert!(kinds(&diff, "operator_removed")
+            .iter()
+            .any(|change| change.old.as_deref() == Some("/F1 12 Tf")));
+        let modified: Vec<Option<String>> = kinds(&diff, "object_modified")
+            .into_iter()
+            .map(|change| change.object)
+            .collect();
+        assert!(modified.contains(&Some(reference(content_id))));
+        assert!(modified.contains(&Some(reference(info_id))));
+
+        let options = DiffOptions {
+            old_revision: Some(3),
+            ..Default::default()
+        };
+        assert!(diff_pdfs(&updated_path, &updated_path, &options).is_err());
+    }
+}
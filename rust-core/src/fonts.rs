@@ -0,0 +1,243 @@
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::merge::{resolve_array, resolve_dictionary};
+use crate::sign::page_resource_dictionaries;
+
+/// Fonts every conforming viewer must supply; they are often left unembedded.
+const STANDARD_FONTS: [&str; 14] = [
+    "Courier",
+    "Courier-Bold",
+    "Courier-BoldOblique",
+    "Courier-Oblique",
+    "Helvetica",
+    "Helvetica-Bold",
+    "Helvetica-BoldOblique",
+    "Helvetica-Oblique",
+    "Times-Roman",
+    "Times-Bold",
+    "Times-BoldItalic",
+    "Times-Italic",
+    "Symbol",
+    "ZapfDingbats",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FontInfo {
+    pub resource_name: String, // Name the content stream uses, e.g. "F1"
+    pub name: String,          // BaseFont without the subset prefix
+    pub subtype: String,       // Type1, MMType1, TrueType, Type0 or Type3
+    pub embedded: bool,
+    pub subset: bool,
+    pub standard: bool, // One of the standard 14 fonts
+    pub encoding: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PageFonts {
+    pub page: u32,
+    pub fonts: Vec<FontInfo>,
+}
+
+/// Lists the fonts each page uses, including those used by Form XObjects
+/// and annotation appearances on the page.
+///
+/// Type3 fonts define their glyphs in the file and always count as embedded.
+pub fn list_fonts(file_path: &str) -> Result<Vec<PageFonts>, Box<dyn std::error::Error>> {
+    let document = Document::load(file_path)?;
+
+    let mut pages = Vec::new();
+    for (number, page_id) in document.get_pages() {
+        let mut seen: HashSet<ObjectId> = HashSet::new();
+        let mut fonts = Vec::new();
+        for resources in page_resource_dictionaries(&document, page_id) {
+            let Some(entries) = resolve_dictionary(&document, resources.get(b"Font").ok()) else {
+                continue;
+            };
+            for (resource_name, font) in entries.iter() {
+                // The same font object reached through several forms is listed once
+                if let Ok(id) = font.as_reference() {
+                    if !seen.insert(id) {
+                        continue;
+                    }
+                }
+                if let Some(font) = resolve_dictionary(&document, Some(font)) {
+                    fonts.push(font_info(&document, resource_name, font));
+                }
+            }
+        }
+        pages.push(PageFonts {
+            page: number,
+            fonts,
+        });
+    }
+    Ok(pages)
+}
+
+fn font_info(document: &Document, resource_name: &[u8], font: &Dictionary) -> FontInfo {
+    let name = |key: &[u8]| {
+        font.get(key)
+            .and_then(Object::as_name)
+            .ok()
+            .map(|name| String::from_utf8_lossy(name).to_string())
+    };
+    let subtype = name(b"Subtype").unwrap_or_default();
+    let base_font = name(b"BaseFont").unwrap_or_default();
+
+    // Subset fonts carry a six-letter tag, e.g. "ABCDEF+Helvetica"
+    let subset = base_font.len() > 7
+        && base_font.as_bytes()[6] == b'+'
+        && base_font.as_bytes()[..6].iter().all(u8::is_ascii_uppercase);
+    let display_name = if subset {
+        base_font[7..].to_string()
+    } else {
+        base_font
+    };
+
+    // Composite fonts keep their descriptor on the descendant CIDFont
+    let descriptor_owner = if subtype == "Type0" {
+        resolve_array(document, font.get(b"DescendantFonts").ok())
+            .and_then(|descendants| descendants.first())
+            .and_then(|descendant| resolve_dictionary(document, Some(descendant)))
+    } else {
+        Some(font)
+    };
+    let embedded = subtype == "Type3"
+        || descriptor_owner
+            .and_then(|owner| resolve_dictionary(document, owner.get(b"FontDescriptor").ok()))
+            .is_some_and(|descriptor| {
+                [b"FontFile".as_slice(), b"FontFile2", b"FontFile3"]
+                    .iter()
+                    .any(|key| descriptor.has(key))
+            });
+
+    FontInfo {
+        resource_name: String::from_utf8_lossy(resource_name).to_string(),
+        standard: !embedded && STANDARD_FONTS.contains(&display_name.as_str()),
+        name: display_name,
+        subtype,
+        embedded,
+        subset,
+        encoding: font_encoding(document, font),
+    }
+}
+
+/// Describes a font's `/Encoding`: a predefined encoding or CMap name, the
+/// base encoding of a differences dictionary, or the name of an embedded CMap.
+fn font_encoding(document: &Document, font: &Dictionary) -> Option<String> {
+    let encoding = document.dereference(font.get(b"Encoding").ok()?).ok()?.1;
+    let text = |name: &[u8]| String::from_utf8_lossy(name).to_string();
+    match encoding {
+        Object::Name(name) => Some(text(name)),
+        Object::Dictionary(dict) => Some(
+            dict.get(b"BaseEncoding")
+                .and_then(Object::as_name)
+                .map(|base| format!("{} with differences", text(base)))
+                .unwrap_or_else(|_| "Custom".to_string()),
+        ),
+        Object::Stream(cmap) => Some(
+            cmap.dict
+                .get(b"CMapName")
+                .and_then(Object::as_name)
+                .map(text)
+                .unwrap_or_else(|_| "Embedded CMap".to_string()),
+        ),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_pdf;
+    use lopdf::Stream;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_list_fonts_reports_embedding() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("fonts.pdf").to_string_lossy().to_string();
+        let mut document = create_test_pdf(2, "Fonts", "text").unwrap();
+
+        // Page 2 also draws a form that uses an embedded subset TrueType font
+        let font_file = document.add_object(Stream::new(Dictionary::new(), vec![0; 16]));
+        let mut descriptor = Dictionary::new();
+        descriptor.set("Type", Object::Name(b"FontDescriptor".to_vec()));
+        descriptor.set("FontFile2", Object::Reference(font_file));
+        let descriptor_id = document.add_object(descriptor);
+        let mut font = Dictionary::new();
+        font.set("Type", Object::Name(b"Font".to_vec()));
+        font.set("Subtype", Object::Name(b"TrueType".to_vec()));
+        font.set("BaseFont", Object::Name(b"ABCDEF+Arial".to_vec()));
+        font.set("FontDescriptor", Object::Reference(descriptor_id));
+        font.set("Encoding", Object::Name(b"WinAnsiEncoding".to_vec()));
+        let font_id = document.add_object(font);
+
+        let mut form_fonts = Dictionary::new();
+        form_fonts.set("TT0", Object::Reference(font_id));
+        let mut form_resources = Dictionary::new();
+        form_resources.set("Font", Object::Dictionary(form_fonts));
+        let mut form_dict = Dictionary::new();
+        form_dict.set("Type", Object::Name(b"XObject".to_vec()));
+        form_dict.set("Subtype", Object::Name(b"Form".to_vec()));
+        form_dict.set("Resources", Object::Dictionary(form_resources));
+        let form_id = document.add_object(Stream::new(form_dict, b"BT ET".to_vec()));
+        let page_id = document.get_pages()[&2];
+        crate::sign::add_page_resource(
+            &mut document,
+            page_id,
+            "XObject",
+            "Fm0",
+            Object::Reference(form_id),
+        )
+        .unwrap();
+        document.save(&path).unwrap();
+
+        let pages = list_fonts(&path).unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].fonts.len(), 1);
+        let helvetica = &pages[0].fonts[0];
+        assert_eq!(helvetica.resource_name, "F1");
+        assert_eq!(helvetica.name, "Helvetica");
+        assert_eq!(helvetica.subtype, "Type1");
+        assert!(!helvetica.embedded);
+        assert!(helvetica.standard);
+        assert_eq!(helvetica.encoding, None);
+
+        assert_eq!(pages[1].fonts.len(), 2);
+        let arial = pages[1]
+            .fonts
+            .iter()
+            .find(|font| font.resource_name == "TT0")
+            .unwrap();
+        assert_eq!(arial.name, "Arial");
+        assert!(arial.embedded);
+        assert!(arial.subset);
+        assert!(!arial.standard);
+        assert_eq!(arial.encoding.as_deref(), Some("WinAnsiEncoding"));
+    }
+
+    #[test]
+    fn test_composite_font_encoding() {
+        let mut document = Document::with_version("1.7");
+        let mut descendant = Dictionary::new();
+        descendant.set("Subtype", Object::Name(b"CIDFontType2".to_vec()));
+        let descendant_id = document.add_object(descendant);
+        let mut font = Dictionary::new();
+        font.set("Subtype", Object::Name(b"Type0".to_vec()));
+        font.set("BaseFont", Object::Name(b"NotoSans".to_vec()));
+        font.set("Encoding", Object::Name(b"Identity-H".to_vec()));
+        font.set(
+            "DescendantFonts",
+            Object::Array(vec![Object::Reference(descendant_id)]),
+        );
+
+        let info = font_info(&document, b"F0", &font);
+        assert_eq!(info.subtype, "Type0");
+        assert_eq!(info.encoding.as_deref(), Some("Identity-H"));
+        assert!(!info.embedded);
+        assert!(!info.subset);
+        assert!(!info.standard);
+    }
+}
@@ -0,0 +1,560 @@
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::merge::{file_stem, resolve_dictionary};
+use crate::page_range::selected_page_numbers;
+use crate::sign::page_resource_dictionaries;
+use crate::split::sanitize_file_name;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageInfo {
+    pub name: String, // Resource name, e.g. "Im0"
+    pub object_id: u32,
+    pub width: u32,
+    pub height: u32,
+    pub bits_per_component: Option<u32>, // Absent for JPX images that carry their own
+    pub color_space: Option<String>,     // Family, e.g. "DeviceRGB" or "ICCBased"
+    pub components: Option<u32>,
+    pub filters: Vec<String>,
+    pub byte_size: u64, // Encoded size in the file
+    pub image_mask: bool,
+    pub soft_mask: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PageImages {
+    pub page: u32,
+    pub images: Vec<ImageInfo>,
+}
+
+/// One image written by [`extract_images`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExtractedImage {
+    pub page: u32, // First page the image appears on
+    pub name: String,
+    pub output_file: String,
+    pub format: String, // "jpg", "jp2" or "png"
+}
+
+/// An image [`extract_images`] could not convert.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SkippedImage {
+    pub page: u32,
+    pub name: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImageExtraction {
+    pub images: Vec<ExtractedImage>,
+    pub skipped: Vec<SkippedImage>,
+}
+
+/// Lists the image XObjects each page uses, including images drawn through
+/// Form XObjects and annotation appearances. Inline images are not listed.
+pub fn list_images(file_path: &str) -> Result<Vec<PageImages>, Box<dyn std::error::Error>> {
+    let document = Document::load(file_path)?;
+    Ok(document
+        .get_pages()
+        .into_iter()
+        .map(|(page, page_id)| PageImages {
+            page,
+            images: page_images(&document, page_id)
+                .into_iter()
+                .map(|(name, id, stream)| image_info(&document, name, id, stream))
+                .collect(),
+        })
+        .collect())
+}
+
+/// Writes the images used on the selected pages to `output_dir`, each image
+/// once, as `{name}_p{page}_{object}_{resource}.{ext}`. The object number
+/// keeps images apart whose resource names clash, and characters that are
+/// not safe in file names are replaced in the resource name.
+///
+/// DCT images are written as-is as JPEG and JPX images as JPEG 2000. Other
+/// images are decoded and written as PNG when their color space maps onto
+/// PNG (gray, RGB, or indexed over either); CMYK, Lab and other color spaces
+/// are reported as skipped, as are filters that cannot be decoded.
+pub fn extract_images(
+    input_path: &str,
+    output_dir: &str,
+    page_range: Option<&str>,
+) -> Result<ImageExtraction, Box<dyn std::error::Error>> {
+    let document = Document::load(input_path)?;
+    let page_ids = document.get_pages();
+    let mut numbers = selected_page_numbers(page_ids.len() as u32, page_range, None)?;
+    numbers.sort_unstable();
+    std::fs::create_dir_all(output_dir)?;
+
+    let stem = file_stem(input_path);
+    let mut written = HashSet::new();
+    let mut extraction = ImageExtraction {
+        images: Vec::new(),
+        skipped: Vec::new(),
+    };
+    for page in numbers {
+        let Some(page_id) = page_ids.get(&page) else {
+            continue;
+        };
+        for (name, id, stream) in page_images(&document, *page_id) {
+            if !written.insert(id) {
+                continue;
+            }
+            let name = String::from_utf8_lossy(&name).to_string();
+            match encode_image(&document, stream) {
+                Ok((format, data)) => {
+                    let file_name = format!(
+                        "{stem}_p{page}_{}_{}.{format}",
+                        id.0,
+                        sanitize_file_name(&name)
+                    );
+                    let output_file = Path::new(output_dir)
+                        .join(file_name)
+                        .to_string_lossy()
+                        .to_string();
+                    std::fs::write(&output_file, data)?;
+                    extraction.images.push(ExtractedImage {
+                        page,
+                        name,
+                        output_file,
+                        format: format.to_string(),
+                    });
+                }
+                Err(reason) => extraction.skipped.push(SkippedImage { page, name, reason }),
+            }
+        }
+    }
+    Ok(extraction)
+}
+
+/// Returns the image XObjects reachable from a page's resources, each image
+/// object once.
+fn page_images(document: &Document, page_id: ObjectId) -> Vec<(Vec<u8>, ObjectId, &Stream)> {
+    let mut seen = HashSet::new();
+    let mut images = Vec::new();
+    for resources in page_resource_dictionaries(document, page_id) {
+        let Some(xobjects) = resolve_dictionary(document, resources.get(b"XObject").ok()) else {
+            continue;
+        };
+        for (name, xobject) in xobjects.iter() {
+            let Ok(id) = xobject.as_reference() else {
+                continue;
+            };
+            let Ok(stream) = document.get_object(id).and_then(Object::as_stream) else {
+                continue;
+            };
+            if stream.dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Image")
+                && seen.insert(id)
+            {
+                images.push((name.clone(), id, stream));
+            }
+        }
+    }
+    images
+}
+
+fn image_info(document: &Document, name: Vec<u8>, id: ObjectId, stream: &Stream) -> ImageInfo {
+    let dict = &stream.dict;
+    let integer = |key: &[u8]| {
+        dict.get(key)
+            .and_then(|value| document.dereference(value))
+            .and_then(|(_, value)| value.as_i64())
+            .ok()
+    };
+    let image_mask = dict
+        .get(b"ImageMask")
+        .and_then(Object::as_bool)
+        .unwrap_or(false);
+    let color_space = color_space(document, dict);
+
+    ImageInfo {
+        name: String::from_utf8_lossy(&name).to_string(),
+        object_id: id.0,
+        width: integer(b"Width").unwrap_or(0) as u32,
+        height: integer(b"Height").unwrap_or(0) as u32,
+        bits_per_component: integer(b"BitsPerComponent")
+            .map(|bits| bits as u32)
+            .or(image_mask.then_some(1)),
+        components: color_space.as_ref().and_then(|space| space.components),
+        color_space: color_space.map(|space| space.family),
+        filters: filters(dict),
+        byte_size: stream.content.len() as u64,
+        image_mask,
+        soft_mask: dict.has(b"SMask"),
+    }
+}
+
+struct ColorSpace {
+    family: String,
+    components: Option<u32>,
+    // For /Indexed: base color space components and the lookup table
+    palette: Option<(u32, Vec<u8>)>,
+}
+
+fn color_space(document: &Document, dict: &Dictionary) -> Option<ColorSpace> {
+    let space = document.dereference(dict.get(b"ColorSpace").ok()?).ok()?.1;
+    resolve_color_space(document, space)
+}
+
+fn resolve_color_space(document: &Document, space: &Object) -> Option<ColorSpace> {
+    let name_text = |name: &[u8]| String::from_utf8_lossy(name).to_string();
+    let (family, params): (&[u8], &[Object]) = match space {
+        Object::Name(name) => (name, &[]),
+        Object::Array(items) => (items.first()?.as_name().ok()?, &items[1..]),
+        _ => return None,
+    };
+    let components = match family {
+        b"DeviceGray" | b"CalGray" | b"G" => Some(1),
+        b"DeviceRGB" | b"CalRGB" | b"RGB" | b"Lab" => Some(3),
+        b"DeviceCMYK" | b"CMYK" => Some(4),
+        b"Indexed" | b"I" | b"Separation" | b"Pattern" => Some(1),
+        b"ICCBased" => params
+            .first()
+            .and_then(|profile| document.dereference(profile).ok())
+            .and_then(|(_, profile)| profile.as_stream().ok())
+            .and_then(|profile| profile.dict.get(b"N").and_then(Object::as_i64).ok())
+            .map(|n| n as u32),
+        b"DeviceN" => params
+            .first()
+            .and_then(|names| document.dereference(names).ok())
+            .and_then(|(_, names)| names.as_array().ok())
+            .map(|names| names.len() as u32),
+        _ => None,
+    };
+
+    let palette = if matches!(family, b"Indexed" | b"I") {
+        let base = params
+            .first()
+            .and_then(|base| document.dereference(base).ok())
+            .and_then(|(_, base)| resolve_color_space(document, base))
+            .and_then(|base| base.components);
+        let lookup = params
+            .get(2)
+            .and_then(|lookup| document.dereference(lookup).ok())
+            .and_then(|(_, lookup)| match lookup {
+                Object::String(bytes, _) => Some(bytes.clone()),
+                Object::Stream(stream) => Some(
+                    stream
+                        .decompressed_content()
+                        .unwrap_or_else(|_| stream.content.clone()),
+                ),
+                _ => None,
+            });
+        base.zip(lookup)
+    } else {
+        None
+    };
+
+    Some(ColorSpace {
+        family: name_text(family),
+        components,
+        palette,
+    })
+}
+
+fn filters(dict: &Dictionary) -> Vec<String> {
+    let text = |name: &[u8]| String::from_utf8_lossy(name).to_string();
+    match dict.get(b"Filter") {
+        Ok(Object::Name(name)) => vec![text(name)],
+        Ok(Object::Array(names)) => names
+            .iter()
+            .filter_map(|name| name.as_name().ok())
+            .map(text)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Converts an image XObject to a standalone file, returning its extension
+/// and contents, or the reason it cannot be converted.
+fn encode_image(document: &Document, stream: &Stream) -> Result<(&'static str, Vec<u8>), String> {
+    let filters = filters(&stream.dict);
+    match filters.last().map(String::as_str) {
+        // Passed through only when they are the sole filter; anything
+        // layered on top would need decoding first
+        Some("DCTDecode" | "DCT") if filters.len() == 1 => {
+            return Ok(("jpg", stream.content.clone()))
+        }
+        Some("JPXDecode") if filters.len() == 1 => return Ok(("jp2", stream.content.clone())),
+        Some(other @ ("DCTDecode" | "DCT" | "JPXDecode" | "CCITTFaxDecode" | "JBIG2Decode")) => {
+            return Err(format!("{other} images cannot be converted"))
+        }
+        _ => {}
+    }
+
+    let dict = &stream.dict;
+    let integer = |key: &[u8]| {
+        dict.get(key)
+            .and_then(|value| document.dereference(value))
+            .and_then(|(_, value)| value.as_i64())
+            .ok()
+    };
+    let width = integer(b"Width").unwrap_or(0) as u32;
+    let height = integer(b"Height").unwrap_or(0) as u32;
+    if width == 0 || height == 0 {
+        return Err("Image has no dimensions".to_string());
+    }
+    let image_mask = dict
+        .get(b"ImageMask")
+        .and_then(Object::as_bool)
+        .unwrap_or(false);
+    let bits = if image_mask {
+        1
+    } else {
+        integer(b"BitsPerComponent").unwrap_or(8) as u8
+    };
+
+    let (color_type, components, palette) = if image_mask {
+        (png::ColorType::Grayscale, 1, None)
+    } else {
+        let space = color_space(document, dict).ok_or("Unknown color space")?;
+        match (space.components, space.palette) {
+            (_, Some((base, lookup))) => {
+                let palette = match base {
+                    3 => lookup,
+                    1 => lookup.iter().flat_map(|gray| [*gray; 3]).collect(),
+                    _ => return Err(format!("Indexed {} images are not supported", space.family)),
+                };
+                (png::ColorType::Indexed, 1, Some(palette))
+            }
+            (Some(1), None) if space.family != "Separation" => (png::ColorType::Grayscale, 1, None),
+            (Some(3), None) if space.family != "Lab" => (png::ColorType::Rgb, 3, None),
+            _ => return Err(format!("{} images cannot be written as PNG", space.family)),
+        }
+    };
+    let bit_depth = match (bits, color_type) {
+        (1, png::ColorType::Grayscale | png::ColorType::Indexed) => png::BitDepth::One,
+        (2, png::ColorType::Grayscale | png::ColorType::Indexed) => png::BitDepth::Two,
+        (4, png::ColorType::Grayscale | png::ColorType::Indexed) => png::BitDepth::Four,
+        (8, _) => png::BitDepth::Eight,
+        (16, png::ColorType::Grayscale | png::ColorType::Rgb) => png::BitDepth::Sixteen,
+        _ => return Err(format!("{bits}-bit samples are not supported")),
+    };
+
+    let data = if filters.is_empty() {
+        stream.content.clone()
+    } else {
+        stream
+            .decompressed_content()
+            .map_err(|e| format!("Could not decode image data: {e}"))?
+    };
+    // Rows are byte-aligned in both formats, so samples carry over as-is
+    let row_bytes = (width as usize * components * bits as usize).div_ceil(8);
+    let expected = row_bytes * height as usize;
+    if data.len() < expected {
+        return Err("Image data is truncated".to_string());
+    }
+
+    let mut output = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut output, width, height);
+        encoder.set_color(color_type);
+        encoder.set_depth(bit_depth);
+        if let Some(mut palette) = palette {
+            // PLTE holds at most 2^bits entries
+            palette.truncate(3 << bits);
+            encoder.set_palette(palette);
+        }
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+        writer
+            .write_image_data(&data[..expected])
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(("png", output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sign::add_page_resource;
+    use crate::test_utils::create_test_pdf;
+    use tempfile::TempDir;
+
+    fn add_image(document: &mut Document, page: u32, name: &str, image: Stream) -> ObjectId {
+        let image_id = document.add_object(image);
+        let page_id = document.get_pages()[&page];
+        add_page_resource(
+            document,
+            page_id,
+            "XObject",
+            name,
+            Object::Reference(image_id),
+        )
+        .unwrap();
+        image_id
+    }
+
+    fn image_stream(color_space: Object, filter: Option<&str>, content: Vec<u8>) -> Stream {
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::Name(b"XObject".to_vec()));
+        dict.set("Subtype", Object::Name(b"Image".to_vec()));
+        dict.set("Width", Object::Integer(2));
+        dict.set("Height", Object::Integer(2));
+        dict.set("BitsPerComponent", Object::Integer(8));
+        dict.set("ColorSpace", color_space);
+        if let Some(filter) = filter {
+            dict.set("Filter", Object::Name(filter.as_bytes().to_vec()));
+        }
+        Stream::new(dict, content)
+    }
+
+    fn jpeg_stream() -> Stream {
+        image_stream(
+            Object::Name(b"DeviceGray".to_vec()),
+            Some("DCTDecode"),
+            vec![0xFF, 0xD8, 0xFF, 0xD9],
+        )
+    }
+
+    fn gradient() -> Vec<u8> {
+        (0..192).map(|i| (i % 64) as u8 * 4).collect()
+    }
+
+    fn sample_pdf(dir: &TempDir) -> String {
+        let path = dir.path().join("figures.pdf").to_string_lossy().to_string();
+        let mut document = create_test_pdf(2, "Figures", "text").unwrap();
+
+        let mut rgb = image_stream(Object::Name(b"DeviceRGB".to_vec()), None, gradient());
+        // Large enough for compression to pay off
+        rgb.dict.set("Width", Object::Integer(8));
+        rgb.dict.set("Height", Object::Integer(8));
+        rgb.compress().unwrap();
+        add_image(&mut document, 1, "Im0", rgb);
+        let jpeg_id = add_image(&mut document, 1, "Im1", jpeg_stream());
+        // Shared with page 2, which also has a CMYK image
+        let page_two = document.get_pages()[&2];
+        add_page_resource(
+            &mut document,
+            page_two,
+            "XObject",
+            "Logo",
+            Object::Reference(jpeg_id),
+        )
+        .unwrap();
+        let cmyk = image_stream(Object::Name(b"DeviceCMYK".to_vec()), None, vec![0; 16]);
+        add_image(&mut document, 2, "Im2", cmyk);
+
+        document.save(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_list_images() {
+        let dir = TempDir::new().unwrap();
+        let path = sample_pdf(&dir);
+
+        let pages = list_images(&path).unwrap();
+        assert_eq!(pages.len(), 2);
+        let rgb = pages[0]
+            .images
+            .iter()
+            .find(|image| image.name == "Im0")
+            .unwrap();
+        assert_eq!((rgb.width, rgb.height), (8, 8));
+        assert_eq!(rgb.bits_per_component, Some(8));
+        assert_eq!(rgb.color_space.as_deref(), Some("DeviceRGB"));
+        assert_eq!(rgb.components, Some(3));
+        assert_eq!(rgb.filters, vec!["FlateDecode".to_string()]);
+        assert!(rgb.byte_size > 0);
+
+        let names: Vec<&str> = pages[1]
+            .images
+            .iter()
+            .map(|image| image.name.as_str())
+            .collect();
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(&"Logo") && names.contains(&"Im2"));
+    }
+
+    #[test]
+    fn test_extract_images() {
+        let dir = TempDir::new().unwrap();
+        let path = sample_pdf(&dir);
+        let output_dir = dir.path().join("images").to_string_lossy().to_string();
+
+        let extraction = extract_images(&path, &output_dir, None).unwrap();
+        assert_eq!(extraction.images.len(), 2);
+        let jpeg = extraction
+            .images
+            .iter()
+            .find(|image| image.format == "jpg")
+            .unwrap();
+        assert!(jpeg.output_file.contains("figures_p1_"));
+        assert!(jpeg.output_file.ends_with("_Im1.jpg"));
+        assert_eq!(
+            std::fs::read(&jpeg.output_file).unwrap(),
+            vec![0xFF, 0xD8, 0xFF, 0xD9]
+        );
+
+        let png_file = extraction
+            .images
+            .iter()
+            .find(|image| image.format == "png")
+            .unwrap();
+        let decoder = png::Decoder::new(std::fs::File::open(&png_file.output_file).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut pixels).unwrap();
+        assert_eq!(pixels, gradient());
+
+        assert_eq!(extraction.skipped.len(), 1);
+        assert_eq!(extraction.skipped[0].name, "Im2");
+
+        let page_two = extract_images(&path, &output_dir, Some("2")).unwrap();
+        assert_eq!(page_two.images.len(), 1);
+        assert_eq!(page_two.images[0].name, "Logo");
+    }
+
+    #[test]
+    fn test_extracted_file_names_are_unique_and_safe() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("figures.pdf").to_string_lossy().to_string();
+        let mut document = create_test_pdf(1, "Figures", "text").unwrap();
+        // A form on the page reuses the page's resource name for another image
+        let page_image = add_image(&mut document, 1, "Fig/1", jpeg_stream());
+        let form_image = document.add_object(jpeg_stream());
+        let mut xobjects = Dictionary::new();
+        xobjects.set("Fig/1", Object::Reference(form_image));
+        let mut resources = Dictionary::new();
+        resources.set("XObject", Object::Dictionary(xobjects));
+        let mut form = Dictionary::new();
+        form.set("Type", Object::Name(b"XObject".to_vec()));
+        form.set("Subtype", Object::Name(b"Form".to_vec()));
+        form.set("Resources", Object::Dictionary(resources));
+        let form_id = document.add_object(Stream::new(form, b"/Fig#2F1 Do".to_vec()));
+        let page_id = document.get_pages()[&1];
+        add_page_resource(
+            &mut document,
+            page_id,
+            "XObject",
+            "Fm0",
+            Object::Reference(form_id),
+        )
+        .unwrap();
+        document.save(&path).unwrap();
+        let output_dir = dir.path().join("images").to_string_lossy().to_string();
+
+        let extraction = extract_images(&path, &output_dir, None).unwrap();
+        let mut files: Vec<String> = extraction
+            .images
+            .iter()
+            .map(|image| image.output_file.clone())
+            .collect();
+        files.sort();
+        let mut expected: Vec<String> = [page_image, form_image]
+            .iter()
+            .map(|id| {
+                Path::new(&output_dir)
+                    .join(format!("figures_p1_{}_Fig_1.jpg", id.0))
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+        expected.sort();
+        assert_eq!(files, expected);
+        assert!(files.iter().all(|file| Path::new(file).exists()));
+    }
+}
@@ -0,0 +1,418 @@
+use lopdf::content::{Content, Operation};
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use napi_derive::napi;
+use std::collections::HashMap;
+
+use crate::page_edit::{paper_size, set_page_order};
+use crate::page_range::select_pages;
+use crate::rotate::{normalize_page, rect_object};
+use crate::sign::{inherited_page_attribute, page_box};
+use crate::watermark::matrix_operands;
+
+#[derive(Debug, Clone, Default)]
+#[napi(object)]
+pub struct ImposeOptions {
+    pub page_range: Option<String>, // Source pages to place (default: all)
+    pub paper_size: Option<String>, // Sheet size; defaults to the size of the first source page
+    pub width: Option<f64>, // Sheet width in points; overrides paper_size together with height
+    pub height: Option<f64>,
+    pub landscape: Option<bool>, // Defaults to whatever suits the layout best
+    pub margin: Option<f64>,     // Blank border around the sheet in points (default: 0)
+    pub gutter: Option<f64>,     // Space between placed pages in points (default: 0)
+    pub border: Option<bool>,    // Draw a thin frame around each placed page
+}
+
+/// Sheet geometry shared by the N-up and booklet layouts.
+struct Layout {
+    width: f64,
+    height: f64,
+    columns: usize,
+    rows: usize,
+    margin: f64,
+    gutter: f64,
+    border: bool,
+}
+
+/// Places `per_sheet` (2, 4, 6 or 9) source pages on each output sheet, left
+/// to right and top to bottom.
+///
+/// Each page keeps its vector content as a Form XObject and is scaled to fit
+/// its cell. Annotations and links on the source pages are not carried over.
+///
+/// # Returns
+/// The number of sheets written.
+pub fn n_up(
+    input_path: &str,
+    output_path: &str,
+    per_sheet: u32,
+    options: &ImposeOptions,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    // Grid for a portrait sheet; landscape sheets swap columns and rows
+    let (columns, rows) = match per_sheet {
+        2 => (1, 2),
+        4 => (2, 2),
+        6 => (2, 3),
+        9 => (3, 3),
+        other => return Err(format!("Pages per sheet must be 2, 4, 6 or 9, got {other}").into()),
+    };
+
+    let mut document = Document::load(input_path)?;
+    let page_ids = select_pages(&document, options.page_range.as_deref(), None)?;
+    if page_ids.is_empty() {
+        return Err("No pages selected".into());
+    }
+
+    // Two or six portrait pages fill a landscape sheet best, and vice versa
+    let (width, height) = source_size(&mut document, page_ids[0])?;
+    let rotated_grid = columns != rows;
+    let landscape = options
+        .landscape
+        .unwrap_or(rotated_grid == (width <= height));
+    let layout = sheet_layout(options, (width, height), landscape, (columns, rows))?;
+
+    let cells: Vec<Option<ObjectId>> = page_ids.into_iter().map(Some).collect();
+    let sheets: Vec<&[Option<ObjectId>]> = cells.chunks(per_sheet as usize).collect();
+    compose(&mut document, &sheets, &layout)?;
+
+    document.save(output_path)?;
+    Ok(sheets.len() as u32)
+}
+
+/// Lays pages out for saddle-stitch binding: two pages per side, in the order
+/// that reads correctly once the printed stack is folded in half. Blank
+/// pages pad the document to a multiple of four.
+///
+/// Print the result double-sided, flipping on the short edge.
+///
+/// # Returns
+/// The number of sides written (twice the number of physical sheets).
+pub fn booklet(
+    input_path: &str,
+    output_path: &str,
+    options: &ImposeOptions,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    let mut document = Document::load(input_path)?;
+    let page_ids = select_pages(&document, options.page_range.as_deref(), None)?;
+    if page_ids.is_empty() {
+        return Err("No pages selected".into());
+    }
+
+    let size = source_size(&mut document, page_ids[0])?;
+    let landscape = options.landscape.unwrap_or(true);
+    let layout = sheet_layout(options, size, landscape, (1, 2))?;
+
+    let mut cells: Vec<Option<ObjectId>> = Vec::new();
+    for (left, right) in booklet_order(page_ids.len()) {
+        cells.push(left.map(|index| page_ids[index]));
+        cells.push(right.map(|index| page_ids[index]));
+    }
+    let sides: Vec<&[Option<ObjectId>]> = cells.chunks(2).collect();
+    compose(&mut document, &sides, &layout)?;
+
+    document.save(output_path)?;
+    Ok(sides.len() as u32)
+}
+
+/// Returns the 0-based (left, right) pages of each booklet side; `None` is a
+/// blank padding page.
+pub(crate) fn booklet_order(count: usize) -> Vec<(Option<usize>, Option<usize>)> {
+    let padded = count.div_ceil(4) * 4;
+    let page = |index: usize| (index < count).then_some(index);
+    (0..padded / 2)
+        .map(|side| {
+            let (low, high) = (side, padded - 1 - side);
+            // Fronts carry the last page on the left, backs on the right
+            if side % 2 == 0 {
+                (page(high), page(low))
+            } else {
+                (page(low), page(high))
+            }
+        })
+        .collect()
+}
+
+/// Turns a page into a Form XObject whose BBox is the page's visible area.
+///
+/// The page's content streams are joined into the form and its resources
+/// are copied onto it; rotated pages are normalized first so the form shows
+/// the page upright. The page itself is left in place.
+pub(crate) fn page_form_xobject(
+    document: &mut Document,
+    page_id: ObjectId,
+) -> lopdf::Result<ObjectId> {
+    normalize_page(document, page_id)?;
+
+    let mut content = Vec::new();
+    for content_id in document.get_page_contents(page_id) {
+        if let Ok(stream) = document.get_object(content_id).and_then(Object::as_stream) {
+            let data = stream
+                .decompressed_content()
+                .unwrap_or_else(|_| stream.content.clone());
+            content.extend(data);
+            // Streams are concatenated, so keep each one on its own token boundary
+            content.push(b'\n');
+        }
+    }
+
+    let mut form = Dictionary::new();
+    form.set("Type", Object::Name(b"XObject".to_vec()));
+    form.set("Subtype", Object::Name(b"Form".to_vec()));
+    form.set("BBox", rect_object(page_box(document, page_id)));
+    let resources = match inherited_page_attribute(document, page_id, b"Resources") {
+        Some(Object::Dictionary(resources)) => resources,
+        _ => Dictionary::new(),
+    };
+    form.set("Resources", Object::Dictionary(resources));
+
+    let mut stream = Stream::new(form, content);
+    // Compression is best effort; an uncompressed form is still valid
+    let _ = stream.compress();
+    Ok(document.add_object(stream))
+}
+
+/// Normalizes the first page and returns its visible width and height.
+fn source_size(document: &mut Document, page_id: ObjectId) -> lopdf::Result<(f64, f64)> {
+    normalize_page(document, page_id)?;
+    let [llx, lly, urx, ury] = page_box(document, page_id);
+    Ok((urx - llx, ury - lly))
+}
+
+fn sheet_layout(
+    options: &ImposeOptions,
+    source: (f64, f64),
+    landscape: bool,
+    (columns, rows): (usize, usize),
+) -> Result<Layout, Box<dyn std::error::Error>> {
+    let (width, height) = match (options.width, options.height) {
+        (Some(width), Some(height)) => (width, height),
+        _ => match options.paper_size.as_deref() {
+            Some(name) => {
+                paper_size(name).ok_or_else(|| format!("Unknown paper size: '{name}'"))?
+            }
+            None => source,
+        },
+    };
+    let (width, height) = if landscape {
+        (width.max(height), width.min(height))
+    } else {
+        (width.min(height), width.max(height))
+    };
+    let (columns, rows) = if landscape {
+        (rows, columns)
+    } else {
+        (columns, rows)
+    };
+
+    let layout = Layout {
+        width,
+        height,
+        columns,
+        rows,
+        margin: options.margin.unwrap_or(0.0).max(0.0),
+        gutter: options.gutter.unwrap_or(0.0).max(0.0),
+        border: options.border.unwrap_or(false),
+    };
+    if layout.cell_size().0 <= 0.0 || layout.cell_size().1 <= 0.0 {
+        return Err("Margin and gutter leave no room for pages".into());
+    }
+    Ok(layout)
+}
+
+impl Layout {
+    fn cell_size(&self) -> (f64, f64) {
+        let usable_width = self.width - 2.0 * self.margin - (self.columns - 1) as f64 * self.gutter;
+        let usable_height = self.height - 2.0 * self.margin - (self.rows - 1) as f64 * self.gutter;
+        (
+            usable_width / self.columns as f64,
+            usable_height / self.rows as f64,
+        )
+    }
+
+    /// Lower-left corner of the cell at `index`, counting left to right from
+    /// the top row.
+    fn cell_origin(&self, index: usize) -> (f64, f64) {
+        let (cell_width, cell_height) = self.cell_size();
+        let (column, row) = (index % self.columns, index / self.columns);
+        (
+            self.margin + column as f64 * (cell_width + self.gutter),
+            self.height - self.margin - (row + 1) as f64 * cell_height - row as f64 * self.gutter,
+        )
+    }
+}
+
+/// Replaces the document's pages with one new page per entry in `sheets`,
+/// each drawing its source pages as Form XObjects in layout order.
+fn compose(
+    document: &mut Document,
+    sheets: &[&[Option<ObjectId>]],
+    layout: &Layout,
+) -> lopdf::Result<()> {
+    let mut forms: HashMap<ObjectId, (ObjectId, [f64; 4])> = HashMap::new();
+    let mut sheet_ids = Vec::with_capacity(sheets.len());
+    let (cell_width, cell_height) = layout.cell_size();
+
+    for cells in sheets {
+        let mut operations = Vec::new();
+        let mut xobjects = Dictionary::new();
+        for (index, page_id) in cells.iter().enumerate() {
+            let Some(page_id) = page_id else {
+                continue;
+            };
+            let (form_id, [llx, lly, urx, ury]) = match forms.get(page_id) {
+                Some(form) => *form,
+                None => {
+                    let form_id = page_form_xobject(document, *page_id)?;
+                    let form = (form_id, page_box(document, *page_id));
+                    forms.insert(*page_id, form);
+                    form
+                }
+            };
+
+            let (width, height) = (urx - llx, ury - lly);
+            let scale = (cell_width / width).min(cell_height / height);
+            let (cell_x, cell_y) = layout.cell_origin(index);
+            let x = cell_x + (cell_width - width * scale) / 2.0;
+            let y = cell_y + (cell_height - height * scale) / 2.0;
+
+            let name = format!("P{}", index + 1);
+            xobjects.set(name.as_str(), Object::Reference(form_id));
+            operations.push(Operation::new("q", vec![]));
+            operations.push(Operation::new(
+                "cm",
+                matrix_operands([scale, 0.0, 0.0, scale, x - llx * scale, y - lly * scale]),
+            ));
+            operations.push(Operation::new("Do", vec![Object::Name(name.into_bytes())]));
+            operations.push(Operation::new("Q", vec![]));
+            if layout.border {
+                operations.extend([
+                    Operation::new("q", vec![]),
+                    Operation::new("w", vec![Object::Real(0.5)]),
+                    Operation::new("G", vec![Object::Integer(0)]),
+                    Operation::new(
+                        "re",
+                        vec![
+                            Object::Real(x as f32),
+                            Object::Real(y as f32),
+                            Object::Real((width * scale) as f32),
+                            Object::Real((height * scale) as f32),
+                        ],
+                    ),
+                    Operation::new("S", vec![]),
+                    Operation::new("Q", vec![]),
+                ]);
+            }
+        }
+
+        let content_data = Content { operations }.encode()?;
+        let content_id = document.add_object(Stream::new(Dictionary::new(), content_data));
+        let mut resources = Dictionary::new();
+        resources.set("XObject", Object::Dictionary(xobjects));
+
+        let mut sheet = Dictionary::new();
+        sheet.set("Type", Object::Name(b"Page".to_vec()));
+        sheet.set(
+            "MediaBox",
+            rect_object([0.0, 0.0, layout.width, layout.height]),
+        );
+        sheet.set("Resources", Object::Dictionary(resources));
+        sheet.set("Contents", Object::Reference(content_id));
+        sheet_ids.push(document.add_object(sheet));
+    }
+
+    set_page_order(document, &sheet_ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sign::page_rect;
+    use crate::test_utils::create_test_pdf;
+    use tempfile::TempDir;
+
+    fn save_input(dir: &TempDir, pages: u32) -> String {
+        let path = dir.path().join("input.pdf").to_string_lossy().to_string();
+        create_test_pdf(pages, "Handout", "text")
+            .unwrap()
+            .save(&path)
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn test_booklet_order() {
+        let order = booklet_order(6);
+        assert_eq!(
+            order,
+            vec![
+                (None, Some(0)),
+                (Some(1), None),
+                (Some(5), Some(2)),
+                (Some(3), Some(4)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_four_up() {
+        let dir = TempDir::new().unwrap();
+        let input = save_input(&dir, 5);
+        let output = dir.path().join("4up.pdf").to_string_lossy().to_string();
+
+        let options = ImposeOptions {
+            margin: Some(18.0),
+            gutter: Some(9.0),
+            border: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(n_up(&input, &output, 4, &options).unwrap(), 2);
+        assert!(n_up(&input, &output, 3, &options).is_err());
+
+        let document = Document::load(&output).unwrap();
+        let pages = document.get_pages();
+        assert_eq!(pages.len(), 2);
+        let sheet = pages[&1];
+        assert_eq!(
+            page_rect(&document, sheet, b"MediaBox"),
+            Some([0.0, 0.0, 612.0, 792.0])
+        );
+        let content =
+            String::from_utf8_lossy(&document.get_page_content(sheet).unwrap()).to_string();
+        assert_eq!(content.matches(" Do").count(), 4);
+        assert_eq!(content.matches(" re").count(), 4);
+
+        // The form holds the original page content
+        let form = document
+            .get_dictionary(sheet)
+            .and_then(|page| page.get_deref(b"Resources", &document))
+            .and_then(Object::as_dict)
+            .and_then(|resources| resources.get(b"XObject"))
+            .and_then(Object::as_dict)
+            .and_then(|xobjects| xobjects.get(b"P1"))
+            .and_then(Object::as_reference)
+            .and_then(|id| document.get_object(id))
+            .and_then(Object::as_stream)
+            .unwrap();
+        let data = form.decompressed_content().unwrap_or(form.content.clone());
+        assert!(String::from_utf8_lossy(&data).contains("Handout - Page 1"));
+    }
+
+    #[test]
+    fn test_two_up_booklet_is_landscape() {
+        let dir = TempDir::new().unwrap();
+        let input = save_input(&dir, 8);
+        let output = dir.path().join("booklet.pdf").to_string_lossy().to_string();
+
+        let options = ImposeOptions {
+            paper_size: Some("Letter".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(booklet(&input, &output, &options).unwrap(), 4);
+
+        let document = Document::load(&output).unwrap();
+        let first = document.get_pages()[&1];
+        assert_eq!(
+            page_rect(&document, first, b"MediaBox"),
+            Some([0.0, 0.0, 792.0, 612.0])
+        );
+    }
+}
@@ -0,0 +1,395 @@
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+use crate::merge::{name_tree_entries, resolve_array, resolve_dictionary};
+use crate::page_edit::paper_size;
+use crate::rotate::page_rotation;
+use crate::sign::{page_box, page_rect};
+
+/// Paper formats recognized in [`PageInfo::paper_size`].
+const PAPER_SIZES: [&str; 6] = ["A3", "A4", "A5", "Letter", "Legal", "Tabloid"];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PageInfo {
+    pub number: u32,
+    pub width: f64, // Visible (CropBox) size in points, before rotation
+    pub height: f64,
+    pub rotation: i64,
+    pub media_box: [f64; 4],
+    pub crop_box: Option<[f64; 4]>,
+    pub paper_size: Option<String>, // Named format within a point of the visible size
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PdfInfo {
+    pub file: String,
+    pub file_size: u64,
+    pub version: String,
+    pub page_count: u32,
+    pub info: BTreeMap<String, String>, // Document Info dictionary entries
+    pub producer: Option<String>,       // From Info, or XMP when Info has none
+    pub encrypted: bool,
+    pub linearized: bool,
+    pub tagged: bool,
+    pub pdfa: Option<String>,  // Claimed PDF/A conformance, e.g. "2B"
+    pub pdfua: Option<String>, // Claimed PDF/UA part
+    pub has_form: bool,
+    pub form_field_count: u32,
+    pub signature_count: u32, // Signed signature fields
+    pub attachment_count: u32,
+    pub object_count: u32,
+    pub pages: Vec<PageInfo>,
+}
+
+/// Collects what is known about a PDF without changing it.
+///
+/// Conformance claims (PDF/A, PDF/UA) are read from the XMP metadata as
+/// declared by the producer; they are not validated.
+pub fn get_pdf_info(file_path: &str) -> Result<PdfInfo, Box<dyn std::error::Error>> {
+    let file_size = std::fs::metadata(file_path)?.len();
+    let document = Document::load(file_path)?;
+    let catalog = document.catalog()?;
+
+    let info: BTreeMap<String, String> =
+        resolve_dictionary(&document, document.trailer.get(b"Info").ok())
+            .map(|info| {
+                info.iter()
+                    .filter_map(|(key, value)| {
+                        let value = document.dereference(value).ok()?.1;
+                        Some((
+                            String::from_utf8_lossy(key).to_string(),
+                            object_text(value)?,
+                        ))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+    let xmp = xmp_metadata(&document);
+    let producer = info.get("Producer").cloned().or_else(|| {
+        xmp.as_deref()
+            .and_then(|xmp| xmp_property(xmp, "pdf:Producer"))
+    });
+    let pdfa = xmp.as_deref().and_then(|xmp| {
+        let part = xmp_property(xmp, "pdfaid:part")?;
+        let conformance = xmp_property(xmp, "pdfaid:conformance").unwrap_or_default();
+        Some(format!("{part}{}", conformance.to_uppercase()))
+    });
+    let pdfua = xmp
+        .as_deref()
+        .and_then(|xmp| xmp_property(xmp, "pdfuaid:part"));
+
+    let tagged = resolve_dictionary(&document, catalog.get(b"MarkInfo").ok())
+        .and_then(|mark_info| mark_info.get(b"Marked").and_then(Object::as_bool).ok())
+        .unwrap_or(false);
+    let linearized = document
+        .objects
+        .values()
+        .any(|object| object.as_dict().is_ok_and(|dict| dict.has(b"Linearized")));
+
+    let fields = form_fields(&document);
+    let signature_count = fields
+        .iter()
+        .filter(|id| {
+            field_attribute(&document, **id, b"FT")
+                .is_some_and(|ft| ft.as_name().ok() == Some(b"Sig".as_slice()))
+                && field_attribute(&document, **id, b"V").is_some()
+        })
+        .count();
+
+    let page_ids = document.get_pages();
+    let mut attachment_count = resolve_dictionary(&document, catalog.get(b"Names").ok())
+        .and_then(|names| resolve_dictionary(&document, names.get(b"EmbeddedFiles").ok()))
+        .map(|tree| name_tree_entries(&document, tree).len())
+        .unwrap_or(0);
+    let mut pages = Vec::with_capacity(page_ids.len());
+    for (number, page_id) in &page_ids {
+        attachment_count += page_annotations(&document, *page_id)
+            .iter()
+            .filter(|annot| {
+                annot.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"FileAttachment")
+            })
+            .count();
+
+        let [llx, lly, urx, ury] = page_box(&document, *page_id);
+        let (width, height) = (urx - llx, ury - lly);
+        let paper = PAPER_SIZES.iter().find(|name| {
+            paper_size(name).is_some_and(|(w, h)| {
+                let fits = |a: f64, b: f64| (a - w).abs() < 1.0 && (b - h).abs() < 1.0;
+                fits(width, height) || fits(height, width)
+            })
+        });
+        pages.push(PageInfo {
+            number: *number,
+            width,
+            height,
+            rotation: page_rotation(&document, *page_id),
+            media_box: page_rect(&document, *page_id, b"MediaBox")
+                .unwrap_or([0.0, 0.0, 612.0, 792.0]),
+            crop_box: page_rect(&document, *page_id, b"CropBox"),
+            paper_size: paper.map(|name| name.to_string()),
+        });
+    }
+
+    Ok(PdfInfo {
+        file: file_path.to_string(),
+        file_size,
+        version: document.version.clone(),
+        page_count: pages.len() as u32,
+        info,
+        producer,
+        encrypted: document.is_encrypted() || document.encryption_state.is_some(),
+        linearized,
+        tagged,
+        pdfa,
+        pdfua,
+        has_form: catalog.has(b"AcroForm"),
+        form_field_count: fields.len() as u32,
+        signature_count: signature_count as u32,
+        attachment_count: attachment_count as u32,
+        object_count: document.objects.len() as u32,
+        pages,
+    })
+}
+
+/// Renders a simple value (string, name, number, boolean) as text.
+pub(crate) fn object_text(object: &Object) -> Option<String> {
+    match object {
+        Object::String(..) => lopdf::decode_text_string(object).ok(),
+        Object::Name(name) => Some(String::from_utf8_lossy(name).to_string()),
+        Object::Integer(value) => Some(value.to_string()),
+        Object::Real(value) => Some(value.to_string()),
+        Object::Boolean(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// Returns the catalog's XMP metadata packet as text.
+pub(crate) fn xmp_metadata(document: &Document) -> Option<String> {
+    let catalog = document.catalog().ok()?;
+    let metadata_id = catalog
+        .get(b"Metadata")
+        .and_then(Object::as_reference)
+        .ok()?;
+    let stream = document
+        .get_object(metadata_id)
+        .and_then(Object::as_stream)
+        .ok()?;
+    let data = stream
+        .decompressed_content()
+        .unwrap_or_else(|_| stream.content.clone());
+    Some(String::from_utf8_lossy(&data).to_string())
+}
+
+/// Reads a simple XMP property written either as an attribute
+/// (`pdf:Producer="..."`) or as an element, taking the first `rdf:li` of
+/// language alternatives and sequences.
+pub(crate) fn xmp_property(xmp: &str, name: &str) -> Option<String> {
+    let attribute = format!("{name}=\"");
+    if let Some(start) = xmp.find(&attribute) {
+        let value = &xmp[start + attribute.len()..];
+        return Some(xml_unescape(&value[..value.find('"')?]));
+    }
+
+    let open = format!("<{name}");
+    let start = xmp.find(&open)?;
+    let element = &xmp[start + open.len()..];
+    // Skip a longer name sharing the prefix (e.g. pdfaid:partX)
+    if !element.starts_with(['>', ' ', '\t', '\r', '\n']) {
+        return None;
+    }
+    let body = &element[element.find('>')? + 1..];
+    let body = &body[..body.find(&format!("</{name}>"))?];
+    let text = match body.find("<rdf:li") {
+        Some(li) => {
+            let item = &body[li..];
+            let item = &item[item.find('>')? + 1..];
+            &item[..item.find("</rdf:li>")?]
+        }
+        None => body,
+    };
+    Some(xml_unescape(text.trim()))
+}
+
+fn xml_unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Returns the terminal fields of the document's AcroForm, the ones that hold
+/// values (as opposed to intermediate nodes that only group them).
+pub(crate) fn form_fields(document: &Document) -> Vec<ObjectId> {
+    fn walk(
+        document: &Document,
+        id: ObjectId,
+        seen: &mut HashSet<ObjectId>,
+        out: &mut Vec<ObjectId>,
+    ) {
+        if !seen.insert(id) {
+            return;
+        }
+        let Ok(field) = document.get_dictionary(id) else {
+            return;
+        };
+        let kids: Vec<ObjectId> = resolve_array(document, field.get(b"Kids").ok())
+            .map(|kids| {
+                kids.iter()
+                    .filter_map(|kid| kid.as_reference().ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        // Kids without a /T are the field's widgets, not child fields
+        let child_fields: Vec<ObjectId> = kids
+            .into_iter()
+            .filter(|kid| document.get_dictionary(*kid).is_ok_and(|kid| kid.has(b"T")))
+            .collect();
+        if child_fields.is_empty() {
+            out.push(id);
+        }
+        for kid in child_fields {
+            walk(document, kid, seen, out);
+        }
+    }
+
+    let mut fields = Vec::new();
+    let Some(form) = document
+        .catalog()
+        .ok()
+        .and_then(|catalog| resolve_dictionary(document, catalog.get(b"AcroForm").ok()))
+    else {
+        return fields;
+    };
+    let mut seen = HashSet::new();
+    for field in resolve_array(document, form.get(b"Fields").ok())
+        .into_iter()
+        .flatten()
+    {
+        if let Ok(id) = field.as_reference() {
+            walk(document, id, &mut seen, &mut fields);
+        }
+    }
+    fields
+}
+
+/// Looks up a field attribute, following `/Parent` for inheritable keys
+/// such as `/FT` and `/V`.
+pub(crate) fn field_attribute<'a>(
+    document: &'a Document,
+    field_id: ObjectId,
+    key: &[u8],
+) -> Option<&'a Object> {
+    let mut node_id = field_id;
+    for _ in 0..32 {
+        let node = document.get_dictionary(node_id).ok()?;
+        if let Ok(value) = node.get(key) {
+            return Some(value);
+        }
+        node_id = node.get(b"Parent").and_then(Object::as_reference).ok()?;
+    }
+    None
+}
+
+/// Returns the annotation dictionaries of a page.
+pub(crate) fn page_annotations(document: &Document, page_id: ObjectId) -> Vec<&Dictionary> {
+    document
+        .get_dictionary(page_id)
+        .ok()
+        .and_then(|page| resolve_array(document, page.get(b"Annots").ok()))
+        .map(|annots| {
+            annots
+                .iter()
+                .filter_map(|annot| resolve_dictionary(document, Some(annot)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_pdf;
+    use lopdf::Stream;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_xmp_property() {
+        let xmp = r#"<rdf:Description pdfaid:part="2" pdfaid:conformance="b">
+            <dc:title><rdf:Alt><rdf:li xml:lang="x-default">Q&amp;A</rdf:li></rdf:Alt></dc:title>
+            <pdf:Producer>SoloPDF</pdf:Producer>
+            </rdf:Description>"#;
+        assert_eq!(xmp_property(xmp, "pdfaid:part").as_deref(), Some("2"));
+        assert_eq!(xmp_property(xmp, "dc:title").as_deref(), Some("Q&A"));
+        assert_eq!(
+            xmp_property(xmp, "pdf:Producer").as_deref(),
+            Some("SoloPDF")
+        );
+        assert_eq!(xmp_property(xmp, "pdfuaid:part"), None);
+    }
+
+    #[test]
+    fn test_get_pdf_info() {
+        let dir = TempDir::new().unwrap();
+        let mut document = create_test_pdf(2, "Info", "text").unwrap();
+        let pages = document.get_pages();
+
+        let mut info = Dictionary::new();
+        info.set("Title", lopdf::text_string("Quarterly report"));
+        info.set("Producer", lopdf::text_string("Report Writer 2"));
+        let info_id = document.add_object(info);
+        document.trailer.set("Info", Object::Reference(info_id));
+
+        let xmp = br#"<x:xmpmeta><rdf:RDF><rdf:Description pdfaid:part="1" pdfaid:conformance="A"/></rdf:RDF></x:xmpmeta>"#;
+        let mut metadata = Dictionary::new();
+        metadata.set("Type", Object::Name(b"Metadata".to_vec()));
+        metadata.set("Subtype", Object::Name(b"XML".to_vec()));
+        let metadata_id = document.add_object(Stream::new(metadata, xmp.to_vec()));
+        let mut mark_info = Dictionary::new();
+        mark_info.set("Marked", Object::Boolean(true));
+
+        // One signed signature field and one text field
+        let mut signature = Dictionary::new();
+        signature.set("FT", Object::Name(b"Sig".to_vec()));
+        signature.set("T", lopdf::text_string("Approval"));
+        signature.set("V", Object::Dictionary(Dictionary::new()));
+        let signature_id = document.add_object(signature);
+        let mut text = Dictionary::new();
+        text.set("FT", Object::Name(b"Tx".to_vec()));
+        text.set("T", lopdf::text_string("Name"));
+        let text_id = document.add_object(text);
+        let mut form = Dictionary::new();
+        form.set(
+            "Fields",
+            Object::Array(vec![
+                Object::Reference(signature_id),
+                Object::Reference(text_id),
+            ]),
+        );
+
+        let catalog = document.catalog_mut().unwrap();
+        catalog.set("Metadata", Object::Reference(metadata_id));
+        catalog.set("MarkInfo", Object::Dictionary(mark_info));
+        catalog.set("AcroForm", Object::Dictionary(form));
+        document
+            .get_dictionary_mut(pages[&2])
+            .unwrap()
+            .set("Rotate", Object::Integer(90));
+
+        let path = dir.path().join("info.pdf").to_string_lossy().to_string();
+        document.save(&path).unwrap();
+
+        let info = get_pdf_info(&path).unwrap();
+        assert_eq!(info.page_count, 2);
+        assert_eq!(info.info["Title"], "Quarterly report");
+        assert_eq!(info.producer.as_deref(), Some("Report Writer 2"));
+        assert_eq!(info.pdfa.as_deref(), Some("1A"));
+        assert!(info.tagged && info.has_form && !info.encrypted);
+        assert_eq!((info.form_field_count, info.signature_count), (2, 1));
+        assert_eq!(info.attachment_count, 0);
+        assert_eq!(info.pages[0].paper_size.as_deref(), Some("Letter"));
+        assert_eq!(info.pages[1].rotation, 90);
+        assert!(info.file_size > 0);
+    }
+}
@@ -0,0 +1,539 @@
+use lopdf::xref::{XrefEntry, XrefType};
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream, StringFormat};
+use napi_derive::napi;
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// Stream bytes shown by default before the contents are cut off.
+const DEFAULT_MAX_STREAM_BYTES: u32 = 4096;
+
+#[derive(Debug, Clone, Default)]
+#[napi(object)]
+pub struct InspectOptions {
+    pub format: Option<String>,        // "json" (default) or "text"
+    pub max_stream_bytes: Option<u32>, // Stream contents shown per object (default: 4096, 0 hides them)
+    pub decode_streams: Option<bool>,  // Show decoded rather than raw stream data (default: true)
+}
+
+/// Dumps part of a PDF's low-level structure for debugging.
+///
+/// `target` selects what to show:
+/// - `"trailer"`: the trailer dictionary
+/// - `"xref"`: the cross-reference entries as loaded
+/// - `"pages"`: the page tree, one node per line in text form
+/// - an object number such as `"12"` or `"12 0"` (a trailing `R` is
+///   accepted): that object, with stream contents up to `max_stream_bytes`
+///
+/// Names are written as `/Name`, strings as `(text)` or `<hex>` and
+/// references as `12 0 R`, in both formats.
+pub fn inspect_pdf(
+    file_path: &str,
+    target: &str,
+    options: &InspectOptions,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let text = match options.format.as_deref().unwrap_or("json") {
+        "json" => false,
+        "text" => true,
+        other => return Err(format!("Unsupported format: '{other}'").into()),
+    };
+    let document = Document::load(file_path)?;
+    let inspector = Inspector {
+        document: &document,
+        max_stream_bytes: options.max_stream_bytes.unwrap_or(DEFAULT_MAX_STREAM_BYTES) as usize,
+        decode_streams: options.decode_streams.unwrap_or(true),
+    };
+
+    let target = target.trim();
+    Ok(match (target.to_ascii_lowercase().as_str(), text) {
+        ("trailer", false) => serde_json::to_string_pretty(
+            &inspector.value(&Object::Dictionary(document.trailer.clone())),
+        )?,
+        ("trailer", true) => format!(
+            "trailer\n{}\n",
+            inspector.syntax(&Object::Dictionary(document.trailer.clone()), 0)
+        ),
+        ("xref", false) => serde_json::to_string_pretty(&inspector.xref_value())?,
+        ("xref", true) => inspector.xref_text(),
+        ("pages", false) => serde_json::to_string_pretty(&inspector.page_tree_value())?,
+        ("pages", true) => inspector.page_tree_text(),
+        _ => {
+            let id = parse_object_id(target)?;
+            let object = document
+                .get_object(id)
+                .map_err(|_| format!("Object {} {} not found", id.0, id.1))?;
+            if text {
+                format!(
+                    "{} {} obj\n{}\nendobj\n",
+                    id.0,
+                    id.1,
+                    inspector.syntax(object, 0)
+                )
+            } else {
+                serde_json::to_string_pretty(&json!({
+                    "id": reference(id),
+                    "object": inspector.value(object),
+                }))?
+            }
+        }
+    })
+}
+
+/// Parses `"12"`, `"12 0"` or `"12 0 R"`. The generation defaults to 0.
+fn parse_object_id(target: &str) -> Result<ObjectId, Box<dyn std::error::Error>> {
+    let invalid =
+        || format!("Unknown target '{target}': expected trailer, xref, pages or an object number");
+    let mut parts = target.split_whitespace();
+    let number = parts
+        .next()
+        .and_then(|part| part.parse::<u32>().ok())
+        .ok_or_else(invalid)?;
+    let generation = match parts.next() {
+        Some(part) => part.parse::<u16>().map_err(|_| invalid())?,
+        None => 0,
+    };
+    match parts.next() {
+        None | Some("R") if parts.next().is_none() => Ok((number, generation)),
+        _ => Err(invalid().into()),
+    }
+}
+
+pub(crate) fn reference(id: ObjectId) -> String {
+    format!("{} {} R", id.0, id.1)
+}
+
+struct Inspector<'a> {
+    document: &'a Document,
+    max_stream_bytes: usize,
+    decode_streams: bool,
+}
+
+impl Inspector<'_> {
+    /// Converts an object to JSON.
+    fn value(&self, object: &Object) -> Value {
+        match object {
+            Object::Null => Value::Null,
+            Object::Boolean(value) => json!(value),
+            Object::Integer(value) => json!(value),
+            Object::Real(value) => json!(value),
+            Object::Name(name) => json!(format!("/{}", String::from_utf8_lossy(name))),
+            Object::String(bytes, format) => json!(string_syntax(bytes, format)),
+            Object::Reference(id) => json!(reference(*id)),
+            Object::Array(items) => {
+                Value::Array(items.iter().map(|item| self.value(item)).collect())
+            }
+            Object::Dictionary(dict) => self.dictionary_value(dict),
+            Object::Stream(stream) => {
+                let (content, length, decoded) = self.stream_content(stream);
+                let mut value = json!({
+                    "dict": self.dictionary_value(&stream.dict),
+                    "raw_length": stream.content.len(),
+                    "decoded": decoded,
+                    "length": length,
+                });
+                if let Some((text, truncated)) = content {
+                    value["content"] = json!(text);
+                    value["truncated"] = json!(truncated);
+                }
+                value
+            }
+        }
+    }
+
+    fn dictionary_value(&self, dict: &Dictionary) -> Value {
+        Value::Object(
+            dict.iter()
+                .map(|(key, value)| (String::from_utf8_lossy(key).to_string(), self.value(value)))
+                .collect::<Map<_, _>>(),
+        )
+    }
+
+    /// Writes an object in PDF syntax, with one dictionary entry per line.
+    fn syntax(&self, object: &Object, indent: usize) -> String {
+        let pad = "  ".repeat(indent);
+        match object {
+            Object::Null => "null".to_string(),
+            Object::Boolean(value) => value.to_string(),
+            Object::Integer(value) => value.to_string(),
+            Object::Real(value) => value.to_string(),
+            Object::Name(name) => format!("/{}", String::from_utf8_lossy(name)),
+            Object::String(bytes, format) => string_syntax(bytes, format),
+            Object::Reference(id) => reference(*id),
+            Object::Array(items) => format!(
+                "[{}]",
+                items
+                    .iter()
+                    .map(|item| self.syntax(item, indent))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+            Object::Dictionary(dict) => {
+                if dict.is_empty() {
+                    return "<< >>".to_string();
+                }
+                let mut text = "<<\n".to_string();
+                for (key, value) in dict.iter() {
+                    let _ = writeln!(
+                        text,
+                        "{pad}  /{} {}",
+                        String::from_utf8_lossy(key),
+                        self.syntax(value, indent + 1)
+                    );
+                }
+                text + &pad + ">>"
+            }
+            Object::Stream(stream) => {
+                let mut text = self.syntax(&Object::Dictionary(stream.dict.clone()), indent);
+                let (content, length, decoded) = self.stream_content(stream);
+                let _ = write!(
+                    text,
+                    "\nstream ({length} bytes{})",
+                    if decoded { ", decoded" } else { "" }
+                );
+                if let Some((content, truncated)) = content {
+                    let _ = write!(text, "\n{content}");
+                    if truncated {
+                        text.push_str("\n...");
+                    }
+                }
+                text + "\nendstream"
+            }
+        }
+    }
+
+    /// Returns the stream contents to show (and whether they were cut off),
+    /// the full length of the data, and whether it was decoded.
+    ///
+    /// Data that is mostly printable is shown as text, anything else as hex.
+    fn stream_content(&self, stream: &Stream) -> (Option<(String, bool)>, usize, bool) {
+        let decoded = if self.decode_streams && stream.dict.has(b"Filter") {
+            stream.decompressed_content().ok()
+        } else {
+            None
+        };
+        let is_decoded = decoded.is_some();
+        let data = decoded.unwrap_or_else(|| stream.content.clone());
+        if self.max_stream_bytes == 0 {
+            return (None, data.len(), is_decoded);
+        }
+
+        let shown = &data[..data.len().min(self.max_stream_bytes)];
+        let printable = shown
+            .iter()
+            .filter(|byte| byte.is_ascii_graphic() || byte.is_ascii_whitespace())
+            .count();
+        let content = if printable * 10 >= shown.len() * 9 {
+            String::from_utf8_lossy(shown).to_string()
+        } else {
+            shown.iter().map(|byte| format!("{byte:02x}")).collect()
+        };
+        (
+            Some((content, shown.len() < data.len())),
+            data.len(),
+            is_decoded,
+        )
+    }
+
+    fn xref_value(&self) -> Value {
+        let table = &self.document.reference_table;
+        let entries: Vec<Value> = table
+            .entries
+            .iter()
+            .map(|(id, entry)| match entry {
+                XrefEntry::Normal { offset, generation } => json!({
+                    "id": id, "type": "normal", "offset": offset, "generation": generation,
+                }),
+                XrefEntry::Compressed { container, index } => json!({
+                    "id": id, "type": "compressed", "container": container, "index": index,
+                }),
+                XrefEntry::Free | XrefEntry::UnusableFree => json!({ "id": id, "type": "free" }),
+            })
+            .collect();
+        json!({
+            "type": match table.cross_reference_type {
+                XrefType::CrossReferenceStream => "stream",
+                XrefType::CrossReferenceTable => "table",
+            },
+            "size": table.size,
+            "start": self.document.xref_start,
+            "entries": entries,
+        })
+    }
+
+    fn xref_text(&self) -> String {
+        let table = &self.document.reference_table;
+        let kind = match table.cross_reference_type {
+            XrefType::CrossReferenceStream => "stream",
+            XrefType::CrossReferenceTable => "table",
+        };
+        let mut text = format!(
+            "xref ({kind}), size {}, startxref {}\n",
+            table.size, self.document.xref_start
+        );
+        for (id, entry) in &table.entries {
+            let _ = match entry {
+                XrefEntry::Normal { offset, generation } => {
+                    writeln!(text, "{id} {generation}: offset {offset}")
+                }
+                XrefEntry::Compressed { container, index } => {
+                    writeln!(text, "{id} 0: object stream {container}, index {index}")
+                }
+                XrefEntry::Free | XrefEntry::UnusableFree => writeln!(text, "{id}: free"),
+            };
+        }
+        text
+    }
+
+    /// Walks the page tree from the catalog, numbering leaf pages in order.
+    /// Nodes already visited are reported as cycles instead of followed.
+    fn page_tree(&self) -> Vec<PageNode> {
+        let Some(root) = self
+            .document
+            .catalog()
+            .ok()
+            .and_then(|catalog| catalog.get(b"Pages").and_then(Object::as_reference).ok())
+        else {
+            return Vec::new();
+        };
+        let mut nodes = Vec::new();
+        let mut pending = vec![(root, 0)];
+        let mut visited = HashSet::new();
+        let mut page_number = 0;
+        while let Some((id, depth)) = pending.pop() {
+            let mut node = PageNode {
+                id,
+                depth,
+                kind: String::new(),
+                page_number: None,
+                attributes: Vec::new(),
+            };
+            if !visited.insert(id) {
+                node.kind = "cycle".to_string();
+                nodes.push(node);
+                continue;
+            }
+            let Ok(dict) = self.document.get_dictionary(id) else {
+                node.kind = "missing".to_string();
+                nodes.push(node);
+                continue;
+            };
+            node.kind = dict
+                .get(b"Type")
+                .and_then(Object::as_name)
+                .map(|name| String::from_utf8_lossy(name).to_string())
+                .unwrap_or_else(|_| "untyped".to_string());
+            for key in [
+                b"Count".as_slice(),
+                b"MediaBox",
+                b"CropBox",
+                b"Rotate",
+                b"Resources",
+            ] {
+                if let Ok(value) = dict.get(key) {
+                    // Inline resource dictionaries are too long for one line
+                    let value = match value {
+                        Object::Dictionary(_) => Object::Name(b"inline".to_vec()),
+                        other => other.clone(),
+                    };
+                    node.attributes
+                        .push((String::from_utf8_lossy(key).to_string(), value));
+                }
+            }
+            match dict.get(b"Kids").and_then(Object::as_array) {
+                Ok(kids) => pending.extend(
+                    kids.iter()
+                        .rev()
+                        .filter_map(|kid| kid.as_reference().ok())
+                        .map(|kid| (kid, depth + 1)),
+                ),
+                Err(_) => {
+                    page_number += 1;
+                    node.page_number = Some(page_number);
+                }
+            }
+            nodes.push(node);
+        }
+        nodes
+    }
+
+    fn page_tree_value(&self) -> Value {
+        // Rebuild the nesting from the depth-first node list
+        fn nest(inspector: &Inspector, nodes: &[PageNode], index: &mut usize) -> Value {
+            let node = &nodes[*index];
+            *index += 1;
+            let mut value = json!({ "id": reference(node.id), "type": node.kind });
+            if let Some(page) = node.page_number {
+                value["page"] = json!(page);
+            }
+            for (key, attribute) in &node.attributes {
+                value[key.as_str()] = inspector.value(attribute);
+            }
+            let mut kids = Vec::new();
+            while *index < nodes.len() && nodes[*index].depth > node.depth {
+                kids.push(nest(inspector, nodes, index));
+            }
+            if !kids.is_empty() {
+                value["kids"] = Value::Array(kids);
+            }
+            value
+        }
+
+        let nodes = self.page_tree();
+        if nodes.is_empty() {
+            return Value::Null;
+        }
+        nest(self, &nodes, &mut 0)
+    }
+
+    fn page_tree_text(&self) -> String {
+        let mut text = String::new();
+        for node in self.page_tree() {
+            let _ = write!(
+                text,
+                "{}{} {}",
+                "  ".repeat(node.depth),
+                node.kind,
+                reference(node.id)
+            );
+            if let Some(page) = node.page_number {
+                let _ = write!(text, " #{page}");
+            }
+            for (key, value) in &node.attributes {
+                let _ = write!(text, " /{key} {}", self.syntax(value, 0));
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+struct PageNode {
+    id: ObjectId,
+    depth: usize,
+    kind: String, // /Type of the node, or "missing" / "cycle"
+    page_number: Option<u32>,
+    attributes: Vec<(String, Object)>,
+}
+
+/// Writes a string as `(text)` when it decodes to printable text, otherwise
+/// as `<hex>`.
+pub(crate) fn string_syntax(bytes: &[u8], format: &StringFormat) -> String {
+    let decoded = lopdf::decode_text_string(&Object::String(bytes.to_vec(), *format));
+    match decoded {
+        Ok(text) if !text.chars().any(char::is_control) => format!(
+            "({})",
+            text.replace('\\', "\\\\")
+                .replace('(', "\\(")
+                .replace(')', "\\)")
+        ),
+        _ => format!(
+            "<{}>",
+            bytes
+                .iter()
+                .map(|byte| format!("{byte:02X}"))
+                .collect::<String>()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_pdf;
+    use tempfile::TempDir;
+
+    fn sample_pdf(dir: &TempDir) -> String {
+        let path = dir.path().join("sample.pdf").to_string_lossy().to_string();
+        create_test_pdf(2, "Inspect", "text")
+            .unwrap()
+            .save(&path)
+            .unwrap();
+        path
+    }
+
+    fn text_options() -> InspectOptions {
+        InspectOptions {
+            format: Some("text".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_inspect_structure() {
+        let dir = TempDir::new().unwrap();
+        let path = sample_pdf(&dir);
+
+        let trailer: Value =
+            serde_json::from_str(&inspect_pdf(&path, "trailer", &Default::default()).unwrap())
+                .unwrap();
+        let root = trailer["Root"].as_str().unwrap().to_string();
+        assert!(root.ends_with(" 0 R"));
+
+        let xref: Value =
+            serde_json::from_str(&inspect_pdf(&path, "xref", &Default::default()).unwrap())
+                .unwrap();
+        assert!(xref["start"].as_u64().unwrap() > 0);
+        assert!(xref["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|entry| entry["type"] == "normal" && entry["offset"].as_u64().unwrap() > 0));
+
+        let pages: Value =
+            serde_json::from_str(&inspect_pdf(&path, "pages", &Default::default()).unwrap())
+                .unwrap();
+        assert_eq!(pages["type"], "Pages");
+        assert_eq!(pages["Count"], 2);
+        let kids = pages["kids"].as_array().unwrap();
+        assert_eq!(kids.len(), 2);
+        assert_eq!(kids[1]["page"], 2);
+        assert_eq!(kids[1]["MediaBox"], json!([0, 0, 612, 792]));
+
+        let tree = inspect_pdf(&path, "pages", &text_options()).unwrap();
+        let lines: Vec<&str> = tree.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("Pages "));
+        assert!(
+            lines[2].starts_with("  Page ") && lines[2].contains(" #2 /MediaBox [0 0 612 792]")
+        );
+    }
+
+    #[test]
+    fn test_inspect_object() {
+        let dir = TempDir::new().unwrap();
+        let path = sample_pdf(&dir);
+        let document = Document::load(&path).unwrap();
+        let page_id = document.get_pages()[&1];
+        let content_id = document
+            .get_dictionary(page_id)
+            .unwrap()
+            .get(b"Contents")
+            .and_then(Object::as_reference)
+            .unwrap();
+
+        let target = format!("{} {} R", content_id.0, content_id.1);
+        let object: Value =
+            serde_json::from_str(&inspect_pdf(&path, &target, &Default::default()).unwrap())
+                .unwrap();
+        let stream = &object["object"];
+        assert!(stream["content"]
+            .as_str()
+            .unwrap()
+            .contains("(Inspect - Page 1)"));
+        assert_eq!(stream["truncated"], false);
+
+        let options = InspectOptions {
+            max_stream_bytes: Some(2),
+            ..text_options()
+        };
+        let text = inspect_pdf(&path, &content_id.0.to_string(), &options).unwrap();
+        assert!(text.starts_with(&format!("{} 0 obj\n<<", content_id.0)));
+        assert!(text.contains("\nBT\n...\nendstream\nendobj"));
+
+        let page = inspect_pdf(&path, &page_id.0.to_string(), &text_options()).unwrap();
+        assert!(page.contains("  /Type /Page\n"));
+
+        assert!(inspect_pdf(&path, "9999", &Default::default()).is_err());
+        assert!(inspect_pdf(&path, "catalog", &Default::default()).is_err());
+    }
+}
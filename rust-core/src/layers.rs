@@ -0,0 +1,748 @@
+use lopdf::content::{Content, Operation};
+use lopdf::{Dictionary, Object, ObjectId, StringFormat};
+use std::collections::HashSet;
+
+use crate::merge::resolve_dictionary;
+use crate::sign::{add_page_resource, inherited_page_attribute};
+
+/// Creates (or reuses) an optional content group with the given name and
+/// registers it in the catalog's `/OCProperties`.
+///
+/// `visibility` is one of `"always"` (default), `"print"` (print-only) or
+/// `"screen"` (screen-only). When a layer with that name already exists and a
+/// visibility is given, the layer is switched to it.
+pub(crate) fn ensure_layer(
+    document: &mut lopdf::Document,
+    name: &str,
+    visibility: Option<&str>,
+) -> lopdf::Result<ObjectId> {
+    let ocg_id = match find_layers(document, name).first() {
+        Some(existing) if visibility.is_none() => return Ok(*existing),
+        Some(existing) => *existing,
+        None => {
+            let mut ocg = Dictionary::new();
+            ocg.set("Type", Object::Name(b"OCG".to_vec()));
+            ocg.set(
+                "Name",
+                Object::String(name.as_bytes().to_vec(), StringFormat::Literal),
+            );
+            document.add_object(ocg)
+        }
+    };
+
+    // Print-only and screen-only layers rely on usage application
+    // dictionaries so viewers switch them automatically
+    let usage_states = match visibility {
+        Some("print") => Some(("OFF", "ON")),
+        Some("screen") => Some(("ON", "OFF")),
+        _ => None,
+    };
+    let ocg = document.get_dictionary_mut(ocg_id)?;
+    match usage_states {
+        Some((view_state, print_state)) => {
+            let mut view = Dictionary::new();
+            view.set("ViewState", Object::Name(view_state.into()));
+            let mut print = Dictionary::new();
+            print.set("PrintState", Object::Name(print_state.into()));
+            let mut usage = Dictionary::new();
+            usage.set("View", Object::Dictionary(view));
+            usage.set("Print", Object::Dictionary(print));
+            ocg.set("Usage", Object::Dictionary(usage));
+        }
+        None => {
+            ocg.remove(b"Usage");
+        }
+    }
+
+    let mut properties = oc_properties(document);
+    push_unique(&mut properties, "OCGs", ocg_id);
+
+    let mut config = match properties.get(b"D") {
+        Ok(Object::Dictionary(dict)) => dict.clone(),
+        Ok(Object::Reference(id)) => document.get_dictionary(*id)?.clone(),
+        _ => Dictionary::new(),
+    };
+    push_unique(&mut config, "Order", ocg_id);
+    clear_default_state(&mut config, ocg_id);
+    if usage_states.is_some() {
+        // Print-only layers start hidden for viewers that ignore /AS
+        if visibility == Some("print") {
+            push_unique(&mut config, "OFF", ocg_id);
+        }
+        add_auto_state(&mut config, "View", ocg_id);
+        add_auto_state(&mut config, "Print", ocg_id);
+    }
+    properties.set("D", Object::Dictionary(config));
+    document
+        .catalog_mut()?
+        .set("OCProperties", Object::Dictionary(properties));
+
+    // Optional content was introduced in PDF 1.5
+    if document.version.as_str() < "1.5" {
+        document.version = "1.5".to_string();
+    }
+
+    Ok(ocg_id)
+}
+
+/// Wraps operations in a `/OC` marked-content sequence for the given layer,
+/// registering the layer under the page's `/Properties` resources.
+pub(crate) fn wrap_in_layer(
+    document: &mut lopdf::Document,
+    page_id: ObjectId,
+    layer_id: ObjectId,
+    operations: Vec<Operation>,
+) -> lopdf::Result<Vec<Operation>> {
+    let property_name = format!("SoloLayer{}", layer_id.0);
+    add_page_resource(
+        document,
+        page_id,
+        "Properties",
+        &property_name,
+        Object::Reference(layer_id),
+    )?;
+
+    let mut wrapped = vec![Operation::new(
+        "BDC",
+        vec![
+            Object::Name(b"OC".to_vec()),
+            Object::Name(property_name.into_bytes()),
+        ],
+    )];
+    wrapped.extend(operations);
+    wrapped.push(Operation::new("EMC", vec![]));
+    Ok(wrapped)
+}
+
+/// Removes every optional content group named `layer_name`, along with the
+/// marked content that draws it on each page.
+///
+/// # Returns
+/// The number of pages that had layer content removed.
+pub fn remove_watermark(
+    file_path: &str,
+    layer_name: &str,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    let mut document = lopdf::Document::load(file_path)?;
+
+    let layer_ids = find_layers(&document, layer_name);
+    if layer_ids.is_empty() {
+        return Err(format!("No layer named '{layer_name}' found").into());
+    }
+
+    let pages_changed = remove_layers(&mut document, &layer_ids)?;
+    document.save(file_path)?;
+    Ok(pages_changed)
+}
+
+/// Deletes the given optional content groups and everything drawn only in
+/// them: marked content in page, form and appearance streams, XObjects and
+/// annotations whose `/OC` names one of the groups, and membership
+/// dictionaries (OCMDs) over them.
+///
+/// Content that still pointed at a deleted group would become visible, since
+/// viewers treat a missing group as on.
+///
+/// # Returns
+/// The number of pages that had layer content removed.
+pub(crate) fn remove_layers(
+    document: &mut lopdf::Document,
+    layer_ids: &[ObjectId],
+) -> lopdf::Result<u32> {
+    let mut groups: HashSet<ObjectId> = layer_ids.iter().copied().collect();
+    groups.extend(
+        document
+            .objects
+            .iter()
+            .filter(|(_, object)| {
+                object.as_dict().is_ok_and(|dict| {
+                    dict.get(b"Type").and_then(Object::as_name).ok() == Some(b"OCMD")
+                        && references_any(dict, layer_ids)
+                })
+            })
+            .map(|(id, _)| *id),
+    );
+    let hidden: HashSet<ObjectId> = document
+        .objects
+        .iter()
+        .filter(|(_, object)| {
+            let dict = match object {
+                Object::Dictionary(dict) => dict,
+                Object::Stream(stream) => &stream.dict,
+                _ => return false,
+            };
+            dict.get(b"OC")
+                .and_then(Object::as_reference)
+                .is_ok_and(|group| groups.contains(&group))
+        })
+        .map(|(id, _)| *id)
+        .collect();
+
+    let layer = HiddenLayers {
+        groups: &groups,
+        hidden: &hidden,
+    };
+    let mut pages_changed = 0;
+    let mut visited_forms = HashSet::new();
+    let page_ids: Vec<ObjectId> = document.get_pages().values().copied().collect();
+    for page_id in page_ids {
+        let resources = match inherited_page_attribute(document, page_id, b"Resources") {
+            Some(Object::Dictionary(resources)) => resources,
+            Some(Object::Reference(id)) => document.get_dictionary(id).cloned().unwrap_or_default(),
+            _ => Dictionary::new(),
+        };
+        let contents = document.get_page_contents(page_id);
+        let mut changed =
+            layer.strip_content(document, &contents, &resources, &mut visited_forms)?;
+
+        let annots = document
+            .get_dictionary(page_id)?
+            .get(b"Annots")
+            .ok()
+            .and_then(|annots| document.dereference(annots).ok())
+            .and_then(|(_, annots)| annots.as_array().ok())
+            .cloned()
+            .unwrap_or_default();
+        let mut kept = Vec::with_capacity(annots.len());
+        for annot in annots {
+            let id = annot.as_reference().ok();
+            let Some(dict) = resolve_dictionary(document, Some(&annot)).cloned() else {
+                kept.push(annot);
+                continue;
+            };
+            // Popups go with the annotation they belong to
+            let parent = dict.get(b"Parent").and_then(Object::as_reference).ok();
+            let is_hidden = id.is_some_and(|id| hidden.contains(&id))
+                || parent.is_some_and(|parent| hidden.contains(&parent))
+                || dict
+                    .get(b"OC")
+                    .and_then(Object::as_reference)
+                    .is_ok_and(|group| groups.contains(&group));
+            if is_hidden {
+                changed = true;
+                continue;
+            }
+            for form_id in appearance_streams(document, &dict) {
+                let form_resources = document
+                    .get_object(form_id)
+                    .and_then(Object::as_stream)
+                    .ok()
+                    .and_then(|form| resolve_dictionary(document, form.dict.get(b"Resources").ok()))
+                    .cloned()
+                    .unwrap_or_default();
+                if visited_forms.insert(form_id) {
+                    changed |= layer.strip_content(
+                        document,
+                        &[form_id],
+                        &form_resources,
+                        &mut visited_forms,
+                    )?;
+                }
+            }
+            kept.push(annot);
+        }
+        if changed {
+            if document.get_dictionary(page_id)?.has(b"Annots") {
+                document
+                    .get_dictionary_mut(page_id)?
+                    .set("Annots", Object::Array(kept));
+            }
+            pages_changed += 1;
+        }
+    }
+
+    unregister_layers(document, layer_ids)?;
+    let removed: HashSet<ObjectId> = groups.union(&hidden).copied().collect();
+    for id in &removed {
+        document.objects.remove(id);
+    }
+    // Resource entries and annotation lists that named removed objects
+    for object in document.objects.values_mut() {
+        drop_references(object, &removed);
+    }
+    Ok(pages_changed)
+}
+
+/// The groups being removed and the objects drawn only in them.
+struct HiddenLayers<'a> {
+    groups: &'a HashSet<ObjectId>,
+    hidden: &'a HashSet<ObjectId>,
+}
+
+impl HiddenLayers<'_> {
+    /// Removes marked content in a removed group and `Do` operators drawing
+    /// hidden XObjects from content streams, then does the same in the forms
+    /// they use. Returns whether anything was removed.
+    fn strip_content(
+        &self,
+        document: &mut lopdf::Document,
+        stream_ids: &[ObjectId],
+        resources: &Dictionary,
+        visited_forms: &mut HashSet<ObjectId>,
+    ) -> lopdf::Result<bool> {
+        let names_of = |category: &[u8], removed: &HashSet<ObjectId>| -> Vec<Vec<u8>> {
+            resolve_dictionary(document, resources.get(category).ok())
+                .map(|entries| {
+                    entries
+                        .iter()
+                        .filter(|(_, value)| {
+                            value.as_reference().is_ok_and(|id| removed.contains(&id))
+                        })
+                        .map(|(name, _)| name.clone())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let property_names = names_of(b"Properties", self.groups);
+        let xobject_names = names_of(b"XObject", self.hidden);
+        let forms: Vec<ObjectId> = resolve_dictionary(document, resources.get(b"XObject").ok())
+            .map(|xobjects| {
+                xobjects
+                    .iter()
+                    .filter_map(|(_, value)| value.as_reference().ok())
+                    .filter(|id| !self.hidden.contains(id))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut changed = false;
+        if !property_names.is_empty() || !xobject_names.is_empty() {
+            // Marked content may in theory span streams, so the skip depth is
+            // carried from one content stream to the next
+            let mut skip_depth = 0usize;
+            for stream_id in stream_ids {
+                let Ok(stream) = document.get_object(*stream_id).and_then(Object::as_stream) else {
+                    continue;
+                };
+                let data = stream
+                    .decompressed_content()
+                    .unwrap_or_else(|_| stream.content.clone());
+                let content = Content::decode(&data)?;
+
+                let mut removed_any = false;
+                let mut kept = Vec::with_capacity(content.operations.len());
+                for operation in content.operations {
+                    if skip_depth > 0 {
+                        removed_any = true;
+                        match operation.operator.as_str() {
+                            "BDC" | "BMC" => skip_depth += 1,
+                            "EMC" => skip_depth -= 1,
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    if is_layer_start(&operation, &property_names) {
+                        removed_any = true;
+                        skip_depth = 1;
+                        continue;
+                    }
+                    if operation.operator == "Do"
+                        && operation
+                            .operands
+                            .first()
+                            .and_then(|name| name.as_name().ok())
+                            .is_some_and(|name| xobject_names.iter().any(|hidden| hidden == name))
+                    {
+                        removed_any = true;
+                        continue;
+                    }
+                    kept.push(operation);
+                }
+
+                if removed_any {
+                    let data = Content { operations: kept }.encode()?;
+                    document.change_content_stream(*stream_id, data);
+                    changed = true;
+                }
+            }
+        }
+
+        for form_id in forms {
+            if !visited_forms.insert(form_id) {
+                continue;
+            }
+            let Ok(form) = document.get_object(form_id).and_then(Object::as_stream) else {
+                continue;
+            };
+            if form.dict.get(b"Subtype").and_then(Object::as_name).ok() != Some(b"Form") {
+                continue;
+            }
+            // Forms without their own resources use those of the page
+            let form_resources = resolve_dictionary(document, form.dict.get(b"Resources").ok())
+                .cloned()
+                .unwrap_or_else(|| resources.clone());
+            changed |= self.strip_content(document, &[form_id], &form_resources, visited_forms)?;
+        }
+        Ok(changed)
+    }
+}
+
+/// Returns the appearance streams of an annotation, for every appearance
+/// type and state.
+fn appearance_streams(document: &lopdf::Document, annot: &Dictionary) -> Vec<ObjectId> {
+    let Some(appearances) = resolve_dictionary(document, annot.get(b"AP").ok()) else {
+        return Vec::new();
+    };
+    let mut streams = Vec::new();
+    for (_, appearance) in appearances.iter() {
+        let Ok(id) = appearance.as_reference() else {
+            if let Object::Dictionary(states) = appearance {
+                streams.extend(
+                    states
+                        .iter()
+                        .filter_map(|(_, state)| state.as_reference().ok()),
+                );
+            }
+            continue;
+        };
+        match document.get_object(id) {
+            Ok(Object::Dictionary(states)) => streams.extend(
+                states
+                    .iter()
+                    .filter_map(|(_, state)| state.as_reference().ok()),
+            ),
+            Ok(_) => streams.push(id),
+            Err(_) => {}
+        }
+    }
+    streams
+}
+
+fn references_any(dict: &Dictionary, ids: &[ObjectId]) -> bool {
+    fn walk(object: &Object, ids: &[ObjectId]) -> bool {
+        match object {
+            Object::Reference(id) => ids.contains(id),
+            Object::Array(items) => items.iter().any(|item| walk(item, ids)),
+            _ => false,
+        }
+    }
+    // /OCGs is a group or an array of them; /VE nests arrays of groups
+    [b"OCGs".as_slice(), b"VE"]
+        .iter()
+        .filter_map(|key| dict.get(key).ok())
+        .any(|value| walk(value, ids))
+}
+
+/// Removes dictionary entries and array items that refer to removed
+/// objects, leaving no reference to a deleted group or its content.
+fn drop_references(object: &mut Object, removed: &HashSet<ObjectId>) {
+    let is_removed = |value: &Object| value.as_reference().is_ok_and(|id| removed.contains(&id));
+    match object {
+        Object::Array(items) => {
+            items.retain(|item| !is_removed(item));
+            items
+                .iter_mut()
+                .for_each(|item| drop_references(item, removed));
+        }
+        Object::Dictionary(dict) => drop_dictionary_references(dict, removed),
+        Object::Stream(stream) => drop_dictionary_references(&mut stream.dict, removed),
+        _ => {}
+    }
+}
+
+fn drop_dictionary_references(dict: &mut Dictionary, removed: &HashSet<ObjectId>) {
+    let keys: Vec<Vec<u8>> = dict
+        .iter()
+        .filter(|(_, value)| value.as_reference().is_ok_and(|id| removed.contains(&id)))
+        .map(|(key, _)| key.clone())
+        .collect();
+    for key in keys {
+        dict.remove(&key);
+    }
+    dict.iter_mut()
+        .for_each(|(_, value)| drop_references(value, removed));
+}
+
+/// Returns the ids of all optional content groups with the given name.
+pub(crate) fn find_layers(document: &lopdf::Document, name: &str) -> Vec<ObjectId> {
+    let properties = oc_properties(document);
+    let Ok(ocgs) = properties.get(b"OCGs").and_then(Object::as_array) else {
+        return Vec::new();
+    };
+
+    ocgs.iter()
+        .filter_map(|ocg| ocg.as_reference().ok())
+        .filter(|id| {
+            document
+                .get_dictionary(*id)
+                .and_then(|ocg| ocg.get(b"Name"))
+                .and_then(Object::as_str)
+                .is_ok_and(|ocg_name| ocg_name == name.as_bytes())
+        })
+        .collect()
+}
+
+/// Returns a copy of the catalog's `/OCProperties` dictionary, or an empty
+/// dictionary when the document has no optional content.
+pub(crate) fn oc_properties(document: &lopdf::Document) -> Dictionary {
+    let Ok(catalog) = document.catalog() else {
+        return Dictionary::new();
+    };
+    match catalog.get(b"OCProperties") {
+        Ok(Object::Dictionary(dict)) => dict.clone(),
+        Ok(Object::Reference(id)) => document.get_dictionary(*id).cloned().unwrap_or_default(),
+        _ => Dictionary::new(),
+    }
+}
+
+fn is_layer_start(operation: &Operation, property_names: &[Vec<u8>]) -> bool {
+    operation.operator == "BDC"
+        && operation.operands.len() == 2
+        && operation.operands[0].as_name().ok() == Some(b"OC")
+        && operation.operands[1]
+            .as_name()
+            .is_ok_and(|name| property_names.iter().any(|p| p == name))
+}
+
+fn push_unique(dict: &mut Dictionary, key: &str, id: ObjectId) {
+    let mut entries = dict
+        .get(key.as_bytes())
+        .and_then(Object::as_array)
+        .cloned()
+        .unwrap_or_default();
+    if !entries.contains(&Object::Reference(id)) {
+        entries.push(Object::Reference(id));
+    }
+    dict.set(key, Object::Array(entries));
+}
+
+/// Adds the layer to the `/AS` auto-state entry for the given usage event,
+/// creating the entry when it is missing.
+fn add_auto_state(config: &mut Dictionary, event: &str, id: ObjectId) {
+    let mut auto_states = config
+        .get(b"AS")
+        .and_then(Object::as_array)
+        .cloned()
+        .unwrap_or_default();
+
+    let existing = auto_states.iter_mut().find_map(|state| match state {
+        Object::Dictionary(dict)
+            if dict.get(b"Event").and_then(Object::as_name).ok() == Some(event.as_bytes()) =>
+        {
+            Some(dict)
+        }
+        _ => None,
+    });
+
+    match existing {
+        Some(dict) => push_unique(dict, "OCGs", id),
+        None => {
+            let mut dict = Dictionary::new();
+            dict.set("Event", Object::Name(event.into()));
+            dict.set("Category", Object::Array(vec![Object::Name(event.into())]));
+            dict.set("OCGs", Object::Array(vec![Object::Reference(id)]));
+            auto_states.push(Object::Dictionary(dict));
+        }
+    }
+    config.set("AS", Object::Array(auto_states));
+}
+
+/// Takes the layer out of the default configuration's `/OFF` array and its
+/// `/AS` auto-state entries, so its visibility can be set afresh.
+fn clear_default_state(config: &mut Dictionary, id: ObjectId) {
+    let reference = Object::Reference(id);
+    if let Ok(Object::Array(off)) = config.get_mut(b"OFF") {
+        off.retain(|entry| *entry != reference);
+    }
+    if let Ok(Object::Array(auto_states)) = config.get_mut(b"AS") {
+        for state in auto_states.iter_mut() {
+            if let Object::Dictionary(dict) = state {
+                if let Ok(Object::Array(ocgs)) = dict.get_mut(b"OCGs") {
+                    ocgs.retain(|entry| *entry != reference);
+                }
+            }
+        }
+    }
+}
+
+/// Drops the given layers from `/OCProperties`, removing the dictionary
+/// entirely when no layers remain.
+fn unregister_layers(document: &mut lopdf::Document, layer_ids: &[ObjectId]) -> lopdf::Result<()> {
+    let retain = |array: &mut Vec<Object>| {
+        array.retain(|entry| {
+            entry
+                .as_reference()
+                .map_or(true, |id| !layer_ids.contains(&id))
+        })
+    };
+
+    let mut properties = oc_properties(document);
+    if let Ok(Object::Array(ocgs)) = properties.get_mut(b"OCGs") {
+        retain(ocgs);
+        if ocgs.is_empty() {
+            document.catalog_mut()?.remove(b"OCProperties");
+            return Ok(());
+        }
+    }
+
+    let mut config = match properties.get(b"D") {
+        Ok(Object::Dictionary(dict)) => dict.clone(),
+        Ok(Object::Reference(id)) => document.get_dictionary(*id)?.clone(),
+        _ => Dictionary::new(),
+    };
+    for key in [b"Order".as_slice(), b"ON", b"OFF", b"Locked"] {
+        if let Ok(Object::Array(array)) = config.get_mut(key) {
+            retain(array);
+        }
+    }
+    if let Ok(Object::Array(auto_states)) = config.get_mut(b"AS") {
+        for state in auto_states.iter_mut() {
+            if let Object::Dictionary(dict) = state {
+                if let Ok(Object::Array(ocgs)) = dict.get_mut(b"OCGs") {
+                    retain(ocgs);
+                }
+            }
+        }
+    }
+    properties.set("D", Object::Dictionary(config));
+    document
+        .catalog_mut()?
+        .set("OCProperties", Object::Dictionary(properties));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sign::sign_pdf_with_options;
+    use crate::test_utils::create_test_pdf;
+    use crate::SigningOptions;
+    use tempfile::NamedTempFile;
+
+    fn layered_options(layout: Option<&str>, visibility: &str) -> SigningOptions {
+        SigningOptions {
+            font_size: Some(12.0),
+            color: None,
+            x_position: None,
+            y_position: None,
+            pages: None,
+            page_range: None,
+            position: None,
+            rotation: None,
+            opacity: None,
+            layout: layout.map(str::to_string),
+            tile_spacing: None,
+            placement: None,
+            layer_name: Some("Watermark".to_string()),
+            layer_visibility: Some(visibility.to_string()),
+        }
+    }
+
+    fn saved_test_pdf(pages: u32) -> (NamedTempFile, String) {
+        let mut doc = create_test_pdf(pages, "test", "content").unwrap();
+        let temp_file = NamedTempFile::new().unwrap();
+        let path = temp_file.path().to_string_lossy().to_string();
+        doc.save(&path).unwrap();
+        (temp_file, path)
+    }
+
+    #[test]
+    fn test_watermark_is_registered_as_layer() {
+        let (_temp_file, path) = saved_test_pdf(2);
+        sign_pdf_with_options(
+            path.clone(),
+            "DRAFT".to_string(),
+            Some(layered_options(Some("diagonal"), "print")),
+        )
+        .unwrap();
+
+        let doc = lopdf::Document::load(&path).unwrap();
+        let layers = find_layers(&doc, "Watermark");
+        assert_eq!(layers.len(), 1);
+
+        let config = oc_properties(&doc);
+        let config = config.get(b"D").unwrap().as_dict().unwrap();
+        let off = config.get(b"OFF").unwrap().as_array().unwrap();
+        assert!(off.contains(&Object::Reference(layers[0])));
+
+        let page_id = *doc.get_pages().get(&1).unwrap();
+        let content = String::from_utf8_lossy(&doc.get_page_content(page_id).unwrap()).to_string();
+        assert!(content.contains("/OC /SoloLayer"));
+        assert!(content.contains("EMC"));
+    }
+
+    #[test]
+    fn test_remove_watermark_strips_layer_content() {
+        let (_temp_file, path) = saved_test_pdf(3);
+        sign_pdf_with_options(
+            path.clone(),
+            "DRAFT".to_string(),
+            Some(layered_options(None, "always")),
+        )
+        .unwrap();
+
+        let removed = remove_watermark(&path, "Watermark").unwrap();
+        assert_eq!(removed, 3);
+
+        let doc = lopdf::Document::load(&path).unwrap();
+        assert!(find_layers(&doc, "Watermark").is_empty());
+        for page_id in doc.get_pages().values() {
+            let content = doc.get_page_content(*page_id).unwrap();
+            let content = String::from_utf8_lossy(&content);
+            assert!(!content.contains("DRAFT"));
+            assert!(content.contains("Page"));
+        }
+    }
+
+    #[test]
+    fn test_remove_unknown_layer_fails() {
+        let (_temp_file, path) = saved_test_pdf(1);
+        assert!(remove_watermark(&path, "Missing").is_err());
+    }
+
+    #[test]
+    fn test_existing_layer_takes_the_requested_visibility() {
+        let (_temp_file, path) = saved_test_pdf(1);
+        let layer_state = |path: &str| {
+            let doc = lopdf::Document::load(path).unwrap();
+            let layers = find_layers(&doc, "Watermark");
+            assert_eq!(layers.len(), 1);
+            let reference = Object::Reference(layers[0]);
+            let print_state = doc
+                .get_dictionary(layers[0])
+                .unwrap()
+                .get(b"Usage")
+                .and_then(Object::as_dict)
+                .and_then(|usage| usage.get(b"Print"))
+                .and_then(Object::as_dict)
+                .and_then(|print| print.get(b"PrintState"))
+                .and_then(Object::as_name)
+                .map(|state| String::from_utf8_lossy(state).to_string())
+                .ok();
+            let properties = oc_properties(&doc);
+            let config = properties.get(b"D").unwrap().as_dict().unwrap();
+            let hidden = config
+                .get(b"OFF")
+                .and_then(Object::as_array)
+                .is_ok_and(|off| off.contains(&reference));
+            let auto_states = config
+                .get(b"AS")
+                .and_then(Object::as_array)
+                .map(|states| {
+                    states
+                        .iter()
+                        .filter_map(|state| {
+                            state.as_dict().ok()?.get(b"OCGs").ok()?.as_array().ok()
+                        })
+                        .map(|ocgs| ocgs.iter().filter(|ocg| **ocg == reference).count())
+                        .sum::<usize>()
+                })
+                .unwrap_or(0);
+            (print_state, hidden, auto_states)
+        };
+
+        for (visibility, expected) in [
+            ("screen", (Some("OFF".to_string()), false, 2)),
+            ("print", (Some("ON".to_string()), true, 2)),
+            ("always", (None, false, 0)),
+        ] {
+            sign_pdf_with_options(
+                path.clone(),
+                "DRAFT".to_string(),
+                Some(layered_options(None, visibility)),
+            )
+            .unwrap();
+            assert_eq!(layer_state(&path), expected, "{visibility}");
+        }
+    }
+}
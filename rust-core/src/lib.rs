@@ -1,17 +1,54 @@
 use napi_derive::napi;
 
 // Declare modules
+pub mod barcode;
+pub mod bates;
 #[cfg(feature = "crypto")]
 pub mod crypto;
+pub mod diff;
+pub mod fonts;
+pub mod images;
+pub mod impose;
+pub mod info;
+pub mod inspect;
+pub mod layers;
+pub mod merge;
+pub mod metadata;
+pub mod overlay;
 #[path = "page-count.rs"]
 pub mod page_count;
+#[path = "page-edit.rs"]
+pub mod page_edit;
+#[path = "page-range.rs"]
+pub mod page_range;
+pub mod repair;
+pub mod resize;
+pub mod revisions;
+pub mod rotate;
 pub mod sign;
+pub mod split;
+pub mod stamp;
+pub mod validate;
+pub mod watermark;
 
 #[cfg(feature = "crypto")]
 pub use crypto::*;
 
 // Re-export SigningOptions from sign module
-pub use sign::SigningOptions;
+pub use barcode::BarcodeOptions;
+pub use bates::BatesOptions;
+pub use diff::DiffOptions;
+pub use impose::ImposeOptions;
+pub use inspect::InspectOptions;
+pub use merge::{CollateOptions, MergeOptions};
+pub use metadata::DocumentMetadata;
+pub use overlay::OverlayOptions;
+pub use page_edit::BlankPageOptions;
+pub use resize::{PageBoxOptions, ScaleOptions};
+pub use rotate::RotateOptions;
+pub use sign::{SanitizeOptions, SigningOptions};
+pub use split::SplitOptions;
+pub use stamp::{HeaderFooterOptions, RunningText};
 
 // Test utilities module for internal use
 #[cfg(any(test, feature = "test-utils"))]
@@ -23,6 +60,14 @@ pub fn get_page_count(file_path: String) -> napi::Result<u32> {
     page_count::get_page_count(file_path)
 }
 
+/// Resolve a page-range expression (e.g. "1-3,7,10-", "odd", "r1", "!2")
+/// to 1-based page numbers
+#[napi]
+pub fn parse_page_range(expression: String, total_pages: u32) -> napi::Result<Vec<u32>> {
+    page_range::parse_page_range(&expression, total_pages)
+        .map_err(|e| napi::Error::from_reason(format!("Invalid page range: {e}")))
+}
+
 /// Generate a new cryptographic key pair for digital signing
 #[cfg(feature = "crypto")]
 #[napi]
@@ -122,6 +167,430 @@ pub fn sign_pdf_with_options(
     sign::sign_pdf_with_options(file_path, signature_text, options)
 }
 
+/// Stamp running headers and footers with page number templates
+#[napi]
+pub fn add_header_footer(file_path: String, options: HeaderFooterOptions) -> napi::Result<u32> {
+    stamp::add_header_footer(&file_path, &options)
+        .map_err(|e| napi::Error::from_reason(format!("Failed to add header/footer: {e}")))
+}
+
+/// Draw a QR code or Code 128 barcode on a page and return the encoded payload
+#[napi]
+pub fn stamp_barcode(
+    input_path: String,
+    output_path: String,
+    options: BarcodeOptions,
+) -> napi::Result<String> {
+    barcode::stamp_barcode(&input_path, &output_path, &options)
+        .map_err(|e| napi::Error::from_reason(format!("Barcode stamping failed: {e}")))
+}
+
+/// Stamp Bates numbers across a set of PDFs and return the manifest as JSON
+#[napi]
+pub fn stamp_bates_numbers(
+    input_paths: Vec<String>,
+    options: Option<BatesOptions>,
+) -> napi::Result<String> {
+    let manifest = bates::stamp_bates_numbers(&input_paths, &options.unwrap_or_default())
+        .map_err(|e| napi::Error::from_reason(format!("Bates numbering failed: {e}")))?;
+
+    serde_json::to_string_pretty(&manifest)
+        .map_err(|e| napi::Error::from_reason(format!("Serialization failed: {e}")))
+}
+
+/// Merge PDFs into one document and return a summary as JSON
+#[napi]
+pub fn merge_pdfs(
+    input_paths: Vec<String>,
+    output_path: String,
+    options: Option<MergeOptions>,
+) -> napi::Result<String> {
+    let summary = merge::merge_pdfs(&input_paths, &output_path, &options.unwrap_or_default())
+        .map_err(|e| napi::Error::from_reason(format!("Merge failed: {e}")))?;
+
+    serde_json::to_string_pretty(&summary)
+        .map_err(|e| napi::Error::from_reason(format!("Serialization failed: {e}")))
+}
+
+/// Split a PDF into several files and return the written parts as JSON
+#[napi]
+pub fn split_pdf(input_path: String, options: Option<SplitOptions>) -> napi::Result<String> {
+    let parts = split::split_pdf(&input_path, &options.unwrap_or_default())
+        .map_err(|e| napi::Error::from_reason(format!("Split failed: {e}")))?;
+
+    serde_json::to_string_pretty(&parts)
+        .map_err(|e| napi::Error::from_reason(format!("Serialization failed: {e}")))
+}
+
+/// Copy the pages selected by a page-range expression into a new file
+#[napi]
+pub fn extract_pages(
+    input_path: String,
+    output_path: String,
+    page_range: String,
+) -> napi::Result<u32> {
+    page_edit::extract_pages(&input_path, &output_path, &page_range)
+        .map_err(|e| napi::Error::from_reason(format!("Failed to extract pages: {e}")))
+}
+
+/// Remove the pages selected by a page-range expression
+#[napi]
+pub fn delete_pages(
+    input_path: String,
+    output_path: String,
+    page_range: String,
+) -> napi::Result<u32> {
+    page_edit::delete_pages(&input_path, &output_path, &page_range)
+        .map_err(|e| napi::Error::from_reason(format!("Failed to delete pages: {e}")))
+}
+
+/// Move the listed pages to the front in the given order
+#[napi]
+pub fn reorder_pages(
+    input_path: String,
+    output_path: String,
+    page_order: String,
+) -> napi::Result<u32> {
+    page_edit::reorder_pages(&input_path, &output_path, &page_order)
+        .map_err(|e| napi::Error::from_reason(format!("Failed to reorder pages: {e}")))
+}
+
+/// Insert copies of the selected pages right after each of them
+#[napi]
+pub fn duplicate_pages(
+    input_path: String,
+    output_path: String,
+    page_range: String,
+    copies: Option<u32>,
+) -> napi::Result<u32> {
+    page_edit::duplicate_pages(&input_path, &output_path, &page_range, copies)
+        .map_err(|e| napi::Error::from_reason(format!("Failed to duplicate pages: {e}")))
+}
+
+/// Insert pages from another PDF after the first `at_index` pages of the target
+#[napi]
+pub fn insert_pages(
+    target_path: String,
+    source_path: String,
+    output_path: String,
+    at_index: u32,
+    source_range: Option<String>,
+) -> napi::Result<u32> {
+    page_edit::insert_pages(
+        &target_path,
+        &source_path,
+        &output_path,
+        at_index,
+        source_range.as_deref(),
+    )
+    .map_err(|e| napi::Error::from_reason(format!("Failed to insert pages: {e}")))
+}
+
+/// Insert blank pages before the given 1-based positions
+#[napi]
+pub fn insert_blank_pages(
+    input_path: String,
+    output_path: String,
+    positions: Vec<u32>,
+    options: Option<BlankPageOptions>,
+) -> napi::Result<u32> {
+    page_edit::insert_blank_pages(
+        &input_path,
+        &output_path,
+        &positions,
+        &options.unwrap_or_default(),
+    )
+    .map_err(|e| napi::Error::from_reason(format!("Failed to insert blank pages: {e}")))
+}
+
+/// Rotate pages clockwise by a multiple of 90 degrees (or set their rotation)
+#[napi]
+pub fn rotate_pages(
+    input_path: String,
+    output_path: String,
+    angle: i32,
+    options: Option<RotateOptions>,
+) -> napi::Result<u32> {
+    rotate::rotate_pages(
+        &input_path,
+        &output_path,
+        angle,
+        &options.unwrap_or_default(),
+    )
+    .map_err(|e| napi::Error::from_reason(format!("Failed to rotate pages: {e}")))
+}
+
+/// Bake page rotation into the content so every page has /Rotate 0
+#[napi]
+pub fn normalize_rotation(
+    input_path: String,
+    output_path: String,
+    page_range: Option<String>,
+) -> napi::Result<u32> {
+    rotate::normalize_rotation(&input_path, &output_path, page_range.as_deref())
+        .map_err(|e| napi::Error::from_reason(format!("Failed to normalize rotation: {e}")))
+}
+
+/// Set MediaBox, CropBox, BleedBox, TrimBox or ArtBox on selected pages
+#[napi]
+pub fn set_page_boxes(
+    input_path: String,
+    output_path: String,
+    options: PageBoxOptions,
+) -> napi::Result<u32> {
+    resize::set_page_boxes(&input_path, &output_path, &options)
+        .map_err(|e| napi::Error::from_reason(format!("Failed to set page boxes: {e}")))
+}
+
+/// Crop pages to the area their content covers
+#[napi]
+pub fn auto_crop(
+    input_path: String,
+    output_path: String,
+    page_range: Option<String>,
+    margin: Option<f64>,
+) -> napi::Result<u32> {
+    resize::auto_crop(&input_path, &output_path, page_range.as_deref(), margin)
+        .map_err(|e| napi::Error::from_reason(format!("Failed to crop pages: {e}")))
+}
+
+/// Scale pages onto a new paper size without rasterizing them
+#[napi]
+pub fn scale_pages(
+    input_path: String,
+    output_path: String,
+    options: Option<ScaleOptions>,
+) -> napi::Result<u32> {
+    resize::scale_pages(&input_path, &output_path, &options.unwrap_or_default())
+        .map_err(|e| napi::Error::from_reason(format!("Failed to scale pages: {e}")))
+}
+
+/// Place 2, 4, 6 or 9 pages on each sheet
+#[napi]
+pub fn n_up(
+    input_path: String,
+    output_path: String,
+    per_sheet: u32,
+    options: Option<ImposeOptions>,
+) -> napi::Result<u32> {
+    impose::n_up(
+        &input_path,
+        &output_path,
+        per_sheet,
+        &options.unwrap_or_default(),
+    )
+    .map_err(|e| napi::Error::from_reason(format!("N-up imposition failed: {e}")))
+}
+
+/// Lay pages out two per side in saddle-stitch booklet order
+#[napi]
+pub fn booklet(
+    input_path: String,
+    output_path: String,
+    options: Option<ImposeOptions>,
+) -> napi::Result<u32> {
+    impose::booklet(&input_path, &output_path, &options.unwrap_or_default())
+        .map_err(|e| napi::Error::from_reason(format!("Booklet imposition failed: {e}")))
+}
+
+/// Draw a page of a template PDF over or under selected pages
+#[napi]
+pub fn overlay_pdf(
+    input_path: String,
+    template_path: String,
+    output_path: String,
+    options: Option<OverlayOptions>,
+) -> napi::Result<u32> {
+    overlay::overlay_pdf(
+        &input_path,
+        &template_path,
+        &output_path,
+        &options.unwrap_or_default(),
+    )
+    .map_err(|e| napi::Error::from_reason(format!("Overlay failed: {e}")))
+}
+
+/// Interleave the pages of two PDFs, e.g. scanned fronts and backs
+#[napi]
+pub fn collate(
+    first_path: String,
+    second_path: String,
+    output_path: String,
+    options: Option<CollateOptions>,
+) -> napi::Result<u32> {
+    merge::collate(
+        &first_path,
+        &second_path,
+        &output_path,
+        &options.unwrap_or_default(),
+    )
+    .map_err(|e| napi::Error::from_reason(format!("Collate failed: {e}")))
+}
+
+/// Remove a watermark layer (optional content group) from every page
+#[napi]
+pub fn remove_watermark(file_path: String, layer_name: String) -> napi::Result<u32> {
+    layers::remove_watermark(&file_path, &layer_name)
+        .map_err(|e| napi::Error::from_reason(format!("Failed to remove watermark: {e}")))
+}
+
+/// Strip metadata, scripts, attachments, hidden layers and chosen annotation
+/// types; returns a report of what was removed as JSON
+#[napi]
+pub fn sanitize(
+    input_path: String,
+    output_path: String,
+    options: Option<SanitizeOptions>,
+) -> napi::Result<String> {
+    let report = sign::sanitize(&input_path, &output_path, &options.unwrap_or_default())
+        .map_err(|e| napi::Error::from_reason(format!("Sanitize failed: {e}")))?;
+
+    serde_json::to_string_pretty(&report)
+        .map_err(|e| napi::Error::from_reason(format!("Serialization failed: {e}")))
+}
+
+/// Describe a PDF (version, metadata, pages, security, forms, conformance
+/// claims) as JSON
+#[napi]
+pub fn get_pdf_info(file_path: String) -> napi::Result<String> {
+    let info = info::get_pdf_info(&file_path)
+        .map_err(|e| napi::Error::from_reason(format!("Failed to read PDF info: {e}")))?;
+
+    serde_json::to_string_pretty(&info)
+        .map_err(|e| napi::Error::from_reason(format!("Serialization failed: {e}")))
+}
+
+/// Read Title, Author, dates and custom properties as JSON
+#[napi]
+pub fn get_metadata(file_path: String) -> napi::Result<String> {
+    let metadata = metadata::get_metadata(&file_path)
+        .map_err(|e| napi::Error::from_reason(format!("Failed to read metadata: {e}")))?;
+
+    serde_json::to_string_pretty(&metadata)
+        .map_err(|e| napi::Error::from_reason(format!("Serialization failed: {e}")))
+}
+
+/// Update the Info dictionary and XMP metadata together; returns the
+/// resulting metadata as JSON
+#[napi]
+pub fn set_metadata(
+    input_path: String,
+    output_path: String,
+    metadata: DocumentMetadata,
+) -> napi::Result<String> {
+    let written = metadata::set_metadata(&input_path, &output_path, &metadata)
+        .map_err(|e| napi::Error::from_reason(format!("Failed to set metadata: {e}")))?;
+
+    serde_json::to_string_pretty(&written)
+        .map_err(|e| napi::Error::from_reason(format!("Serialization failed: {e}")))
+}
+
+/// List the fonts used on each page with their type, embedding and encoding
+/// as JSON
+#[napi]
+pub fn list_fonts(file_path: String) -> napi::Result<String> {
+    let fonts = fonts::list_fonts(&file_path)
+        .map_err(|e| napi::Error::from_reason(format!("Failed to list fonts: {e}")))?;
+
+    serde_json::to_string_pretty(&fonts)
+        .map_err(|e| napi::Error::from_reason(format!("Serialization failed: {e}")))
+}
+
+/// List the image XObjects used on each page as JSON
+#[napi]
+pub fn list_images(file_path: String) -> napi::Result<String> {
+    let images = images::list_images(&file_path)
+        .map_err(|e| napi::Error::from_reason(format!("Failed to list images: {e}")))?;
+
+    serde_json::to_string_pretty(&images)
+        .map_err(|e| napi::Error::from_reason(format!("Serialization failed: {e}")))
+}
+
+/// Write the images used on the selected pages to a directory; returns the
+/// written and skipped images as JSON
+#[napi]
+pub fn extract_images(
+    input_path: String,
+    output_dir: String,
+    page_range: Option<String>,
+) -> napi::Result<String> {
+    let extraction = images::extract_images(&input_path, &output_dir, page_range.as_deref())
+        .map_err(|e| napi::Error::from_reason(format!("Failed to extract images: {e}")))?;
+
+    serde_json::to_string_pretty(&extraction)
+        .map_err(|e| napi::Error::from_reason(format!("Serialization failed: {e}")))
+}
+
+/// Dump the trailer, xref table, page tree or a single object as JSON or text
+#[napi]
+pub fn inspect_pdf(
+    file_path: String,
+    target: String,
+    options: Option<InspectOptions>,
+) -> napi::Result<String> {
+    inspect::inspect_pdf(&file_path, &target, &options.unwrap_or_default())
+        .map_err(|e| napi::Error::from_reason(format!("Inspect failed: {e}")))
+}
+
+/// Check the structure of a PDF and report findings with severities as JSON
+#[napi]
+pub fn validate_pdf(file_path: String) -> napi::Result<String> {
+    let report = validate::validate_pdf(&file_path)
+        .map_err(|e| napi::Error::from_reason(format!("Validation failed: {e}")))?;
+
+    serde_json::to_string_pretty(&report)
+        .map_err(|e| napi::Error::from_reason(format!("Serialization failed: {e}")))
+}
+
+/// Compare the structure of two PDFs (or two revisions of one) and return
+/// the changes as JSON
+#[napi]
+pub fn diff_pdfs(
+    old_path: String,
+    new_path: String,
+    options: Option<DiffOptions>,
+) -> napi::Result<String> {
+    let diff = diff::diff_pdfs(&old_path, &new_path, &options.unwrap_or_default())
+        .map_err(|e| napi::Error::from_reason(format!("Diff failed: {e}")))?;
+
+    serde_json::to_string_pretty(&diff)
+        .map_err(|e| napi::Error::from_reason(format!("Serialization failed: {e}")))
+}
+
+/// List the original file and each incremental update with the objects it
+/// changed and the signatures covering it, as JSON
+#[napi]
+pub fn list_revisions(file_path: String) -> napi::Result<String> {
+    let revisions = revisions::list_revisions(&file_path)
+        .map_err(|e| napi::Error::from_reason(format!("Failed to list revisions: {e}")))?;
+
+    serde_json::to_string_pretty(&revisions)
+        .map_err(|e| napi::Error::from_reason(format!("Serialization failed: {e}")))
+}
+
+/// Write the file exactly as it was at a revision (1 is the original);
+/// returns its page count
+#[napi]
+pub fn extract_revision(
+    file_path: String,
+    revision: u32,
+    output_path: String,
+) -> napi::Result<u32> {
+    revisions::extract_revision(&file_path, revision, &output_path)
+        .map_err(|e| napi::Error::from_reason(format!("Failed to extract revision: {e}")))
+}
+
+/// Rebuild a damaged PDF from the objects in its bytes and write a repaired
+/// copy; returns what was fixed as JSON
+#[napi]
+pub fn repair_pdf(input_path: String, output_path: String) -> napi::Result<String> {
+    let report = repair::repair_pdf(&input_path, &output_path)
+        .map_err(|e| napi::Error::from_reason(format!("Repair failed: {e}")))?;
+
+    serde_json::to_string_pretty(&report)
+        .map_err(|e| napi::Error::from_reason(format!("Serialization failed: {e}")))
+}
+
 #[napi]
 pub fn get_pdf_info_before_signing(file_path: String) -> napi::Result<u32> {
     sign::get_pdf_info_before_signing(file_path)
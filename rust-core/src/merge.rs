@@ -0,0 +1,1644 @@
+use lopdf::{Dictionary, Document, Object, ObjectId, StringFormat};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use crate::page_range::parse_page_range;
+
+/// Page attributes a page can inherit from its ancestors in the page tree.
+pub(crate) const INHERITABLE_PAGE_KEYS: [&[u8]; 4] =
+    [b"Resources", b"MediaBox", b"CropBox", b"Rotate"];
+
+/// Catalog entries rebuilt by [`PdfAssembler`] instead of being copied from
+/// the first imported document.
+const MANAGED_CATALOG_KEYS: [&[u8]; 8] = [
+    b"Type",
+    b"Pages",
+    b"Outlines",
+    b"Dests",
+    b"AcroForm",
+    b"OCProperties",
+    b"StructTreeRoot",
+    b"PageLabels",
+];
+
+#[derive(Debug, Clone, Default)]
+#[napi(object)]
+pub struct MergeOptions {
+    pub page_ranges: Option<Vec<String>>, // Page-range expression per input, by position; "" keeps every page
+    pub file_bookmarks: Option<bool>, // Nest each input's outline under a bookmark named after the file (default: false)
+}
+
+#[derive(Debug, Clone, Default)]
+#[napi(object)]
+pub struct CollateOptions {
+    pub reverse_second: Option<bool>, // Take the second file's pages last to first, as a duplex scan of backs produces (default: false)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MergedInput {
+    pub input_file: String,
+    pub pages: Vec<u32>, // Source page numbers, in output order
+}
+
+/// A form field or named destination that was renamed to avoid a clash with
+/// an earlier input.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenamedName {
+    pub input_file: String,
+    pub kind: String, // "field" or "destination"
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MergeSummary {
+    pub output_file: String,
+    pub page_count: u32,
+    pub inputs: Vec<MergedInput>,
+    pub renamed: Vec<RenamedName>,
+}
+
+/// Builds a new document out of pages imported from other documents.
+///
+/// Each import renumbers the source into the assembler's object id space,
+/// flattens inherited page attributes, and carries over the outline entries,
+/// named destinations, form fields, structure elements and page labels that
+/// belong to the imported pages.
+pub(crate) struct PdfAssembler {
+    document: Document,
+    catalog: Option<Dictionary>,
+    info: Option<Object>,
+    outlines: Vec<OutlineNode>,
+    destinations: BTreeMap<Vec<u8>, Object>,
+    fields: Vec<ObjectId>,
+    field_names: HashSet<String>,
+    form: Dictionary,
+    layers: Dictionary,
+    structure: Structure,
+    page_labels: HashMap<ObjectId, PageLabel>,
+    labeled: bool, // Whether any import had page labels
+    pub(crate) renamed: Vec<RenamedName>,
+}
+
+/// The structure trees of the imported documents, gathered under one root.
+#[derive(Default)]
+struct Structure {
+    kids: Vec<Object>,
+    parent_tree: Vec<(i64, Object)>,
+    next_key: i64,    // First /StructParents key free for the next import
+    maps: Dictionary, // Merged /RoleMap and /ClassMap
+}
+
+/// The label a page had in its source document.
+#[derive(Clone, PartialEq)]
+struct PageLabel {
+    style: Option<Vec<u8>>, // Numbering style, e.g. "D" or "r"; none for prefix-only labels
+    prefix: Option<Object>,
+    number: i64,
+}
+
+struct OutlineNode {
+    id: ObjectId,
+    children: Vec<OutlineNode>,
+}
+
+impl PdfAssembler {
+    pub(crate) fn new() -> Self {
+        PdfAssembler {
+            document: Document::new(),
+            catalog: None,
+            info: None,
+            outlines: Vec::new(),
+            destinations: BTreeMap::new(),
+            fields: Vec::new(),
+            field_names: HashSet::new(),
+            form: Dictionary::new(),
+            layers: Dictionary::new(),
+            structure: Structure::default(),
+            page_labels: HashMap::new(),
+            labeled: false,
+            renamed: Vec::new(),
+        }
+    }
+
+    /// Imports the given 1-based pages of `source`, in the order listed.
+    ///
+    /// A page listed more than once is copied, so every occurrence gets its
+    /// own page object. With `bookmark`, the source's outline is nested under
+    /// a new top-level entry with that title.
+    ///
+    /// # Returns
+    /// The ids of the imported pages, to be passed to [`PdfAssembler::finish`]
+    /// in the desired output order.
+    pub(crate) fn import(
+        &mut self,
+        mut source: Document,
+        page_numbers: &[u32],
+        source_name: &str,
+        bookmark: Option<&str>,
+    ) -> Result<Vec<ObjectId>, Box<dyn std::error::Error>> {
+        if source.is_encrypted() {
+            return Err(format!("'{source_name}' is encrypted").into());
+        }
+
+        source.renumber_objects_with(self.document.max_id + 1);
+        let source_pages = source.get_pages();
+        let total = source_pages.len();
+
+        let mut kept = HashSet::new();
+        let mut imported = Vec::with_capacity(page_numbers.len());
+        for number in page_numbers {
+            let page_id = *source_pages.get(number).ok_or_else(|| {
+                format!("Page {number} is out of range ('{source_name}' has {total} pages)")
+            })?;
+            if kept.insert(page_id) {
+                flatten_inherited_attributes(&mut source, page_id)?;
+                imported.push(page_id);
+            } else {
+                let copy = duplicate_page(&mut source, page_id)?;
+                // Only one page can own the page's structure entries
+                source.get_dictionary_mut(copy)?.remove(b"StructParents");
+                imported.push(copy);
+            }
+        }
+        let dropped: HashSet<ObjectId> = source_pages
+            .values()
+            .filter(|id| !kept.contains(id))
+            .copied()
+            .collect();
+
+        let catalog_id = source.trailer.get(b"Root").and_then(Object::as_reference)?;
+        let catalog = source.catalog()?.clone();
+        let page_tree = page_tree_nodes(&source, &catalog);
+
+        // The outline is read before destinations are renamed, while its
+        // named destinations still resolve against the source catalog
+        self.import_outline(&mut source, &catalog, &dropped, &imported, bookmark)?;
+        self.import_destinations(&mut source, &catalog, &dropped, source_name);
+        self.import_form(&mut source, &catalog, &kept, source_name)?;
+        self.import_layers(&source, &catalog);
+        self.import_structure(&mut source, &catalog);
+        self.import_page_labels(&source, &catalog, page_numbers, &imported);
+
+        if self.catalog.is_none() {
+            let mut extras = catalog.clone();
+            for key in MANAGED_CATALOG_KEYS {
+                extras.remove(key);
+            }
+            // Other name trees (attachments, scripts) come from the first
+            // document; destinations are merged separately
+            if let Some(mut names) = resolve_dictionary(&source, extras.get(b"Names").ok()).cloned()
+            {
+                names.remove(b"Dests");
+                extras.set("Names", Object::Dictionary(names));
+            }
+            self.catalog = Some(extras);
+            self.info = source.trailer.get(b"Info").ok().cloned();
+        }
+
+        for (id, mut object) in std::mem::take(&mut source.objects) {
+            if id == catalog_id || dropped.contains(&id) || page_tree.contains(&id) {
+                continue;
+            }
+            null_references(&mut object, &dropped);
+            self.document.objects.insert(id, object);
+        }
+        self.document.max_id = self.document.max_id.max(source.max_id);
+        if source.version > self.document.version {
+            self.document.version = source.version;
+        }
+
+        Ok(imported)
+    }
+
+    /// Writes the page tree, outline, name tree and form for the imported
+    /// pages and returns the finished document, with unreferenced objects
+    /// removed and object ids compacted.
+    pub(crate) fn finish(
+        mut self,
+        page_order: &[ObjectId],
+    ) -> Result<Document, Box<dyn std::error::Error>> {
+        if page_order.is_empty() {
+            return Err("No pages selected".into());
+        }
+
+        let pages_id = self.document.new_object_id();
+        for page_id in page_order {
+            self.document
+                .get_dictionary_mut(*page_id)?
+                .set("Parent", Object::Reference(pages_id));
+        }
+        let mut pages = Dictionary::new();
+        pages.set("Type", Object::Name(b"Pages".to_vec()));
+        pages.set(
+            "Kids",
+            Object::Array(page_order.iter().map(|id| Object::Reference(*id)).collect()),
+        );
+        pages.set("Count", Object::Integer(page_order.len() as i64));
+        self.document
+            .objects
+            .insert(pages_id, Object::Dictionary(pages));
+
+        let mut catalog = self.catalog.take().unwrap_or_default();
+        catalog.set("Type", Object::Name(b"Catalog".to_vec()));
+        catalog.set("Pages", Object::Reference(pages_id));
+
+        if !self.outlines.is_empty() {
+            let outlines_id = self.document.new_object_id();
+            let visible = link_outline(&mut self.document, outlines_id, &self.outlines)?;
+            let mut outlines = Dictionary::new();
+            outlines.set("Type", Object::Name(b"Outlines".to_vec()));
+            outlines.set("First", Object::Reference(self.outlines[0].id));
+            outlines.set(
+                "Last",
+                Object::Reference(self.outlines[self.outlines.len() - 1].id),
+            );
+            outlines.set("Count", Object::Integer(visible));
+            self.document
+                .objects
+                .insert(outlines_id, Object::Dictionary(outlines));
+            catalog.set("Outlines", Object::Reference(outlines_id));
+        }
+
+        if !self.destinations.is_empty() {
+            let mut entries = Vec::with_capacity(self.destinations.len() * 2);
+            for (name, destination) in std::mem::take(&mut self.destinations) {
+                entries.push(Object::String(name, StringFormat::Literal));
+                entries.push(destination);
+            }
+            let mut dests = Dictionary::new();
+            dests.set("Names", Object::Array(entries));
+            let mut names = match catalog.get(b"Names") {
+                Ok(Object::Dictionary(names)) => names.clone(),
+                _ => Dictionary::new(),
+            };
+            names.set("Dests", Object::Dictionary(dests));
+            catalog.set("Names", Object::Dictionary(names));
+        }
+
+        if !self.fields.is_empty() {
+            self.form.set(
+                "Fields",
+                Object::Array(
+                    self.fields
+                        .iter()
+                        .map(|id| Object::Reference(*id))
+                        .collect(),
+                ),
+            );
+            let form_id = self.document.add_object(self.form);
+            catalog.set("AcroForm", Object::Reference(form_id));
+        }
+
+        if self.layers.has(b"OCGs") {
+            catalog.set("OCProperties", Object::Dictionary(self.layers));
+        }
+
+        if let Some(root_id) = self.structure.write(&mut self.document) {
+            catalog.set("StructTreeRoot", Object::Reference(root_id));
+            let mut mark_info = match catalog.get(b"MarkInfo") {
+                Ok(Object::Dictionary(dict)) => dict.clone(),
+                _ => Dictionary::new(),
+            };
+            mark_info.set("Marked", Object::Boolean(true));
+            catalog.set("MarkInfo", Object::Dictionary(mark_info));
+        }
+
+        if self.labeled {
+            let mut nums = Vec::new();
+            let mut previous: Option<&PageLabel> = None;
+            for (index, page_id) in page_order.iter().enumerate() {
+                let Some(label) = self.page_labels.get(page_id) else {
+                    continue;
+                };
+                let continues = previous.is_some_and(|previous| {
+                    previous.style == label.style
+                        && previous.prefix == label.prefix
+                        && (label.style.is_none() || previous.number + 1 == label.number)
+                });
+                if !continues {
+                    let mut range = Dictionary::new();
+                    if let Some(style) = &label.style {
+                        range.set("S", Object::Name(style.clone()));
+                        if label.number != 1 {
+                            range.set("St", Object::Integer(label.number));
+                        }
+                    }
+                    if let Some(prefix) = &label.prefix {
+                        range.set("P", prefix.clone());
+                    }
+                    nums.push(Object::Integer(index as i64));
+                    nums.push(Object::Dictionary(range));
+                }
+                previous = Some(label);
+            }
+            let mut labels = Dictionary::new();
+            labels.set("Nums", Object::Array(nums));
+            catalog.set("PageLabels", Object::Dictionary(labels));
+        }
+
+        let catalog_id = self.document.add_object(catalog);
+        self.document
+            .trailer
+            .set("Root", Object::Reference(catalog_id));
+        if let Some(info) = self.info {
+            self.document.trailer.set("Info", info);
+        }
+
+        self.document.prune_objects();
+        self.document.renumber_objects();
+        Ok(self.document)
+    }
+
+    fn import_destinations(
+        &mut self,
+        source: &mut Document,
+        catalog: &Dictionary,
+        dropped: &HashSet<ObjectId>,
+        source_name: &str,
+    ) {
+        let mut renames: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+        for (name, destination) in named_destinations(source, catalog) {
+            if destination_page(source, &destination).is_some_and(|page| dropped.contains(&page)) {
+                continue;
+            }
+            let mut target = name.clone();
+            let mut suffix = 2;
+            while self.destinations.contains_key(&target) {
+                target = format!("{}-{suffix}", String::from_utf8_lossy(&name)).into_bytes();
+                suffix += 1;
+            }
+            if target != name {
+                self.renamed.push(RenamedName {
+                    input_file: source_name.to_string(),
+                    kind: "destination".to_string(),
+                    from: String::from_utf8_lossy(&name).to_string(),
+                    to: String::from_utf8_lossy(&target).to_string(),
+                });
+                renames.insert(name, target.clone());
+            }
+            self.destinations.insert(target, destination);
+        }
+
+        // Links and outline items name their destination with a string (name
+        // tree) or a name (legacy /Dests); the output only has a name tree
+        for object in source.objects.values_mut() {
+            let dict = match object {
+                Object::Dictionary(dict) => dict,
+                Object::Stream(stream) => &mut stream.dict,
+                _ => continue,
+            };
+            if let Ok(destination) = dict.get_mut(b"Dest") {
+                rename_destination(destination, &renames);
+            }
+            if let Ok(Object::Dictionary(action)) = dict.get_mut(b"A") {
+                if let Ok(destination) = action.get_mut(b"D") {
+                    rename_destination(destination, &renames);
+                }
+            }
+        }
+    }
+
+    fn import_outline(
+        &mut self,
+        source: &mut Document,
+        catalog: &Dictionary,
+        dropped: &HashSet<ObjectId>,
+        imported: &[ObjectId],
+        bookmark: Option<&str>,
+    ) -> lopdf::Result<()> {
+        let first = resolve_dictionary(source, catalog.get(b"Outlines").ok())
+            .and_then(|outlines| outlines.get(b"First").and_then(Object::as_reference).ok());
+        let named: HashMap<Vec<u8>, Object> =
+            named_destinations(source, catalog).into_iter().collect();
+
+        let mut unreachable = Vec::new();
+        let mut visited = HashSet::new();
+        let nodes = collect_outline(
+            source,
+            first,
+            &named,
+            dropped,
+            &mut unreachable,
+            &mut visited,
+        );
+        for id in unreachable {
+            let item = source.get_dictionary_mut(id)?;
+            item.remove(b"Dest");
+            item.remove(b"A");
+        }
+
+        match bookmark {
+            Some(title) => {
+                let mut item = Dictionary::new();
+                item.set("Title", lopdf::text_string(title));
+                if let Some(first_page) = imported.first() {
+                    item.set(
+                        "Dest",
+                        Object::Array(vec![
+                            Object::Reference(*first_page),
+                            Object::Name(b"Fit".to_vec()),
+                        ]),
+                    );
+                }
+                // Open by default so the file's own entries are visible
+                item.set("Count", Object::Integer(1));
+                let id = source.add_object(item);
+                self.outlines.push(OutlineNode {
+                    id,
+                    children: nodes,
+                });
+            }
+            None => self.outlines.extend(nodes),
+        }
+        Ok(())
+    }
+
+    fn import_form(
+        &mut self,
+        source: &mut Document,
+        catalog: &Dictionary,
+        kept: &HashSet<ObjectId>,
+        source_name: &str,
+    ) -> lopdf::Result<()> {
+        let Some(form) = resolve_dictionary(source, catalog.get(b"AcroForm").ok()).cloned() else {
+            return Ok(());
+        };
+
+        let mut widgets = HashSet::new();
+        for page_id in kept {
+            let annots = source.get_dictionary(*page_id)?.get(b"Annots").ok();
+            if let Some(Object::Array(annots)) = annots
+                .map(|a| source.dereference(a))
+                .and_then(|r| r.ok())
+                .map(|(_, a)| a)
+            {
+                widgets.extend(annots.iter().filter_map(|a| a.as_reference().ok()));
+            }
+        }
+
+        let fields = resolve_array(source, form.get(b"Fields").ok())
+            .cloned()
+            .unwrap_or_default();
+        for field_id in fields.iter().filter_map(|f| f.as_reference().ok()) {
+            if !field_has_widget(source, field_id, &widgets, 0) {
+                continue;
+            }
+            let field = source.get_dictionary_mut(field_id)?;
+            if let Some(name) = field
+                .get(b"T")
+                .ok()
+                .and_then(|t| lopdf::decode_text_string(t).ok())
+            {
+                let mut target = name.clone();
+                let mut suffix = 2;
+                while self.field_names.contains(&target) {
+                    target = format!("{name}_{suffix}");
+                    suffix += 1;
+                }
+                if target != name {
+                    field.set("T", lopdf::text_string(&target));
+                    self.renamed.push(RenamedName {
+                        input_file: source_name.to_string(),
+                        kind: "field".to_string(),
+                        from: name,
+                        to: target.clone(),
+                    });
+                }
+                self.field_names.insert(target);
+            }
+            self.fields.push(field_id);
+        }
+
+        for key in [b"DA".as_slice(), b"Q"] {
+            if !self.form.has(key) {
+                if let Ok(value) = form.get(key) {
+                    self.form.set(key, value.clone());
+                }
+            }
+        }
+        if form
+            .get(b"NeedAppearances")
+            .and_then(Object::as_bool)
+            .unwrap_or(false)
+        {
+            self.form.set("NeedAppearances", Object::Boolean(true));
+        }
+        if let Ok(flags) = form.get(b"SigFlags").and_then(Object::as_i64) {
+            let merged = self
+                .form
+                .get(b"SigFlags")
+                .and_then(Object::as_i64)
+                .unwrap_or(0)
+                | flags;
+            self.form.set("SigFlags", Object::Integer(merged));
+        }
+
+        // Default resources: the first definition of each name wins
+        if let Some(resources) = resolve_dictionary(source, form.get(b"DR").ok()) {
+            let mut merged = match self.form.get(b"DR") {
+                Ok(Object::Dictionary(dict)) => dict.clone(),
+                _ => Dictionary::new(),
+            };
+            for (category, entries) in resources.iter() {
+                let Some(entries) = resolve_dictionary(source, Some(entries)) else {
+                    continue;
+                };
+                let mut combined = match merged.get(category) {
+                    Ok(Object::Dictionary(dict)) => dict.clone(),
+                    _ => Dictionary::new(),
+                };
+                for (name, value) in entries.iter() {
+                    if !combined.has(name) {
+                        combined.set(name.clone(), value.clone());
+                    }
+                }
+                merged.set(category.clone(), Object::Dictionary(combined));
+            }
+            self.form.set("DR", Object::Dictionary(merged));
+        }
+        Ok(())
+    }
+
+    /// Carries optional content groups over so layers from every input stay
+    /// listed (and removable) in the merged document.
+    fn import_layers(&mut self, source: &Document, catalog: &Dictionary) {
+        let Some(properties) = resolve_dictionary(source, catalog.get(b"OCProperties").ok()) else {
+            return;
+        };
+
+        let mut ocgs = match self.layers.get(b"OCGs") {
+            Ok(Object::Array(ocgs)) => ocgs.clone(),
+            _ => Vec::new(),
+        };
+        ocgs.extend(
+            resolve_array(source, properties.get(b"OCGs").ok())
+                .cloned()
+                .unwrap_or_default(),
+        );
+        self.layers.set("OCGs", Object::Array(ocgs));
+
+        let Some(config) = resolve_dictionary(source, properties.get(b"D").ok()) else {
+            return;
+        };
+        let mut merged = match self.layers.get(b"D") {
+            Ok(Object::Dictionary(dict)) => dict.clone(),
+            _ => config.clone(),
+        };
+        if self.layers.has(b"D") {
+            for key in [b"Order".as_slice(), b"ON", b"OFF"] {
+                let mut values = match merged.get(key) {
+                    Ok(Object::Array(values)) => values.clone(),
+                    _ => Vec::new(),
+                };
+                values.extend(
+                    resolve_array(source, config.get(key).ok())
+                        .cloned()
+                        .unwrap_or_default(),
+                );
+                if !values.is_empty() {
+                    merged.set(key, Object::Array(values));
+                }
+            }
+        }
+        self.layers.set("D", Object::Dictionary(merged));
+    }
+
+    /// Adds the source's structure elements to the merged structure tree.
+    ///
+    /// `/StructParents` and `/StructParent` keys are shifted past the ones
+    /// taken by earlier imports, so every parent tree entry stays unique.
+    fn import_structure(&mut self, source: &mut Document, catalog: &Dictionary) {
+        let Some(root) = resolve_dictionary(source, catalog.get(b"StructTreeRoot").ok()).cloned()
+        else {
+            return;
+        };
+
+        let entries = resolve_dictionary(source, root.get(b"ParentTree").ok())
+            .map(|tree| number_tree_entries(source, tree))
+            .unwrap_or_default();
+        let next_key = entries
+            .iter()
+            .map(|(key, _)| key + 1)
+            .chain(root.get(b"ParentTreeNextKey").and_then(Object::as_i64))
+            .max()
+            .unwrap_or(0);
+        let offset = self.structure.next_key;
+        if offset > 0 {
+            for object in source.objects.values_mut() {
+                let dict = match object {
+                    Object::Dictionary(dict) => dict,
+                    Object::Stream(stream) => &mut stream.dict,
+                    _ => continue,
+                };
+                for key in [b"StructParents".as_slice(), b"StructParent"] {
+                    if let Ok(Object::Integer(value)) = dict.get_mut(key) {
+                        *value += offset;
+                    }
+                }
+            }
+        }
+        self.structure.parent_tree.extend(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key + offset, value)),
+        );
+        self.structure.next_key = offset + next_key;
+
+        match resolve_array(source, root.get(b"K").ok()) {
+            Some(kids) => self.structure.kids.extend(kids.iter().cloned()),
+            None => self.structure.kids.extend(root.get(b"K").ok().cloned()),
+        }
+
+        // Role and class maps: the first definition of each name wins
+        for key in [b"RoleMap".as_slice(), b"ClassMap"] {
+            let Some(map) = resolve_dictionary(source, root.get(key).ok()) else {
+                continue;
+            };
+            let mut merged = match self.structure.maps.get(key) {
+                Ok(Object::Dictionary(dict)) => dict.clone(),
+                _ => Dictionary::new(),
+            };
+            for (name, value) in map.iter() {
+                if !merged.has(name) {
+                    merged.set(name.clone(), value.clone());
+                }
+            }
+            self.structure.maps.set(key, Object::Dictionary(merged));
+        }
+    }
+
+    /// Records the label each imported page had in the source, so page label
+    /// ranges can be rebuilt for the output order.
+    ///
+    /// Pages of documents without labels are labeled with their page number,
+    /// which is what viewers showed for them.
+    fn import_page_labels(
+        &mut self,
+        source: &Document,
+        catalog: &Dictionary,
+        page_numbers: &[u32],
+        imported: &[ObjectId],
+    ) {
+        let mut ranges = resolve_dictionary(source, catalog.get(b"PageLabels").ok())
+            .map(|tree| number_tree_entries(source, tree))
+            .unwrap_or_default();
+        ranges.sort_by_key(|(start, _)| *start);
+        self.labeled |= !ranges.is_empty();
+
+        for (number, page_id) in page_numbers.iter().zip(imported) {
+            let index = i64::from(*number) - 1;
+            let range = ranges
+                .iter()
+                .rev()
+                .find(|(start, _)| *start <= index)
+                .and_then(|(start, range)| Some((start, resolve_dictionary(source, Some(range))?)));
+            let label = match range {
+                Some((start, range)) => PageLabel {
+                    style: range
+                        .get(b"S")
+                        .and_then(Object::as_name)
+                        .ok()
+                        .map(<[u8]>::to_vec),
+                    prefix: range.get(b"P").ok().cloned(),
+                    number: range.get(b"St").and_then(Object::as_i64).unwrap_or(1) + index - start,
+                },
+                None => PageLabel {
+                    style: Some(b"D".to_vec()),
+                    prefix: None,
+                    number: index + 1,
+                },
+            };
+            self.page_labels.insert(*page_id, label);
+        }
+    }
+}
+
+impl Structure {
+    /// Writes the structure tree root for the gathered elements and returns
+    /// its id, or `None` when no import had a structure tree.
+    ///
+    /// Elements whose content was entirely on pages that were not imported
+    /// are left out, along with their parent tree entries.
+    fn write(&mut self, document: &mut Document) -> Option<ObjectId> {
+        if self.kids.is_empty() {
+            return None;
+        }
+        let root_id = document.new_object_id();
+
+        let mut kept = HashSet::new();
+        let kids: Vec<Object> = std::mem::take(&mut self.kids)
+            .into_iter()
+            .filter(|kid| prune_structure(document, kid, &mut kept))
+            .collect();
+        for kid in &kids {
+            if let Ok(element) = kid
+                .as_reference()
+                .and_then(|id| document.get_dictionary_mut(id))
+            {
+                element.set("P", Object::Reference(root_id));
+            }
+        }
+
+        let mut nums = Vec::new();
+        self.parent_tree.sort_by_key(|(key, _)| *key);
+        for (key, mut value) in std::mem::take(&mut self.parent_tree) {
+            let used = match &mut value {
+                Object::Reference(id) => match document.get_object_mut(*id) {
+                    Ok(Object::Array(elements)) => keep_elements(elements, &kept),
+                    _ => kept.contains(id),
+                },
+                Object::Array(elements) => keep_elements(elements, &kept),
+                _ => false,
+            };
+            if !used {
+                continue;
+            }
+            nums.push(Object::Integer(key));
+            nums.push(value);
+        }
+        let mut parent_tree = Dictionary::new();
+        parent_tree.set("Nums", Object::Array(nums));
+        let parent_tree_id = document.add_object(parent_tree);
+
+        let mut root = std::mem::take(&mut self.maps);
+        root.set("Type", Object::Name(b"StructTreeRoot".to_vec()));
+        root.set("K", Object::Array(kids));
+        root.set("ParentTree", Object::Reference(parent_tree_id));
+        root.set("ParentTreeNextKey", Object::Integer(self.next_key));
+        document.objects.insert(root_id, Object::Dictionary(root));
+        Some(root_id)
+    }
+}
+
+/// Merges PDFs into one document, in the order given.
+///
+/// `options.page_ranges` selects pages per input with page-range expressions.
+/// Outlines, named destinations, form fields, structure trees and page labels
+/// are carried over; names that clash with an earlier input are given a
+/// numeric suffix.
+pub fn merge_pdfs(
+    input_paths: &[String],
+    output_path: &str,
+    options: &MergeOptions,
+) -> Result<MergeSummary, Box<dyn std::error::Error>> {
+    if input_paths.is_empty() {
+        return Err("No input files given".into());
+    }
+
+    let mut assembler = PdfAssembler::new();
+    let mut page_order = Vec::new();
+    let mut inputs = Vec::with_capacity(input_paths.len());
+
+    for (index, input_path) in input_paths.iter().enumerate() {
+        let document = Document::load(input_path)
+            .map_err(|e| format!("Failed to load PDF '{input_path}': {e}"))?;
+        let total = document.get_pages().len() as u32;
+
+        let pages = match options
+            .page_ranges
+            .as_ref()
+            .and_then(|ranges| ranges.get(index))
+            .filter(|range| !range.trim().is_empty())
+        {
+            Some(range) => parse_page_range(range, total)
+                .map_err(|e| format!("Invalid page range for '{input_path}': {e}"))?,
+            None => (1..=total).collect(),
+        };
+
+        let bookmark = options
+            .file_bookmarks
+            .unwrap_or(false)
+            .then(|| file_stem(input_path));
+        let imported = assembler.import(document, &pages, input_path, bookmark.as_deref())?;
+        page_order.extend(imported);
+        inputs.push(MergedInput {
+            input_file: input_path.clone(),
+            pages,
+        });
+    }
+
+    let renamed = assembler.renamed.clone();
+    let mut document = assembler.finish(&page_order)?;
+    document.save(output_path)?;
+
+    Ok(MergeSummary {
+        output_file: output_path.to_string(),
+        page_count: page_order.len() as u32,
+        inputs,
+        renamed,
+    })
+}
+
+/// Interleaves the pages of two PDFs: first page of `first_path`, first page
+/// of `second_path`, second of each, and so on. When one file is longer, its
+/// remaining pages follow at the end.
+///
+/// # Returns
+/// The number of pages written.
+pub fn collate(
+    first_path: &str,
+    second_path: &str,
+    output_path: &str,
+    options: &CollateOptions,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    let mut assembler = PdfAssembler::new();
+    let mut imported = Vec::with_capacity(2);
+    for (index, input_path) in [first_path, second_path].into_iter().enumerate() {
+        let document = Document::load(input_path)
+            .map_err(|e| format!("Failed to load PDF '{input_path}': {e}"))?;
+        let total = document.get_pages().len() as u32;
+        let mut pages: Vec<u32> = (1..=total).collect();
+        if index == 1 && options.reverse_second.unwrap_or(false) {
+            pages.reverse();
+        }
+        imported.push(assembler.import(document, &pages, input_path, None)?);
+    }
+
+    let (fronts, backs) = (&imported[0], &imported[1]);
+    let mut page_order = Vec::with_capacity(fronts.len() + backs.len());
+    for index in 0..fronts.len().max(backs.len()) {
+        page_order.extend(fronts.get(index));
+        page_order.extend(backs.get(index));
+    }
+
+    let mut document = assembler.finish(&page_order)?;
+    document.save(output_path)?;
+    Ok(page_order.len() as u32)
+}
+
+pub(crate) fn file_stem(path: &str) -> String {
+    Path::new(path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "document".to_string())
+}
+
+/// Copies inheritable attributes (`/Resources`, `/MediaBox`, `/CropBox`,
+/// `/Rotate`) from the page's ancestors onto the page itself, so it can be
+/// moved to another page tree. Indirect values stay shared.
+pub(crate) fn flatten_inherited_attributes(
+    document: &mut Document,
+    page_id: ObjectId,
+) -> lopdf::Result<()> {
+    let mut inherited = Vec::new();
+    let page = document.get_dictionary(page_id)?;
+    for key in INHERITABLE_PAGE_KEYS {
+        if page.has(key) {
+            continue;
+        }
+        let mut parent = page.get(b"Parent").and_then(Object::as_reference).ok();
+        // Guard against cyclic /Parent links in malformed files
+        for _ in 0..64 {
+            let Some(node) = parent.and_then(|id| document.get_dictionary(id).ok()) else {
+                break;
+            };
+            if let Ok(value) = node.get(key) {
+                inherited.push((key, value.clone()));
+                break;
+            }
+            parent = node.get(b"Parent").and_then(Object::as_reference).ok();
+        }
+    }
+
+    let page = document.get_dictionary_mut(page_id)?;
+    for (key, value) in inherited {
+        page.set(key, value);
+    }
+    Ok(())
+}
+
+/// Copies a page object. Form widgets are left off the copy because a widget
+/// annotation can only belong to one page.
+pub(crate) fn duplicate_page(
+    document: &mut Document,
+    page_id: ObjectId,
+) -> lopdf::Result<ObjectId> {
+    let mut page = document.get_dictionary(page_id)?.clone();
+    if let Ok(annots) = page.get(b"Annots") {
+        let annots = document.dereference(annots)?.1.as_array()?;
+        let kept: Vec<Object> = annots
+            .iter()
+            .filter(|annot| {
+                !annot
+                    .as_reference()
+                    .and_then(|id| document.get_dictionary(id))
+                    .is_ok_and(|annot| {
+                        annot.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Widget")
+                    })
+            })
+            .cloned()
+            .collect();
+        page.set("Annots", Object::Array(kept));
+    }
+    Ok(document.add_object(page))
+}
+
+/// Returns the ids of the intermediate (`/Type /Pages`) nodes of the page tree.
+pub(crate) fn page_tree_nodes(document: &Document, catalog: &Dictionary) -> HashSet<ObjectId> {
+    let mut nodes = HashSet::new();
+    let mut pending: Vec<ObjectId> = catalog
+        .get(b"Pages")
+        .and_then(Object::as_reference)
+        .into_iter()
+        .collect();
+    while let Some(id) = pending.pop() {
+        let Ok(node) = document.get_dictionary(id) else {
+            continue;
+        };
+        if node.get_type().ok() == Some(b"Page".as_slice()) || !nodes.insert(id) {
+            continue;
+        }
+        if let Some(kids) = resolve_array(document, node.get(b"Kids").ok()) {
+            pending.extend(kids.iter().filter_map(|kid| kid.as_reference().ok()));
+        }
+    }
+    nodes
+}
+
+/// Collects named destinations from the catalog's `/Names /Dests` tree and
+/// the legacy `/Dests` dictionary.
+pub(crate) fn named_destinations(
+    document: &Document,
+    catalog: &Dictionary,
+) -> Vec<(Vec<u8>, Object)> {
+    let mut destinations = Vec::new();
+    if let Some(dests) = resolve_dictionary(document, catalog.get(b"Dests").ok()) {
+        for (name, destination) in dests.iter() {
+            destinations.push((name.clone(), destination.clone()));
+        }
+    }
+    let tree = resolve_dictionary(document, catalog.get(b"Names").ok())
+        .and_then(|names| resolve_dictionary(document, names.get(b"Dests").ok()));
+    if let Some(tree) = tree {
+        destinations.extend(name_tree_entries(document, tree));
+    }
+    destinations
+}
+
+/// Collects the key/value pairs of a name tree (`/Names` and `/Kids`).
+pub(crate) fn name_tree_entries(document: &Document, tree: &Dictionary) -> Vec<(Vec<u8>, Object)> {
+    fn walk_tree(
+        document: &Document,
+        node: &Dictionary,
+        depth: usize,
+        out: &mut Vec<(Vec<u8>, Object)>,
+    ) {
+        if depth > 32 {
+            return;
+        }
+        if let Some(names) = resolve_array(document, node.get(b"Names").ok()) {
+            for [name, value] in names.as_chunks::<2>().0 {
+                if let Ok(name) = name.as_str() {
+                    out.push((name.to_vec(), value.clone()));
+                }
+            }
+        }
+        if let Some(kids) = resolve_array(document, node.get(b"Kids").ok()) {
+            for kid in kids {
+                if let Some(kid) = resolve_dictionary(document, Some(kid)) {
+                    walk_tree(document, kid, depth + 1, out);
+                }
+            }
+        }
+    }
+
+    let mut entries = Vec::new();
+    walk_tree(document, tree, 0, &mut entries);
+    entries
+}
+
+/// Collects the key/value pairs of a number tree (`/Nums` and `/Kids`).
+fn number_tree_entries(document: &Document, tree: &Dictionary) -> Vec<(i64, Object)> {
+    fn walk_tree(
+        document: &Document,
+        node: &Dictionary,
+        depth: usize,
+        out: &mut Vec<(i64, Object)>,
+    ) {
+        if depth > 32 {
+            return;
+        }
+        if let Some(nums) = resolve_array(document, node.get(b"Nums").ok()) {
+            for [key, value] in nums.as_chunks::<2>().0 {
+                if let Ok(key) = key.as_i64() {
+                    out.push((key, value.clone()));
+                }
+            }
+        }
+        if let Some(kids) = resolve_array(document, node.get(b"Kids").ok()) {
+            for kid in kids {
+                if let Some(kid) = resolve_dictionary(document, Some(kid)) {
+                    walk_tree(document, kid, depth + 1, out);
+                }
+            }
+        }
+    }
+
+    let mut entries = Vec::new();
+    walk_tree(document, tree, 0, &mut entries);
+    entries
+}
+
+/// Drops the parts of a structure element that refer to pages which were
+/// not imported (their `/Pg` was replaced with `null`), and reports whether
+/// anything of the element is left. Kept elements are added to `kept`.
+fn prune_structure(document: &mut Document, kid: &Object, kept: &mut HashSet<ObjectId>) -> bool {
+    let id = match kid {
+        Object::Reference(id) => *id,
+        // Marked-content identifiers of the parent's page
+        Object::Integer(_) => return true,
+        Object::Dictionary(reference) => {
+            // Marked-content (MCR) and object (OBJR) references
+            return !matches!(reference.get(b"Pg"), Ok(Object::Null))
+                && !matches!(reference.get(b"Obj"), Ok(Object::Null));
+        }
+        _ => return false,
+    };
+    if kept.contains(&id) {
+        return true;
+    }
+    let Ok(element) = document.get_dictionary(id) else {
+        return false;
+    };
+    if matches!(
+        element.get(b"Type").and_then(Object::as_name),
+        Ok(b"MCR" | b"OBJR")
+    ) {
+        let keep = !matches!(element.get(b"Pg"), Ok(Object::Null))
+            && !matches!(element.get(b"Obj"), Ok(Object::Null));
+        if keep {
+            kept.insert(id);
+        }
+        return keep;
+    }
+    let page_dropped = matches!(element.get(b"Pg"), Ok(Object::Null));
+    let children = match element.get(b"K") {
+        Ok(Object::Array(children)) => children.clone(),
+        Ok(child) => vec![child.clone()],
+        Err(_) if page_dropped => return false,
+        Err(_) => {
+            kept.insert(id);
+            return true;
+        }
+    };
+    // Mark before descending, so a malformed tree cannot loop
+    kept.insert(id);
+    let children: Vec<Object> = children
+        .into_iter()
+        .filter(|child| {
+            !(page_dropped && matches!(child, Object::Integer(_)))
+                && prune_structure(document, child, kept)
+        })
+        .collect();
+    if children.is_empty() {
+        kept.remove(&id);
+        return false;
+    }
+    if let Ok(element) = document.get_dictionary_mut(id) {
+        element.set("K", Object::Array(children));
+    }
+    true
+}
+
+/// Replaces parent tree references to elements that were left out with
+/// `null`, and reports whether any element is left.
+fn keep_elements(elements: &mut [Object], kept: &HashSet<ObjectId>) -> bool {
+    for element in elements.iter_mut() {
+        if element.as_reference().is_ok_and(|id| !kept.contains(&id)) {
+            *element = Object::Null;
+        }
+    }
+    elements
+        .iter()
+        .any(|element| element.as_reference().is_ok())
+}
+
+/// Returns the page an explicit destination (`[page /XYZ ...]` or
+/// `<< /D [...] >>`) points to.
+pub(crate) fn destination_page(document: &Document, destination: &Object) -> Option<ObjectId> {
+    let (_, destination) = document.dereference(destination).ok()?;
+    let array = match destination {
+        Object::Dictionary(dict) => document.dereference(dict.get(b"D").ok()?).ok()?.1,
+        other => other,
+    };
+    array.as_array().ok()?.first()?.as_reference().ok()
+}
+
+/// Returns the page an outline item or link annotation jumps to, resolving
+/// named destinations through `named`.
+pub(crate) fn link_target(
+    document: &Document,
+    item: &Dictionary,
+    named: &HashMap<Vec<u8>, Object>,
+) -> Option<ObjectId> {
+    let destination = match item.get(b"Dest") {
+        Ok(destination) => destination,
+        Err(_) => {
+            let action = resolve_dictionary(document, item.get(b"A").ok())?;
+            if action.get(b"S").and_then(Object::as_name).ok() != Some(b"GoTo") {
+                return None;
+            }
+            action.get(b"D").ok()?
+        }
+    };
+    match destination {
+        Object::Name(name) | Object::String(name, _) => {
+            destination_page(document, named.get(name)?)
+        }
+        explicit => destination_page(document, explicit),
+    }
+}
+
+fn rename_destination(destination: &mut Object, renames: &HashMap<Vec<u8>, Vec<u8>>) {
+    let name = match destination {
+        Object::Name(name) | Object::String(name, _) => name,
+        _ => return,
+    };
+    let name = renames.get(name.as_slice()).unwrap_or(name).clone();
+    *destination = Object::String(name, StringFormat::Literal);
+}
+
+fn collect_outline(
+    document: &Document,
+    first: Option<ObjectId>,
+    named: &HashMap<Vec<u8>, Object>,
+    dropped: &HashSet<ObjectId>,
+    unreachable: &mut Vec<ObjectId>,
+    visited: &mut HashSet<ObjectId>,
+) -> Vec<OutlineNode> {
+    let mut nodes = Vec::new();
+    let mut next = first;
+    while let Some(id) = next {
+        let Ok(item) = document.get_dictionary(id) else {
+            break;
+        };
+        if !visited.insert(id) {
+            break;
+        }
+        let first_child = item.get(b"First").and_then(Object::as_reference).ok();
+        let children = collect_outline(document, first_child, named, dropped, unreachable, visited);
+        let dead = link_target(document, item, named).is_some_and(|page| dropped.contains(&page));
+        // Entries pointing at pages that were left out are dropped, unless
+        // they still group entries that were kept
+        if !dead || !children.is_empty() {
+            if dead {
+                unreachable.push(id);
+            }
+            nodes.push(OutlineNode { id, children });
+        }
+        next = item.get(b"Next").and_then(Object::as_reference).ok();
+    }
+    nodes
+}
+
+/// Relinks outline items under `parent` and returns how many entries are
+/// visible below it.
+fn link_outline(
+    document: &mut Document,
+    parent: ObjectId,
+    nodes: &[OutlineNode],
+) -> lopdf::Result<i64> {
+    let mut visible = 0;
+    for (index, node) in nodes.iter().enumerate() {
+        let descendants = link_outline(document, node.id, &node.children)?;
+        let item = document.get_dictionary_mut(node.id)?;
+        item.set("Parent", Object::Reference(parent));
+        for (key, sibling) in [
+            (
+                b"Prev".as_slice(),
+                index.checked_sub(1).map(|i| nodes[i].id),
+            ),
+            (b"Next", nodes.get(index + 1).map(|n| n.id)),
+            (b"First", node.children.first().map(|n| n.id)),
+            (b"Last", node.children.last().map(|n| n.id)),
+        ] {
+            match sibling {
+                Some(id) => item.set(key, Object::Reference(id)),
+                None => {
+                    item.remove(key);
+                }
+            }
+        }
+
+        let open = item
+            .get(b"Count")
+            .and_then(Object::as_i64)
+            .is_ok_and(|count| count > 0);
+        if descendants > 0 {
+            item.set(
+                "Count",
+                Object::Integer(if open { descendants } else { -descendants }),
+            );
+        } else {
+            item.remove(b"Count");
+        }
+        visible += 1 + if open { descendants } else { 0 };
+    }
+    Ok(visible)
+}
+
+fn field_has_widget(
+    document: &Document,
+    field_id: ObjectId,
+    widgets: &HashSet<ObjectId>,
+    depth: usize,
+) -> bool {
+    if widgets.contains(&field_id) {
+        return true;
+    }
+    if depth > 32 {
+        return false;
+    }
+    let kids = document
+        .get_dictionary(field_id)
+        .ok()
+        .and_then(|field| resolve_array(document, field.get(b"Kids").ok()));
+    kids.is_some_and(|kids| {
+        kids.iter()
+            .filter_map(|kid| kid.as_reference().ok())
+            .any(|kid| field_has_widget(document, kid, widgets, depth + 1))
+    })
+}
+
+/// Replaces references to removed pages with `null`, so links and
+/// annotations that pointed at them do not dangle.
+pub(crate) fn null_references(object: &mut Object, removed: &HashSet<ObjectId>) {
+    match object {
+        Object::Reference(id) if removed.contains(id) => *object = Object::Null,
+        Object::Array(items) => items
+            .iter_mut()
+            .for_each(|item| null_references(item, removed)),
+        Object::Dictionary(dict) => dict
+            .iter_mut()
+            .for_each(|(_, value)| null_references(value, removed)),
+        Object::Stream(stream) => stream
+            .dict
+            .iter_mut()
+            .for_each(|(_, value)| null_references(value, removed)),
+        _ => {}
+    }
+}
+
+pub(crate) fn resolve_dictionary<'a>(
+    document: &'a Document,
+    object: Option<&'a Object>,
+) -> Option<&'a Dictionary> {
+    document.dereference(object?).ok()?.1.as_dict().ok()
+}
+
+pub(crate) fn resolve_array<'a>(
+    document: &'a Document,
+    object: Option<&'a Object>,
+) -> Option<&'a Vec<Object>> {
+    document.dereference(object?).ok()?.1.as_array().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_pdf;
+    use tempfile::TempDir;
+
+    fn save(dir: &TempDir, name: &str, document: &mut Document) -> String {
+        let path = dir.path().join(name).to_string_lossy().to_string();
+        document.save(&path).unwrap();
+        path
+    }
+
+    fn page_text(document: &Document, number: u32) -> String {
+        let page_id = document.get_pages()[&number];
+        String::from_utf8_lossy(&document.get_page_content(page_id).unwrap()).to_string()
+    }
+
+    /// Adds a one-entry outline and a named destination, both for page 1.
+    fn add_outline(document: &mut Document, title: &str, destination: &str) {
+        let first_page = document.get_pages()[&1];
+        let target = Object::Array(vec![
+            Object::Reference(first_page),
+            Object::Name(b"Fit".to_vec()),
+        ]);
+
+        let mut item = Dictionary::new();
+        item.set("Title", lopdf::text_string(title));
+        item.set(
+            "Dest",
+            Object::String(destination.as_bytes().to_vec(), StringFormat::Literal),
+        );
+        let item_id = document.add_object(item);
+
+        let mut outlines = Dictionary::new();
+        outlines.set("First", Object::Reference(item_id));
+        outlines.set("Last", Object::Reference(item_id));
+        let outlines_id = document.add_object(outlines);
+        document
+            .get_dictionary_mut(item_id)
+            .unwrap()
+            .set("Parent", Object::Reference(outlines_id));
+
+        let mut dests = Dictionary::new();
+        dests.set(
+            "Names",
+            Object::Array(vec![
+                Object::String(destination.as_bytes().to_vec(), StringFormat::Literal),
+                target,
+            ]),
+        );
+        let mut names = Dictionary::new();
+        names.set("Dests", Object::Dictionary(dests));
+
+        let catalog = document.catalog_mut().unwrap();
+        catalog.set("Outlines", Object::Reference(outlines_id));
+        catalog.set("Names", Object::Dictionary(names));
+    }
+
+    /// Adds a text field with its widget on page 1.
+    fn add_field(document: &mut Document, name: &str) {
+        let first_page = document.get_pages()[&1];
+        let mut field = Dictionary::new();
+        field.set("FT", Object::Name(b"Tx".to_vec()));
+        field.set("T", lopdf::text_string(name));
+        field.set("Type", Object::Name(b"Annot".to_vec()));
+        field.set("Subtype", Object::Name(b"Widget".to_vec()));
+        field.set(
+            "Rect",
+            Object::Array(vec![0.into(), 0.into(), 100.into(), 20.into()]),
+        );
+        field.set("P", Object::Reference(first_page));
+        let field_id = document.add_object(field);
+
+        document
+            .get_dictionary_mut(first_page)
+            .unwrap()
+            .set("Annots", Object::Array(vec![Object::Reference(field_id)]));
+        let mut form = Dictionary::new();
+        form.set("Fields", Object::Array(vec![Object::Reference(field_id)]));
+        document
+            .catalog_mut()
+            .unwrap()
+            .set("AcroForm", Object::Dictionary(form));
+    }
+
+    #[test]
+    fn test_merge_with_page_ranges() {
+        let dir = TempDir::new().unwrap();
+        let first = save(
+            &dir,
+            "first.pdf",
+            &mut create_test_pdf(3, "First", "a").unwrap(),
+        );
+        let second = save(
+            &dir,
+            "second.pdf",
+            &mut create_test_pdf(4, "Second", "b").unwrap(),
+        );
+        let output = dir.path().join("merged.pdf").to_string_lossy().to_string();
+
+        let options = MergeOptions {
+            page_ranges: Some(vec!["".to_string(), "r1,1".to_string()]),
+            ..Default::default()
+        };
+        let summary = merge_pdfs(&[first, second], &output, &options).unwrap();
+        assert_eq!(summary.page_count, 5);
+        assert_eq!(summary.inputs[1].pages, vec![4, 1]);
+
+        let merged = Document::load(&output).unwrap();
+        assert_eq!(merged.get_pages().len(), 5);
+        assert!(page_text(&merged, 3).contains("First - Page 3"));
+        assert!(page_text(&merged, 4).contains("Second - Page 4"));
+        assert!(page_text(&merged, 5).contains("Second - Page 1"));
+
+        // Unselected pages of the second input are not carried along
+        let page_objects = merged
+            .objects
+            .values()
+            .filter(|o| {
+                o.as_dict()
+                    .is_ok_and(|d| d.get_type().ok() == Some(b"Page".as_slice()))
+            })
+            .count();
+        assert_eq!(page_objects, 5);
+    }
+
+    #[test]
+    fn test_collate_reversed_backs() {
+        let dir = TempDir::new().unwrap();
+        let fronts = save(
+            &dir,
+            "fronts.pdf",
+            &mut create_test_pdf(3, "Front", "a").unwrap(),
+        );
+        let backs = save(
+            &dir,
+            "backs.pdf",
+            &mut create_test_pdf(2, "Back", "b").unwrap(),
+        );
+        let output = dir.path().join("scan.pdf").to_string_lossy().to_string();
+
+        let options = CollateOptions {
+            reverse_second: Some(true),
+        };
+        assert_eq!(collate(&fronts, &backs, &output, &options).unwrap(), 5);
+
+        let collated = Document::load(&output).unwrap();
+        let order: Vec<String> = (1..=5)
+            .map(|number| {
+                let text = page_text(&collated, number);
+                let start = text.find("(").unwrap() + 1;
+                text[start..text.find(")").unwrap()].to_string()
+            })
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                "Front - Page 1",
+                "Back - Page 2",
+                "Front - Page 2",
+                "Back - Page 1",
+                "Front - Page 3"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_flattens_inherited_attributes() {
+        let dir = TempDir::new().unwrap();
+        let mut document = create_test_pdf(2, "Inherited", "c").unwrap();
+        let pages_id = document
+            .catalog()
+            .unwrap()
+            .get(b"Pages")
+            .unwrap()
+            .as_reference()
+            .unwrap();
+        for page_id in document.get_pages().into_values() {
+            let page = document.get_dictionary_mut(page_id).unwrap();
+            page.remove(b"MediaBox");
+            page.set("Rotate", Object::Integer(0));
+        }
+        let pages = document.get_dictionary_mut(pages_id).unwrap();
+        pages.set(
+            "MediaBox",
+            Object::Array(vec![0.into(), 0.into(), 595.into(), 842.into()]),
+        );
+        pages.set("Rotate", Object::Integer(90));
+        let input = save(&dir, "inherited.pdf", &mut document);
+        let output = dir.path().join("merged.pdf").to_string_lossy().to_string();
+
+        merge_pdfs(&[input], &output, &MergeOptions::default()).unwrap();
+
+        let merged = Document::load(&output).unwrap();
+        let page = merged.get_dictionary(merged.get_pages()[&1]).unwrap();
+        let media_box = page.get(b"MediaBox").unwrap().as_array().unwrap();
+        assert_eq!(media_box[2].as_i64().unwrap(), 595);
+        // The page's own value wins over the inherited one
+        assert_eq!(page.get(b"Rotate").unwrap().as_i64().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_merge_outlines_and_destinations() {
+        let dir = TempDir::new().unwrap();
+        let mut first = create_test_pdf(2, "First", "a").unwrap();
+        add_outline(&mut first, "Intro", "intro");
+        let mut second = create_test_pdf(2, "Second", "b").unwrap();
+        add_outline(&mut second, "Intro again", "intro");
+        let inputs = [
+            save(&dir, "first.pdf", &mut first),
+            save(&dir, "second.pdf", &mut second),
+        ];
+        let output = dir.path().join("merged.pdf").to_string_lossy().to_string();
+
+        let options = MergeOptions {
+            file_bookmarks: Some(true),
+            ..Default::default()
+        };
+        let summary = merge_pdfs(&inputs, &output, &options).unwrap();
+        assert_eq!(summary.renamed.len(), 1);
+        assert_eq!(summary.renamed[0].to, "intro-2");
+
+        let merged = Document::load(&output).unwrap();
+        let catalog = merged.catalog().unwrap();
+        let named: HashMap<Vec<u8>, Object> =
+            named_destinations(&merged, catalog).into_iter().collect();
+        let pages = merged.get_pages();
+        assert_eq!(
+            destination_page(&merged, &named[b"intro".as_slice()]),
+            Some(pages[&1])
+        );
+        assert_eq!(
+            destination_page(&merged, &named[b"intro-2".as_slice()]),
+            Some(pages[&3])
+        );
+
+        // Second file bookmark -> "Intro again", which now uses the renamed destination
+        let outlines = resolve_dictionary(&merged, catalog.get(b"Outlines").ok()).unwrap();
+        let file_entry = merged
+            .get_dictionary(outlines.get(b"Last").unwrap().as_reference().unwrap())
+            .unwrap();
+        assert_eq!(
+            lopdf::decode_text_string(file_entry.get(b"Title").unwrap()).unwrap(),
+            "second"
+        );
+        let child = merged
+            .get_dictionary(file_entry.get(b"First").unwrap().as_reference().unwrap())
+            .unwrap();
+        assert_eq!(link_target(&merged, child, &named), Some(pages[&3]));
+    }
+
+    #[test]
+    fn test_merge_renames_clashing_fields() {
+        let dir = TempDir::new().unwrap();
+        let mut first = create_test_pdf(1, "First", "a").unwrap();
+        add_field(&mut first, "name");
+        let mut second = create_test_pdf(1, "Second", "b").unwrap();
+        add_field(&mut second, "name");
+        let inputs = [
+            save(&dir, "first.pdf", &mut first),
+            save(&dir, "second.pdf", &mut second),
+        ];
+        let output = dir.path().join("merged.pdf").to_string_lossy().to_string();
+
+        let summary = merge_pdfs(&inputs, &output, &MergeOptions::default()).unwrap();
+        assert_eq!(summary.renamed[0].kind, "field");
+
+        let merged = Document::load(&output).unwrap();
+        let form =
+            resolve_dictionary(&merged, merged.catalog().unwrap().get(b"AcroForm").ok()).unwrap();
+        let names: Vec<String> = resolve_array(&merged, form.get(b"Fields").ok())
+            .unwrap()
+            .iter()
+            .map(|field| {
+                let field = merged
+                    .get_dictionary(field.as_reference().unwrap())
+                    .unwrap();
+                lopdf::decode_text_string(field.get(b"T").unwrap()).unwrap()
+            })
+            .collect();
+        assert_eq!(names, vec!["name", "name_2"]);
+    }
+
+    #[test]
+    fn test_merge_rebuilds_page_labels() {
+        let dir = TempDir::new().unwrap();
+        let mut labeled = create_test_pdf(2, "First", "a").unwrap();
+        let mut range = Dictionary::new();
+        range.set("S", Object::Name(b"D".to_vec()));
+        range.set("P", Object::string_literal("A-"));
+        let mut labels = Dictionary::new();
+        labels.set(
+            "Nums",
+            Object::Array(vec![Object::Integer(0), Object::Dictionary(range)]),
+        );
+        labeled
+            .catalog_mut()
+            .unwrap()
+            .set("PageLabels", Object::Dictionary(labels));
+        let first = save(&dir, "first.pdf", &mut labeled);
+        let second = save(
+            &dir,
+            "second.pdf",
+            &mut create_test_pdf(3, "Second", "b").unwrap(),
+        );
+        let output = dir.path().join("merged.pdf").to_string_lossy().to_string();
+
+        let options = MergeOptions {
+            page_ranges: Some(vec!["".to_string(), "3,1".to_string()]),
+            ..Default::default()
+        };
+        merge_pdfs(&[first, second], &output, &options).unwrap();
+
+        let merged = Document::load(&output).unwrap();
+        let nums = merged
+            .catalog()
+            .unwrap()
+            .get(b"PageLabels")
+            .and_then(Object::as_dict)
+            .and_then(|labels| labels.get(b"Nums"))
+            .and_then(Object::as_array)
+            .unwrap();
+        let ranges: Vec<(i64, Option<Vec<u8>>, i64)> = nums
+            .as_chunks::<2>()
+            .0
+            .iter()
+            .map(|[start, range]| {
+                let range = range.as_dict().unwrap();
+                (
+                    start.as_i64().unwrap(),
+                    range
+                        .get(b"P")
+                        .and_then(Object::as_str)
+                        .ok()
+                        .map(<[u8]>::to_vec),
+                    range.get(b"St").and_then(Object::as_i64).unwrap_or(1),
+                )
+            })
+            .collect();
+        // "A-1", "A-2", then the second file's pages 3 and 1
+        assert_eq!(
+            ranges,
+            vec![(0, Some(b"A-".to_vec()), 1), (2, None, 3), (3, None, 1)]
+        );
+    }
+}
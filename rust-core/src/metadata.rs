@@ -0,0 +1,940 @@
+use lopdf::{Dictionary, Document, Object, Stream};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::info::{object_text, xmp_metadata, xmp_property};
+use crate::stamp::civil_from_days;
+
+/// XMP namespace for custom properties written by this crate.
+const XMP_NAMESPACE: &str = "https://soloflow.ai/ns/solopdf/1.0/";
+const XMP_PREFIX: &str = "solopdf";
+
+/// Standard fields: Info key, XMP property, and whether the value is a date.
+const STANDARD_FIELDS: [(&str, &str, bool); 8] = [
+    ("Title", "dc:title", false),
+    ("Author", "dc:creator", false),
+    ("Subject", "dc:description", false),
+    ("Keywords", "pdf:Keywords", false),
+    ("Creator", "xmp:CreatorTool", false),
+    ("Producer", "pdf:Producer", false),
+    ("CreationDate", "xmp:CreateDate", true),
+    ("ModDate", "xmp:ModifyDate", true),
+];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[napi(object)]
+pub struct DocumentMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Option<String>,
+    pub creator: Option<String>, // Application that created the original document
+    pub producer: Option<String>, // Application that wrote the PDF
+    pub creation_date: Option<String>, // ISO 8601, e.g. "2025-01-31T09:30:00+01:00"
+    pub mod_date: Option<String>,
+    pub custom: Option<HashMap<String, String>>, // Properties in the SoloPDF XMP namespace
+}
+
+impl DocumentMetadata {
+    fn fields(&self) -> [&Option<String>; 8] {
+        [
+            &self.title,
+            &self.author,
+            &self.subject,
+            &self.keywords,
+            &self.creator,
+            &self.producer,
+            &self.creation_date,
+            &self.mod_date,
+        ]
+    }
+
+    fn fields_mut(&mut self) -> [&mut Option<String>; 8] {
+        [
+            &mut self.title,
+            &mut self.author,
+            &mut self.subject,
+            &mut self.keywords,
+            &mut self.creator,
+            &mut self.producer,
+            &mut self.creation_date,
+            &mut self.mod_date,
+        ]
+    }
+}
+
+/// Reads the document metadata, preferring the Info dictionary and falling
+/// back to XMP for fields only recorded there. Dates are returned in ISO 8601.
+pub fn get_metadata(file_path: &str) -> Result<DocumentMetadata, Box<dyn std::error::Error>> {
+    let document = Document::load(file_path)?;
+    Ok(read_metadata(&document))
+}
+
+/// Updates the document metadata and writes it to both the Info dictionary
+/// and the XMP metadata stream.
+///
+/// Fields left as `None` keep their current value and an empty string removes
+/// a field; the same applies to each `custom` property, which is written as
+/// an Info key of the same name too. Info keys that are neither standard
+/// fields nor given here are left untouched. `ModDate` is set to
+/// the current time unless given; only dates given here are validated. The
+/// existing XMP packet is edited in place, so properties this function does
+/// not manage (PDF/A identification, history, other namespaces) are kept.
+///
+/// # Returns
+/// The metadata as written.
+pub fn set_metadata(
+    input_path: &str,
+    output_path: &str,
+    metadata: &DocumentMetadata,
+) -> Result<DocumentMetadata, Box<dyn std::error::Error>> {
+    let mut document = Document::load(input_path)?;
+    let mut merged = read_metadata(&document);
+
+    let mut update = metadata.clone();
+    if update.mod_date.is_none() {
+        update.mod_date = Some(now_iso());
+    }
+    // Dates are kept in ISO 8601 so they can go into XMP as they are. Dates
+    // already in the file are left alone, even when they cannot be parsed
+    for date in [&mut update.creation_date, &mut update.mod_date]
+        .into_iter()
+        .flatten()
+        .filter(|date| !date.is_empty())
+    {
+        *date = iso_to_pdf_date(date)
+            .and_then(|pdf_date| pdf_date_to_iso(&pdf_date))
+            .ok_or_else(|| format!("Invalid date '{date}', expected ISO 8601"))?;
+    }
+    for (current, update) in merged.fields_mut().into_iter().zip(update.fields()) {
+        if let Some(value) = update {
+            *current = (!value.is_empty()).then(|| value.clone());
+        }
+    }
+    let mut custom: BTreeMap<String, String> = merged
+        .custom
+        .take()
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+    for (key, value) in metadata.custom.iter().flatten() {
+        if !is_xml_name(key) || STANDARD_FIELDS.iter().any(|(info_key, ..)| info_key == key) {
+            return Err(format!("Invalid custom property name '{key}'").into());
+        }
+        if value.is_empty() {
+            custom.remove(key);
+        } else {
+            custom.insert(key.clone(), value.clone());
+        }
+    }
+
+    // Info dictionary: standard fields and custom properties as extra keys
+    let mut info = match document.trailer.get(b"Info") {
+        Ok(Object::Reference(id)) => document.get_dictionary(*id)?.clone(),
+        Ok(Object::Dictionary(dict)) => dict.clone(),
+        _ => Dictionary::new(),
+    };
+    for ((key, _, is_date), value) in STANDARD_FIELDS.iter().zip(merged.fields()) {
+        match value {
+            Some(value) if *is_date => {
+                let date = iso_to_pdf_date(value).unwrap_or_else(|| value.clone());
+                info.set(*key, Object::string_literal(date));
+            }
+            Some(value) => info.set(*key, lopdf::text_string(value)),
+            None => {
+                info.remove(key.as_bytes());
+            }
+        }
+    }
+    // Other Info keys, including ones from other applications, are left as
+    // they are
+    for (key, value) in metadata.custom.iter().flatten() {
+        if value.is_empty() {
+            info.remove(key.as_bytes());
+        } else {
+            info.set(key.as_str(), lopdf::text_string(value));
+        }
+    }
+    match document.trailer.get(b"Info") {
+        Ok(Object::Reference(id)) => {
+            let id = *id;
+            document.objects.insert(id, Object::Dictionary(info));
+        }
+        _ => {
+            let info_id = document.add_object(info);
+            document.trailer.set("Info", Object::Reference(info_id));
+        }
+    }
+
+    // XMP packet, keeping everything but the managed properties
+    let old_xmp = xmp_metadata(&document).unwrap_or_default();
+    let packet = xmp_packet(&merged, &custom, &old_xmp);
+    let mut dict = Dictionary::new();
+    dict.set("Type", Object::Name(b"Metadata".to_vec()));
+    dict.set("Subtype", Object::Name(b"XML".to_vec()));
+    let stream = Stream::new(dict, packet.into_bytes());
+    let existing = document
+        .catalog()?
+        .get(b"Metadata")
+        .and_then(Object::as_reference)
+        .ok();
+    match existing {
+        Some(id) => {
+            document.objects.insert(id, Object::Stream(stream));
+        }
+        None => {
+            let metadata_id = document.add_object(stream);
+            document
+                .catalog_mut()?
+                .set("Metadata", Object::Reference(metadata_id));
+        }
+    }
+
+    document.save(output_path)?;
+    merged.custom = (!custom.is_empty()).then(|| custom.into_iter().collect());
+    Ok(merged)
+}
+
+pub(crate) fn read_metadata(document: &Document) -> DocumentMetadata {
+    let info = match document.trailer.get(b"Info") {
+        Ok(Object::Reference(id)) => document.get_dictionary(*id).ok(),
+        Ok(Object::Dictionary(dict)) => Some(dict),
+        _ => None,
+    };
+    let xmp = xmp_metadata(document).unwrap_or_default();
+
+    let mut metadata = DocumentMetadata::default();
+    for ((key, property, is_date), slot) in STANDARD_FIELDS.iter().zip(metadata.fields_mut()) {
+        let from_info = info
+            .and_then(|info| info.get(key.as_bytes()).ok())
+            .and_then(|value| document.dereference(value).ok())
+            .and_then(|(_, value)| object_text(value))
+            .map(|value| {
+                if *is_date {
+                    pdf_date_to_iso(&value).unwrap_or(value)
+                } else {
+                    value
+                }
+            });
+        *slot = from_info
+            .or_else(|| xmp_property(&xmp, property))
+            .filter(|value| !value.is_empty());
+    }
+
+    // Custom properties are the ones in our XMP namespace; an Info key of the
+    // same name takes precedence, as for the standard fields
+    let mut custom: HashMap<String, String> = HashMap::new();
+    for (key, value) in xmp_namespace_properties(&xmp, XMP_PREFIX) {
+        if !is_xml_name(&key) || is_standard_info_key(key.as_bytes()) {
+            continue;
+        }
+        let from_info = info
+            .and_then(|info| info.get(key.as_bytes()).ok())
+            .and_then(|value| document.dereference(value).ok())
+            .and_then(|(_, value)| object_text(value));
+        custom.entry(key).or_insert(from_info.unwrap_or(value));
+    }
+    metadata.custom = (!custom.is_empty()).then_some(custom);
+    metadata
+}
+
+fn is_standard_info_key(key: &[u8]) -> bool {
+    key == b"Trapped"
+        || STANDARD_FIELDS
+            .iter()
+            .any(|(info_key, ..)| info_key.as_bytes() == key)
+}
+
+/// Writes the standard fields and custom properties into `previous`, the
+/// document's current XMP packet, or into a new packet if there is none.
+///
+/// Only the properties managed here are replaced; everything else in the
+/// packet is kept as it is. When the packet identifies the file as PDF/A,
+/// the custom properties are also declared in a `pdfaExtension` schema, as
+/// PDF/A requires for namespaces outside the XMP specification.
+fn xmp_packet(
+    metadata: &DocumentMetadata,
+    custom: &BTreeMap<String, String>,
+    previous: &str,
+) -> String {
+    let mut xmp = if previous.contains("<rdf:Description") {
+        previous.to_string()
+    } else {
+        "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+         <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n \
+         <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n  \
+         <rdf:Description rdf:about=\"\">\n  \
+         </rdf:Description>\n \
+         </rdf:RDF>\n\
+         </x:xmpmeta>\n\
+         <?xpacket end=\"w\"?>"
+            .to_string()
+    };
+
+    let mut managed: Vec<String> = ["dc:format", "xmp:MetadataDate"]
+        .iter()
+        .chain(STANDARD_FIELDS.iter().map(|(_, property, _)| property))
+        .map(|property| property.to_string())
+        .collect();
+    managed.extend(
+        xmp_namespace_properties(&xmp, XMP_PREFIX)
+            .into_iter()
+            .map(|(key, _)| format!("{XMP_PREFIX}:{key}")),
+    );
+    for property in &managed {
+        remove_xmp_property(&mut xmp, property);
+    }
+
+    let mut properties = String::from("   <dc:format>application/pdf</dc:format>\n");
+    for ((_, property, is_date), value) in STANDARD_FIELDS.iter().zip(metadata.fields()) {
+        let Some(value) = value else {
+            continue;
+        };
+        // An unparseable date kept from the Info dictionary is no XMP date
+        if *is_date && iso_to_pdf_date(value).is_none() {
+            continue;
+        }
+        let value = xml_escape(value);
+        let body = match *property {
+            "dc:title" | "dc:description" => {
+                format!("<rdf:Alt><rdf:li xml:lang=\"x-default\">{value}</rdf:li></rdf:Alt>")
+            }
+            "dc:creator" => format!("<rdf:Seq><rdf:li>{value}</rdf:li></rdf:Seq>"),
+            _ => value,
+        };
+        properties.push_str(&format!("   <{property}>{body}</{property}>\n"));
+    }
+    if let Some(date) = metadata
+        .mod_date
+        .as_ref()
+        .filter(|date| iso_to_pdf_date(date).is_some())
+    {
+        properties.push_str(&format!(
+            "   <xmp:MetadataDate>{}</xmp:MetadataDate>\n",
+            xml_escape(date)
+        ));
+    }
+    let mut namespaces = vec![
+        ("dc", "http://purl.org/dc/elements/1.1/"),
+        ("xmp", "http://ns.adobe.com/xap/1.0/"),
+        ("pdf", "http://ns.adobe.com/pdf/1.3/"),
+    ];
+    if !custom.is_empty() {
+        namespaces.push((XMP_PREFIX, XMP_NAMESPACE));
+        for (key, value) in custom {
+            properties.push_str(&format!(
+                "   <{XMP_PREFIX}:{key}>{}</{XMP_PREFIX}:{key}>\n",
+                xml_escape(value)
+            ));
+        }
+    }
+    insert_into_description(&mut xmp, &namespaces, &properties);
+
+    if xmp_property(&xmp, "pdfaid:part").is_some() {
+        declare_pdfa_extension(&mut xmp, custom);
+    }
+    xmp
+}
+
+/// Removes every occurrence of an XMP property, written either as an element
+/// or as an attribute of an `rdf:Description`.
+fn remove_xmp_property(xmp: &mut String, name: &str) {
+    let open = format!("<{name}");
+    let mut from = 0;
+    while let Some(found) = xmp[from..].find(&open) {
+        let start = from + found;
+        let after = &xmp[start + open.len()..];
+        // Skip a longer name sharing the prefix (e.g. pdf:ProducerX)
+        if !after.starts_with(['>', '/', ' ', '\t', '\r', '\n']) {
+            from = start + open.len();
+            continue;
+        }
+        let Some(tag_end) = tag_end(xmp, start) else {
+            return;
+        };
+        let end = if xmp[..tag_end].ends_with('/') {
+            tag_end + 1
+        } else {
+            match xmp[tag_end..].find(&format!("</{name}>")) {
+                Some(close) => tag_end + close + name.len() + 3,
+                None => return,
+            }
+        };
+        let start = xmp[..start].trim_end_matches([' ', '\t']).len();
+        let end = end + usize::from(xmp[end..].starts_with('\n'));
+        xmp.replace_range(start..end, "");
+        from = start;
+    }
+
+    let mut from = 0;
+    while let Some(found) = xmp[from..].find(&format!("{name}=")) {
+        let start = from + found;
+        let value = start + name.len() + 1;
+        let quote = xmp[value..].chars().next();
+        let preceded_by_space = xmp[..start].ends_with(char::is_whitespace);
+        let (Some(quote @ ('"' | '\'')), true) = (quote, preceded_by_space) else {
+            from = value;
+            continue;
+        };
+        let Some(close) = xmp[value + 1..].find(quote) else {
+            return;
+        };
+        let start = xmp[..start].trim_end().len();
+        xmp.replace_range(start..value + 1 + close + 1, "");
+        from = start;
+    }
+}
+
+/// Adds `properties` to the first `rdf:Description` of the packet, declaring
+/// the given namespaces on it.
+fn insert_into_description(xmp: &mut String, namespaces: &[(&str, &str)], properties: &str) {
+    let Some(start) = xmp.find("<rdf:Description") else {
+        return;
+    };
+    let Some(mut end) = tag_end(xmp, start) else {
+        return;
+    };
+    let self_closing = xmp[..end].ends_with('/');
+    if self_closing {
+        end -= 1;
+    }
+    let declarations: String = namespaces
+        .iter()
+        .filter(|(prefix, _)| !xmp[start..end].contains(&format!("xmlns:{prefix}=")))
+        .map(|(prefix, uri)| format!("\n    xmlns:{prefix}=\"{uri}\""))
+        .collect();
+    let body = if self_closing {
+        format!("{declarations}>\n{properties}  </rdf:Description>")
+    } else {
+        format!("{declarations}>\n{properties}")
+    };
+    // The new properties bring their own line break after the start tag
+    let replaced = if self_closing {
+        end..end + 2
+    } else {
+        end..end + 1 + usize::from(xmp[end + 1..].starts_with('\n'))
+    };
+    xmp.replace_range(replaced, &body);
+}
+
+/// Declares the custom properties in the packet's PDF/A extension schemas,
+/// replacing an earlier declaration of the SoloPDF namespace.
+fn declare_pdfa_extension(xmp: &mut String, custom: &BTreeMap<String, String>) {
+    let declarations: String = custom
+        .keys()
+        .map(|key| {
+            format!(
+                "        <rdf:li rdf:parseType=\"Resource\">\n         \
+                 <pdfaProperty:name>{key}</pdfaProperty:name>\n         \
+                 <pdfaProperty:valueType>Text</pdfaProperty:valueType>\n         \
+                 <pdfaProperty:category>external</pdfaProperty:category>\n         \
+                 <pdfaProperty:description>Custom document property</pdfaProperty:description>\n        \
+                 </rdf:li>\n"
+            )
+        })
+        .collect();
+    let schema = format!(
+        "     <rdf:li rdf:parseType=\"Resource\"\n       \
+         xmlns:pdfaSchema=\"http://www.aiim.org/pdfa/ns/schema#\"\n       \
+         xmlns:pdfaProperty=\"http://www.aiim.org/pdfa/ns/property#\">\n      \
+         <pdfaSchema:schema>SoloPDF custom properties</pdfaSchema:schema>\n      \
+         <pdfaSchema:namespaceURI>{XMP_NAMESPACE}</pdfaSchema:namespaceURI>\n      \
+         <pdfaSchema:prefix>{XMP_PREFIX}</pdfaSchema:prefix>\n      \
+         <pdfaSchema:property>\n       <rdf:Seq>\n{declarations}       </rdf:Seq>\n      \
+         </pdfaSchema:property>\n     </rdf:li>\n"
+    );
+
+    let bag = xmp
+        .find("<pdfaExtension:schemas")
+        .and_then(|schemas| Some(schemas + xmp[schemas..].find("<rdf:Bag")?))
+        .and_then(|bag| tag_end(xmp, bag))
+        .filter(|end| !xmp[..*end].ends_with('/'));
+    let Some(bag_end) = bag else {
+        if custom.is_empty() {
+            return;
+        }
+        let description = format!(
+            "  <rdf:Description rdf:about=\"\"\n    \
+             xmlns:pdfaExtension=\"http://www.aiim.org/pdfa/ns/extension/\">\n   \
+             <pdfaExtension:schemas>\n    <rdf:Bag>\n{schema}    </rdf:Bag>\n   \
+             </pdfaExtension:schemas>\n  </rdf:Description>\n "
+        );
+        let at = xmp
+            .find("</rdf:RDF>")
+            .or_else(|| {
+                xmp.rfind("</rdf:Description>")
+                    .map(|end| end + "</rdf:Description>".len())
+            })
+            .unwrap_or(xmp.len());
+        xmp.insert_str(at, &description);
+        return;
+    };
+
+    // Drop an earlier SoloPDF schema, walking the bag's top-level items
+    let body_start = bag_end + 1;
+    let body_end = body_start + xmp[body_start..].find("</rdf:Bag>").unwrap_or(0);
+    let mut cursor = body_start;
+    while let Some(found) = xmp[cursor..body_end].find("<rdf:li") {
+        let start = cursor + found;
+        let mut depth = 0;
+        let mut position = start;
+        let end = loop {
+            let next_open = xmp[position + 1..]
+                .find("<rdf:li")
+                .map(|at| position + 1 + at);
+            let Some(next_close) = xmp[position + 1..]
+                .find("</rdf:li>")
+                .map(|at| position + 1 + at)
+            else {
+                break None;
+            };
+            match next_open {
+                Some(open) if open < next_close => {
+                    depth += 1;
+                    position = open;
+                }
+                _ if depth > 0 => {
+                    depth -= 1;
+                    position = next_close;
+                }
+                _ => break Some(next_close + "</rdf:li>".len()),
+            }
+        };
+        let Some(end) = end else {
+            break;
+        };
+        let namespace =
+            format!("<pdfaSchema:namespaceURI>{XMP_NAMESPACE}</pdfaSchema:namespaceURI>");
+        if xmp[start..end].contains(&namespace) {
+            let start = xmp[..start].trim_end_matches([' ', '\t']).len();
+            let end = end + usize::from(xmp[end..].starts_with('\n'));
+            xmp.replace_range(start..end, "");
+            break;
+        }
+        cursor = end;
+    }
+    if !custom.is_empty() {
+        let at = body_start + usize::from(xmp[body_start..].starts_with('\n'));
+        let separator = if at == body_start { "\n" } else { "" };
+        xmp.insert_str(at, &format!("{separator}{schema}"));
+    }
+}
+
+/// Finds the `>` that closes the tag starting at `start`, skipping quoted
+/// attribute values.
+fn tag_end(xml: &str, start: usize) -> Option<usize> {
+    let mut quote = None;
+    for (offset, c) in xml[start..].char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if open == c => quote = None,
+            (None, '>') => return Some(start + offset),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Reads the simple properties written with `prefix` in an XMP packet, as
+/// elements (`<p:Key>value</p:Key>`) or attributes (`p:Key="value"`).
+fn xmp_namespace_properties(xmp: &str, prefix: &str) -> Vec<(String, String)> {
+    let mut properties = Vec::new();
+    let element = format!("<{prefix}:");
+    let attribute = format!("{prefix}:");
+    let mut rest = xmp;
+    while let Some(start) = rest.find(&attribute) {
+        let after = &rest[start + attribute.len()..];
+        let name: String = after
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+            .collect();
+        let is_element = rest[..start].ends_with('<');
+        let is_attribute =
+            rest[..start].ends_with(char::is_whitespace) && after[name.len()..].starts_with("=\"");
+        if !name.is_empty() && (is_element || is_attribute) {
+            let property = format!("{prefix}:{name}");
+            if let Some(value) = xmp_property(&rest[start - usize::from(is_element)..], &property) {
+                properties.push((name.clone(), value));
+            }
+        }
+        rest = &after[name.len()..];
+        // Skip past the closing tag of an element so it is not read again
+        if is_element {
+            if let Some(end) = rest.find(&format!("</{prefix}:{name}>")) {
+                rest = &rest[end + element.len() + name.len() + 2..];
+            }
+        }
+    }
+    properties
+}
+
+fn is_xml_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Converts a PDF date (`D:YYYYMMDDHHmmSSOHH'mm'`, trailing parts optional)
+/// to ISO 8601.
+pub(crate) fn pdf_date_to_iso(date: &str) -> Option<String> {
+    let date = date.trim().strip_prefix("D:").unwrap_or(date.trim());
+    let digits: String = date.chars().take_while(char::is_ascii_digit).collect();
+    if digits.len() < 4 || digits.len() > 14 || !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    let part =
+        |start: usize, default: &str| digits.get(start..start + 2).unwrap_or(default).to_string();
+    let mut iso = format!(
+        "{}-{}-{}T{}:{}:{}",
+        &digits[..4],
+        part(4, "01"),
+        part(6, "01"),
+        part(8, "00"),
+        part(10, "00"),
+        part(12, "00")
+    );
+    let zone = &date[digits.len()..];
+    match zone.chars().next() {
+        Some('Z') => iso.push('Z'),
+        Some(sign @ ('+' | '-')) => {
+            let offset: String = zone[1..].chars().filter(char::is_ascii_digit).collect();
+            let hours = offset.get(..2)?;
+            let minutes = offset.get(2..4).unwrap_or("00");
+            iso.push_str(&format!("{sign}{hours}:{minutes}"));
+        }
+        _ => {}
+    }
+    Some(iso)
+}
+
+/// Converts an ISO 8601 date or date-time to a PDF date string. PDF dates
+/// are passed through unchanged.
+pub(crate) fn iso_to_pdf_date(date: &str) -> Option<String> {
+    let date = date.trim();
+    if date.starts_with("D:") {
+        return pdf_date_to_iso(date).map(|_| date.to_string());
+    }
+    let (day, time) = date.split_once('T').unwrap_or((date, ""));
+    let day_parts: Vec<&str> = day.split('-').collect();
+    if day_parts.len() != 3 || day_parts[0].len() != 4 {
+        return None;
+    }
+    let (clock, zone) = match time.find(['Z', '+', '-']) {
+        Some(index) => time.split_at(index),
+        None => (time, ""),
+    };
+    // Fractional seconds have no PDF equivalent
+    let clock = clock.split('.').next().unwrap_or_default();
+    let clock_parts: Vec<&str> = if clock.is_empty() {
+        Vec::new()
+    } else {
+        clock.split(':').collect()
+    };
+    let mut pdf = String::from("D:");
+    for part in day_parts.iter().chain(clock_parts.iter()) {
+        if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        pdf.push_str(part);
+    }
+    match zone.chars().next() {
+        Some('Z') => pdf.push('Z'),
+        Some(sign) => {
+            let (hours, minutes) = zone[1..].split_once(':').unwrap_or((&zone[1..], "00"));
+            pdf.push_str(&format!("{sign}{hours}'{minutes}'"));
+        }
+        None => {}
+    }
+    pdf_date_to_iso(&pdf).map(|_| pdf)
+}
+
+/// The current UTC time in ISO 8601.
+fn now_iso() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+    let time = seconds.rem_euclid(86_400);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_pdf;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_date_conversion() {
+        assert_eq!(
+            pdf_date_to_iso("D:20250131093000+01'00'").as_deref(),
+            Some("2025-01-31T09:30:00+01:00")
+        );
+        assert_eq!(
+            pdf_date_to_iso("D:2024").as_deref(),
+            Some("2024-01-01T00:00:00")
+        );
+        assert_eq!(
+            iso_to_pdf_date("2025-01-31T09:30:00.250-05:00").as_deref(),
+            Some("D:20250131093000-05'00'")
+        );
+        assert_eq!(iso_to_pdf_date("2025-01-31").as_deref(), Some("D:20250131"));
+        assert_eq!(iso_to_pdf_date("last tuesday"), None);
+    }
+
+    #[test]
+    fn test_set_and_get_metadata() {
+        let dir = TempDir::new().unwrap();
+        let input = dir.path().join("input.pdf").to_string_lossy().to_string();
+        let output = dir.path().join("output.pdf").to_string_lossy().to_string();
+        create_test_pdf(1, "Metadata", "text")
+            .unwrap()
+            .save(&input)
+            .unwrap();
+
+        let update = DocumentMetadata {
+            title: Some("Contract <Draft> & Terms".to_string()),
+            author: Some("Legal".to_string()),
+            creation_date: Some("2025-01-31T09:30:00+01:00".to_string()),
+            custom: Some(HashMap::from([
+                ("DocumentId".to_string(), "DMS-4711".to_string()),
+                ("Department".to_string(), "Legal".to_string()),
+            ])),
+            ..Default::default()
+        };
+        set_metadata(&input, &output, &update).unwrap();
+
+        let document = Document::load(&output).unwrap();
+        let xmp = xmp_metadata(&document).unwrap();
+        assert!(xmp.contains(
+            "<rdf:li xml:lang=\"x-default\">Contract &lt;Draft&gt; &amp; Terms</rdf:li>"
+        ));
+        assert!(xmp.contains("<solopdf:DocumentId>DMS-4711</solopdf:DocumentId>"));
+        assert!(xmp.contains("<xmp:CreateDate>2025-01-31T09:30:00+01:00</xmp:CreateDate>"));
+
+        let metadata = get_metadata(&output).unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Contract <Draft> & Terms"));
+        assert_eq!(
+            metadata.creation_date.as_deref(),
+            Some("2025-01-31T09:30:00+01:00")
+        );
+        assert!(metadata.mod_date.is_some());
+        assert_eq!(metadata.custom.as_ref().unwrap()["DocumentId"], "DMS-4711");
+
+        // Empty strings remove fields; other fields are kept
+        let removal = DocumentMetadata {
+            author: Some(String::new()),
+            custom: Some(HashMap::from([("Department".to_string(), String::new())])),
+            ..Default::default()
+        };
+        set_metadata(&output, &output, &removal).unwrap();
+        let metadata = get_metadata(&output).unwrap();
+        assert_eq!(metadata.author, None);
+        assert_eq!(metadata.title.as_deref(), Some("Contract <Draft> & Terms"));
+        let custom = metadata.custom.unwrap();
+        assert_eq!(custom.len(), 1);
+        let xmp = xmp_metadata(&Document::load(&output).unwrap()).unwrap();
+        assert!(!xmp.contains("dc:creator") && !xmp.contains("Department"));
+    }
+
+    #[test]
+    fn test_xmp_only_metadata_and_identification() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("xmp.pdf").to_string_lossy().to_string();
+        let mut document = create_test_pdf(1, "Metadata", "text").unwrap();
+        let xmp = r#"<rdf:Description xmlns:pdfaid="http://www.aiim.org/pdfa/ns/id/" pdfaid:part="2" pdfaid:conformance="B" solopdf:Batch="7">
+            <pdf:Producer>Scanner 3000</pdf:Producer>
+            <solopdf:Owner>Ops</solopdf:Owner>
+            </rdf:Description>"#;
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::Name(b"Metadata".to_vec()));
+        let metadata_id = document.add_object(Stream::new(dict, xmp.as_bytes().to_vec()));
+        document
+            .catalog_mut()
+            .unwrap()
+            .set("Metadata", Object::Reference(metadata_id));
+        document.save(&path).unwrap();
+
+        let metadata = get_metadata(&path).unwrap();
+        assert_eq!(metadata.producer.as_deref(), Some("Scanner 3000"));
+        let custom = metadata.custom.unwrap();
+        assert_eq!(
+            (custom["Batch"].as_str(), custom["Owner"].as_str()),
+            ("7", "Ops")
+        );
+
+        set_metadata(&path, &path, &DocumentMetadata::default()).unwrap();
+        let xmp = xmp_metadata(&Document::load(&path).unwrap()).unwrap();
+        assert_eq!(xmp_property(&xmp, "pdfaid:part").as_deref(), Some("2"));
+        assert!(xmp.contains("<solopdf:Owner>Ops</solopdf:Owner>"));
+        assert!(!xmp.contains("solopdf:Batch=\"7\""));
+        assert!(xmp.contains("<solopdf:Batch>7</solopdf:Batch>"));
+    }
+
+    #[test]
+    fn test_set_metadata_edits_xmp_in_place() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("pdfa.pdf").to_string_lossy().to_string();
+        let mut document = create_test_pdf(1, "Metadata", "text").unwrap();
+        let xmp = r#"<?xpacket begin="" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about="" xmlns:pdfaid="http://www.aiim.org/pdfa/ns/id/">
+   <pdfaid:part>2</pdfaid:part>
+   <pdfaid:conformance>B</pdfaid:conformance>
+  </rdf:Description>
+  <rdf:Description rdf:about="" xmlns:dc="http://purl.org/dc/elements/1.1/"
+    xmlns:xmpMM="http://ns.adobe.com/xap/1.0/mm/" xmlns:fx="http://ns.example.com/fx/1.0/">
+   <dc:title><rdf:Alt><rdf:li xml:lang="x-default">Old title</rdf:li></rdf:Alt></dc:title>
+   <xmpMM:DocumentID>uuid:1234</xmpMM:DocumentID>
+   <fx:Department>Legal</fx:Department>
+  </rdf:Description>
+  <rdf:Description rdf:about=""
+    xmlns:pdfaExtension="http://www.aiim.org/pdfa/ns/extension/"
+    xmlns:pdfaSchema="http://www.aiim.org/pdfa/ns/schema#">
+   <pdfaExtension:schemas>
+    <rdf:Bag>
+     <rdf:li rdf:parseType="Resource">
+      <pdfaSchema:namespaceURI>http://ns.example.com/fx/1.0/</pdfaSchema:namespaceURI>
+      <pdfaSchema:prefix>fx</pdfaSchema:prefix>
+     </rdf:li>
+    </rdf:Bag>
+   </pdfaExtension:schemas>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>"#;
+        let metadata_id =
+            document.add_object(Stream::new(Dictionary::new(), xmp.as_bytes().to_vec()));
+        document
+            .catalog_mut()
+            .unwrap()
+            .set("Metadata", Object::Reference(metadata_id));
+        document.save(&path).unwrap();
+
+        let update = DocumentMetadata {
+            title: Some("New title".to_string()),
+            custom: Some(HashMap::from([("Batch".to_string(), "7".to_string())])),
+            ..Default::default()
+        };
+        set_metadata(&path, &path, &update).unwrap();
+        set_metadata(&path, &path, &update).unwrap();
+
+        let xmp = xmp_metadata(&Document::load(&path).unwrap()).unwrap();
+        assert_eq!(xmp_property(&xmp, "dc:title").as_deref(), Some("New title"));
+        assert_eq!(xmp.matches("<dc:title>").count(), 1);
+        assert!(xmp.contains("<xmpMM:DocumentID>uuid:1234</xmpMM:DocumentID>"));
+        assert!(xmp.contains("<fx:Department>Legal</fx:Department>"));
+        assert!(xmp.contains("<pdfaSchema:prefix>fx</pdfaSchema:prefix>"));
+        assert_eq!(
+            xmp_property(&xmp, "pdfaid:conformance").as_deref(),
+            Some("B")
+        );
+        // The custom namespace is declared for PDF/A once, however often it is written
+        let declaration =
+            format!("<pdfaSchema:namespaceURI>{XMP_NAMESPACE}</pdfaSchema:namespaceURI>");
+        assert_eq!(xmp.matches(&declaration).count(), 1);
+        assert!(xmp.contains("<pdfaProperty:name>Batch</pdfaProperty:name>"));
+
+        let removal = DocumentMetadata {
+            custom: Some(HashMap::from([("Batch".to_string(), String::new())])),
+            ..Default::default()
+        };
+        set_metadata(&path, &path, &removal).unwrap();
+        let xmp = xmp_metadata(&Document::load(&path).unwrap()).unwrap();
+        assert!(!xmp.contains(&declaration) && !xmp.contains("solopdf:Batch"));
+        assert!(xmp.contains("<pdfaSchema:prefix>fx</pdfaSchema:prefix>"));
+    }
+
+    #[test]
+    fn test_set_metadata_keeps_unparseable_existing_dates() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("dates.pdf").to_string_lossy().to_string();
+        let mut document = create_test_pdf(1, "Metadata", "text").unwrap();
+        let mut info = Dictionary::new();
+        info.set("CreationDate", Object::string_literal("sometime in 2019"));
+        let info_id = document.add_object(info);
+        document.trailer.set("Info", Object::Reference(info_id));
+        document.save(&path).unwrap();
+
+        let update = DocumentMetadata {
+            title: Some("Report".to_string()),
+            ..Default::default()
+        };
+        let written = set_metadata(&path, &path, &update).unwrap();
+        assert_eq!(written.creation_date.as_deref(), Some("sometime in 2019"));
+        let metadata = get_metadata(&path).unwrap();
+        assert_eq!(metadata.creation_date.as_deref(), Some("sometime in 2019"));
+        let xmp = xmp_metadata(&Document::load(&path).unwrap()).unwrap();
+        assert!(!xmp.contains("xmp:CreateDate"));
+
+        let invalid = DocumentMetadata {
+            mod_date: Some("next week".to_string()),
+            ..Default::default()
+        };
+        assert!(set_metadata(&path, &path, &invalid).is_err());
+    }
+
+    #[test]
+    fn test_set_metadata_leaves_other_info_keys_alone() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("info.pdf").to_string_lossy().to_string();
+        let mut document = create_test_pdf(1, "Metadata", "text").unwrap();
+        let keywords = Object::Array(vec![
+            Object::string_literal("invoice"),
+            Object::string_literal("2025"),
+        ]);
+        let mut info = Dictionary::new();
+        info.set("AAPL:Keywords", keywords.clone());
+        info.set("Has Space", Object::string_literal("value"));
+        info.set("Revision", Object::Integer(3));
+        let info_id = document.add_object(info);
+        document.trailer.set("Info", Object::Reference(info_id));
+        document.save(&path).unwrap();
+
+        let update = DocumentMetadata {
+            custom: Some(HashMap::from([(
+                "DocumentId".to_string(),
+                "DMS-4711".to_string(),
+            )])),
+            ..Default::default()
+        };
+        set_metadata(&path, &path, &update).unwrap();
+        set_metadata(&path, &path, &DocumentMetadata::default()).unwrap();
+
+        let document = Document::load(&path).unwrap();
+        let info_id = document
+            .trailer
+            .get(b"Info")
+            .and_then(Object::as_reference)
+            .unwrap();
+        let info = document.get_dictionary(info_id).unwrap();
+        assert_eq!(info.get(b"AAPL:Keywords").unwrap(), &keywords);
+        assert_eq!(info.get(b"Revision").unwrap(), &Object::Integer(3));
+        assert!(info.has(b"Has Space"));
+        assert!(info.has(b"DocumentId"));
+
+        let xmp = xmp_metadata(&document).unwrap();
+        assert!(!xmp.contains("AAPL") && !xmp.contains("Has") && !xmp.contains("Revision"));
+        assert!(xmp.contains("<solopdf:DocumentId>DMS-4711</solopdf:DocumentId>"));
+        let custom = get_metadata(&path).unwrap().custom.unwrap();
+        assert_eq!(custom.keys().collect::<Vec<_>>(), vec!["DocumentId"]);
+    }
+}
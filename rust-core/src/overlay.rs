@@ -0,0 +1,369 @@
+use lopdf::content::{Content, Operation};
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use napi_derive::napi;
+use std::collections::HashSet;
+
+use crate::impose::page_form_xobject;
+use crate::layers;
+use crate::merge::null_references;
+use crate::page_range::select_pages;
+use crate::sign::{add_page_resource, append_page_content, page_box, prepend_page_content};
+use crate::validate::collect_references;
+use crate::watermark::matrix_operands;
+
+#[derive(Debug, Clone, Default)]
+#[napi(object)]
+pub struct OverlayOptions {
+    pub template_page: Option<u32>, // Page of the template to use (default: 1)
+    pub page_range: Option<String>, // Target pages (default: all)
+    pub placement: Option<String>,  // "overlay" (default) or "underlay"
+    pub scale: Option<String>, // "fit" (default) scales into the visible page, "none" keeps template size
+    pub opacity: Option<f64>,  // 0.0 to 1.0 (default: 1.0)
+    pub layer_name: Option<String>, // Put the template in a removable optional content group
+    pub layer_visibility: Option<String>, // "always" (default), "print", "screen"
+}
+
+/// Draws one page of a template PDF over (or under) the selected pages of
+/// the input.
+///
+/// The template page is copied in once as a Form XObject, together with the
+/// fonts, images and other resources it uses, and every target page draws
+/// that same object. Target pages are used in their unrotated coordinates.
+///
+/// # Returns
+/// The number of pages stamped.
+pub fn overlay_pdf(
+    input_path: &str,
+    template_path: &str,
+    output_path: &str,
+    options: &OverlayOptions,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    let underlay = match options.placement.as_deref().unwrap_or("overlay") {
+        "overlay" => false,
+        "underlay" => true,
+        other => return Err(format!("Unsupported placement: '{other}'").into()),
+    };
+    let fit = match options.scale.as_deref().unwrap_or("fit") {
+        "fit" => true,
+        "none" => false,
+        other => return Err(format!("Unsupported scale: '{other}'").into()),
+    };
+
+    let mut document = Document::load(input_path)?;
+    let page_ids = select_pages(&document, options.page_range.as_deref(), None)?;
+    let template = Document::load(template_path)?;
+    let (form_id, [llx, lly, urx, ury]) =
+        import_page_form(&mut document, template, options.template_page.unwrap_or(1))?;
+
+    let layer_id = match options.layer_name.as_deref() {
+        Some(name) => Some(layers::ensure_layer(
+            &mut document,
+            name,
+            options.layer_visibility.as_deref(),
+        )?),
+        None => None,
+    };
+    // Named after the objects, so a second overlay adds to the page
+    // resources instead of replacing the first one's entries
+    let xobject_name = format!("SoloTemplate{}", form_id.0);
+    let graphics_state_id = options.opacity.map(|opacity| {
+        let opacity = opacity.clamp(0.0, 1.0) as f32;
+        let mut graphics_state = Dictionary::new();
+        graphics_state.set("Type", Object::Name(b"ExtGState".to_vec()));
+        graphics_state.set("ca", Object::Real(opacity));
+        graphics_state.set("CA", Object::Real(opacity));
+        document.add_object(graphics_state)
+    });
+
+    for page_id in &page_ids {
+        let [page_llx, page_lly, page_urx, page_ury] = page_box(&document, *page_id);
+        let (width, height) = (urx - llx, ury - lly);
+        let matrix = if fit {
+            let (page_width, page_height) = (page_urx - page_llx, page_ury - page_lly);
+            let scale = (page_width / width).min(page_height / height);
+            [
+                scale,
+                0.0,
+                0.0,
+                scale,
+                page_llx + (page_width - width * scale) / 2.0 - llx * scale,
+                page_lly + (page_height - height * scale) / 2.0 - lly * scale,
+            ]
+        } else {
+            [1.0, 0.0, 0.0, 1.0, page_llx - llx, page_lly - lly]
+        };
+
+        add_page_resource(
+            &mut document,
+            *page_id,
+            "XObject",
+            &xobject_name,
+            Object::Reference(form_id),
+        )?;
+        let mut operations = vec![Operation::new("q", vec![])];
+        if let Some(graphics_state_id) = graphics_state_id {
+            let graphics_state_name = format!("SoloTemplateGS{}", graphics_state_id.0);
+            add_page_resource(
+                &mut document,
+                *page_id,
+                "ExtGState",
+                &graphics_state_name,
+                Object::Reference(graphics_state_id),
+            )?;
+            operations.push(Operation::new(
+                "gs",
+                vec![Object::Name(graphics_state_name.into_bytes())],
+            ));
+        }
+        operations.push(Operation::new("cm", matrix_operands(matrix)));
+        operations.push(Operation::new(
+            "Do",
+            vec![Object::Name(xobject_name.clone().into_bytes())],
+        ));
+        operations.push(Operation::new("Q", vec![]));
+        if let Some(layer_id) = layer_id {
+            operations = layers::wrap_in_layer(&mut document, *page_id, layer_id, operations)?;
+        }
+
+        // Content streams are concatenated, so end on a token boundary
+        let mut content_data = Content { operations }.encode()?;
+        content_data.push(b'\n');
+        let content_id = document.add_object(Stream::new(Dictionary::new(), content_data));
+        if underlay {
+            prepend_page_content(&mut document, *page_id, content_id);
+        } else {
+            append_page_content(&mut document, *page_id, content_id);
+        }
+    }
+
+    document.save(output_path)?;
+    Ok(page_ids.len() as u32)
+}
+
+/// Copies page `page_number` of `source` into `document` as a Form XObject,
+/// along with every object the form references.
+///
+/// # Returns
+/// The id of the form and its bounding box.
+pub(crate) fn import_page_form(
+    document: &mut Document,
+    mut source: Document,
+    page_number: u32,
+) -> Result<(ObjectId, [f64; 4]), Box<dyn std::error::Error>> {
+    if source.is_encrypted() {
+        return Err("Template PDF is encrypted".into());
+    }
+    source.renumber_objects_with(document.max_id + 1);
+    let pages = source.get_pages();
+    let page_id = *pages.get(&page_number).ok_or_else(|| {
+        format!(
+            "Template page {page_number} is out of range (template has {} pages)",
+            pages.len()
+        )
+    })?;
+    let form_id = page_form_xobject(&mut source, page_id)?;
+    let bbox = page_box(&source, page_id);
+
+    // Resources may point back at pages (e.g. through structure elements);
+    // those links are dropped rather than pulling the page tree along
+    let structural: HashSet<ObjectId> = source
+        .objects
+        .iter()
+        .filter(|(_, object)| {
+            object.as_dict().is_ok_and(|dict| {
+                matches!(
+                    dict.get(b"Type").and_then(Object::as_name),
+                    Ok(b"Page" | b"Pages" | b"Catalog")
+                )
+            })
+        })
+        .map(|(id, _)| *id)
+        .collect();
+
+    let mut pending = vec![form_id];
+    let mut copied = HashSet::new();
+    while let Some(id) = pending.pop() {
+        if structural.contains(&id) || !copied.insert(id) {
+            continue;
+        }
+        let Some(mut object) = source.objects.remove(&id) else {
+            continue;
+        };
+        null_references(&mut object, &structural);
+        collect_references(&object, &mut pending);
+        document.objects.insert(id, object);
+    }
+    document.max_id = document.max_id.max(source.max_id);
+
+    Ok((form_id, bbox))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_pdf;
+    use tempfile::TempDir;
+
+    fn save(dir: &TempDir, name: &str, pages: u32, title: &str) -> String {
+        let path = dir.path().join(name).to_string_lossy().to_string();
+        create_test_pdf(pages, title, "text")
+            .unwrap()
+            .save(&path)
+            .unwrap();
+        path
+    }
+
+    fn content(document: &Document, number: u32) -> String {
+        let page_id = document.get_pages()[&number];
+        String::from_utf8_lossy(&document.get_page_content(page_id).unwrap()).to_string()
+    }
+
+    fn template_forms(document: &Document, number: u32) -> Vec<ObjectId> {
+        let page_id = document.get_pages()[&number];
+        document
+            .get_dictionary(page_id)
+            .and_then(|page| page.get(b"Resources"))
+            .and_then(Object::as_dict)
+            .and_then(|resources| resources.get(b"XObject"))
+            .and_then(Object::as_dict)
+            .unwrap()
+            .iter()
+            .filter(|(name, _)| name.starts_with(b"SoloTemplate"))
+            .filter_map(|(_, form)| form.as_reference().ok())
+            .collect()
+    }
+
+    #[test]
+    fn test_overlay_template_page() {
+        let dir = TempDir::new().unwrap();
+        let input = save(&dir, "letter.pdf", 3, "Letter");
+        let template = save(&dir, "stationery.pdf", 2, "Stationery");
+        let output = dir.path().join("out.pdf").to_string_lossy().to_string();
+
+        let options = OverlayOptions {
+            template_page: Some(2),
+            page_range: Some("1-2".to_string()),
+            opacity: Some(0.5),
+            ..Default::default()
+        };
+        assert_eq!(
+            overlay_pdf(&input, &template, &output, &options).unwrap(),
+            2
+        );
+
+        let document = Document::load(&output).unwrap();
+        assert!(content(&document, 1).contains(" Do"));
+        assert!(content(&document, 1).contains("/SoloTemplate"));
+        assert!(!content(&document, 3).contains("SoloTemplate"));
+
+        // The form carries the template page's content and its font
+        let page_id = document.get_pages()[&1];
+        let resources = document
+            .get_dictionary(page_id)
+            .unwrap()
+            .get(b"Resources")
+            .and_then(Object::as_dict)
+            .unwrap();
+        let form_id = template_forms(&document, 1)[0];
+        let form = document
+            .get_object(form_id)
+            .and_then(Object::as_stream)
+            .unwrap();
+        let data = form.decompressed_content().unwrap_or(form.content.clone());
+        assert!(String::from_utf8_lossy(&data).contains("Stationery - Page 2"));
+        let form_resources = form
+            .dict
+            .get(b"Resources")
+            .and_then(Object::as_dict)
+            .unwrap();
+        assert!(form_resources.get(b"Font").is_ok());
+        assert!(resources.get(b"ExtGState").is_ok());
+
+        let template_pages = document
+            .objects
+            .values()
+            .filter(|object| object.type_name().ok() == Some(b"Page".as_slice()))
+            .count();
+        assert_eq!(template_pages, 3);
+
+        assert!(overlay_pdf(
+            &input,
+            &template,
+            &output,
+            &OverlayOptions {
+                template_page: Some(5),
+                ..Default::default()
+            }
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_underlay_is_drawn_first() {
+        let dir = TempDir::new().unwrap();
+        let input = save(&dir, "letter.pdf", 1, "Letter");
+        let template = save(&dir, "letterhead.pdf", 1, "Letterhead");
+        let output = dir.path().join("out.pdf").to_string_lossy().to_string();
+
+        let options = OverlayOptions {
+            placement: Some("underlay".to_string()),
+            layer_name: Some("Letterhead".to_string()),
+            ..Default::default()
+        };
+        overlay_pdf(&input, &template, &output, &options).unwrap();
+
+        let document = Document::load(&output).unwrap();
+        let content = content(&document, 1);
+        let template_at = content.find("/SoloTemplate").unwrap();
+        assert!(template_at < content.find("Letter - Page 1").unwrap());
+        assert!(content.starts_with("/OC"));
+        assert_eq!(layers::find_layers(&document, "Letterhead").len(), 1);
+    }
+
+    #[test]
+    fn test_second_overlay_keeps_the_first() {
+        let dir = TempDir::new().unwrap();
+        let input = save(&dir, "letter.pdf", 1, "Letter");
+        let header = save(&dir, "header.pdf", 1, "Header");
+        let footer = save(&dir, "footer.pdf", 1, "Footer");
+        let once = dir.path().join("once.pdf").to_string_lossy().to_string();
+        let twice = dir.path().join("twice.pdf").to_string_lossy().to_string();
+
+        let options = OverlayOptions {
+            opacity: Some(0.5),
+            ..Default::default()
+        };
+        overlay_pdf(&input, &header, &once, &options).unwrap();
+        overlay_pdf(&once, &footer, &twice, &options).unwrap();
+
+        let document = Document::load(&twice).unwrap();
+        let forms = template_forms(&document, 1);
+        assert_eq!(forms.len(), 2);
+        let mut drawn: Vec<String> = forms
+            .iter()
+            .map(|form_id| {
+                let form = document
+                    .get_object(*form_id)
+                    .and_then(Object::as_stream)
+                    .unwrap();
+                let data = form.decompressed_content().unwrap_or(form.content.clone());
+                String::from_utf8_lossy(&data).to_string()
+            })
+            .collect();
+        drawn.sort();
+        assert!(drawn[0].contains("Footer - Page 1"));
+        assert!(drawn[1].contains("Header - Page 1"));
+
+        let page_id = document.get_pages()[&1];
+        let graphics_states = document
+            .get_dictionary(page_id)
+            .and_then(|page| page.get(b"Resources"))
+            .and_then(Object::as_dict)
+            .and_then(|resources| resources.get(b"ExtGState"))
+            .and_then(Object::as_dict)
+            .unwrap()
+            .len();
+        assert_eq!(graphics_states, 2);
+        assert_eq!(content(&document, 1).matches(" gs").count(), 2);
+    }
+}
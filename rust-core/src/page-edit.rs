@@ -0,0 +1,889 @@
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use napi_derive::napi;
+use std::collections::HashSet;
+
+use crate::merge::{
+    flatten_inherited_attributes, null_references, page_tree_nodes, PdfAssembler,
+    INHERITABLE_PAGE_KEYS,
+};
+use crate::page_range::parse_page_range;
+
+#[derive(Debug, Clone, Default)]
+#[napi(object)]
+pub struct BlankPageOptions {
+    pub paper_size: Option<String>, // "A3", "A4", "A5", "Letter" (default), "Legal" or "Tabloid"
+    pub width: Option<f64>,         // Width in points; overrides paper_size together with height
+    pub height: Option<f64>,
+    pub landscape: Option<bool>,
+    pub count: Option<u32>, // Blank pages inserted at each position (default: 1)
+}
+
+/// Returns the portrait size of a named paper format in points.
+pub(crate) fn paper_size(name: &str) -> Option<(f64, f64)> {
+    match name.to_lowercase().as_str() {
+        "a3" => Some((841.89, 1190.55)),
+        "a4" => Some((595.28, 841.89)),
+        "a5" => Some((419.53, 595.28)),
+        "letter" => Some((612.0, 792.0)),
+        "legal" => Some((612.0, 1008.0)),
+        "tabloid" | "ledger" => Some((792.0, 1224.0)),
+        _ => None,
+    }
+}
+
+/// Copies the selected pages, in the order given, into a new file.
+///
+/// # Returns
+/// The number of pages written.
+pub fn extract_pages(
+    input_path: &str,
+    output_path: &str,
+    page_range: &str,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    let document = Document::load(input_path)?;
+    let pages = parse_page_range(page_range, document.get_pages().len() as u32)?;
+    assemble_pages(document, &pages, input_path, output_path)
+}
+
+/// Removes the selected pages.
+///
+/// Outline items and named destinations that pointed at a removed page are
+/// dropped, and page labels and the structure tree follow the remaining
+/// pages.
+///
+/// # Returns
+/// The number of pages left.
+pub fn delete_pages(
+    input_path: &str,
+    output_path: &str,
+    page_range: &str,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    let document = Document::load(input_path)?;
+    let total = document.get_pages().len() as u32;
+    let deleted = parse_page_range(page_range, total)?;
+
+    let kept: Vec<u32> = (1..=total)
+        .filter(|number| !deleted.contains(number))
+        .collect();
+    if kept.is_empty() {
+        return Err("Cannot delete every page of a document".into());
+    }
+    assemble_pages(document, &kept, input_path, output_path)
+}
+
+/// Moves the selected pages to the front, in the order they are listed; the
+/// remaining pages follow in their original order.
+///
+/// `"3,1,2"` on a five-page document gives 3, 1, 2, 4, 5 and `"r1-1"`
+/// reverses the document. Each page keeps its label.
+///
+/// # Returns
+/// The number of pages.
+pub fn reorder_pages(
+    input_path: &str,
+    output_path: &str,
+    page_order: &str,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    let document = Document::load(input_path)?;
+    let total = document.get_pages().len() as u32;
+    let mut numbers = parse_page_range(page_order, total)?;
+    let rest: Vec<u32> = (1..=total)
+        .filter(|number| !numbers.contains(number))
+        .collect();
+    numbers.extend(rest);
+    assemble_pages(document, &numbers, input_path, output_path)
+}
+
+/// Inserts `copies` copies (default 1) of each selected page right after it.
+///
+/// Outline items, links and structure elements keep pointing at the original
+/// page; each copy gets the same label as its original.
+///
+/// # Returns
+/// The number of pages after duplication.
+pub fn duplicate_pages(
+    input_path: &str,
+    output_path: &str,
+    page_range: &str,
+    copies: Option<u32>,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    let document = Document::load(input_path)?;
+    let total = document.get_pages().len() as u32;
+    let selected = parse_page_range(page_range, total)?;
+
+    let mut numbers = Vec::with_capacity(total as usize);
+    for number in 1..=total {
+        numbers.push(number);
+        if selected.contains(&number) {
+            numbers.extend(std::iter::repeat_n(number, copies.unwrap_or(1) as usize));
+        }
+    }
+    assemble_pages(document, &numbers, input_path, output_path)
+}
+
+/// Inserts blank pages before each of the given 1-based positions; a
+/// position one past the last page appends.
+///
+/// # Returns
+/// The number of pages after insertion.
+pub fn insert_blank_pages(
+    input_path: &str,
+    output_path: &str,
+    positions: &[u32],
+    options: &BlankPageOptions,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    let mut document = Document::load(input_path)?;
+    let pages: Vec<ObjectId> = document.get_pages().into_values().collect();
+    let total = pages.len() as u32;
+    if let Some(position) = positions.iter().find(|p| **p == 0 || **p > total + 1) {
+        return Err(
+            format!("Position {position} is out of range (document has {total} pages)").into(),
+        );
+    }
+
+    let (mut width, mut height) = match (options.width, options.height) {
+        (Some(width), Some(height)) => (width, height),
+        _ => {
+            let name = options.paper_size.as_deref().unwrap_or("Letter");
+            paper_size(name).ok_or_else(|| format!("Unknown paper size: '{name}'"))?
+        }
+    };
+    if options.landscape.unwrap_or(false) {
+        (width, height) = (height.max(width), height.min(width));
+    }
+
+    let mut order = Vec::with_capacity(pages.len() + positions.len());
+    for position in 1..=total + 1 {
+        for _ in 0..positions.iter().filter(|p| **p == position).count() {
+            for _ in 0..options.count.unwrap_or(1) {
+                order.push(add_blank_page(&mut document, width, height));
+            }
+        }
+        if let Some(page_id) = pages.get(position as usize - 1) {
+            order.push(*page_id);
+        }
+    }
+
+    set_page_order(&mut document, &order)?;
+    document.save(output_path)?;
+    Ok(order.len() as u32)
+}
+
+/// Inserts pages of `source_path` into `target_path` after its first
+/// `at_index` pages; 0 puts them in front, the page count appends them.
+///
+/// The target keeps its outline, named destinations, links, form fields,
+/// structure tree and page labels, all still pointing at the same pages after
+/// the shift. The source's own outline entries, fields, structure elements and
+/// page labels are carried over too.
+///
+/// # Returns
+/// The number of pages in the result.
+pub fn insert_pages(
+    target_path: &str,
+    source_path: &str,
+    output_path: &str,
+    at_index: u32,
+    source_range: Option<&str>,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    let target = Document::load(target_path)?;
+    let source = Document::load(source_path)?;
+    let target_total = target.get_pages().len() as u32;
+    if at_index > target_total {
+        return Err(
+            format!("Index {at_index} is out of range (target has {target_total} pages)").into(),
+        );
+    }
+    let source_total = source.get_pages().len() as u32;
+    let source_pages = match source_range {
+        Some(range) => parse_page_range(range, source_total)?,
+        None => (1..=source_total).collect(),
+    };
+
+    let mut assembler = PdfAssembler::new();
+    let target_ids = assembler.import(
+        target,
+        &(1..=target_total).collect::<Vec<_>>(),
+        target_path,
+        None,
+    )?;
+    let source_ids = assembler.import(source, &source_pages, source_path, None)?;
+
+    let (before, after) = target_ids.split_at(at_index as usize);
+    let order: Vec<ObjectId> = [before, &source_ids, after].concat();
+    let mut document = assembler.finish(&order)?;
+    document.save(output_path)?;
+    Ok(order.len() as u32)
+}
+
+/// Rebuilds `document` from the given 1-based pages, in the order listed, so
+/// its outline, destinations, structure tree and page labels follow them.
+///
+/// # Returns
+/// The number of pages written.
+fn assemble_pages(
+    document: Document,
+    page_numbers: &[u32],
+    input_path: &str,
+    output_path: &str,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    let mut assembler = PdfAssembler::new();
+    let page_ids = assembler.import(document, page_numbers, input_path, None)?;
+    let mut assembled = assembler.finish(&page_ids)?;
+    assembled.save(output_path)?;
+    Ok(page_ids.len() as u32)
+}
+
+fn add_blank_page(document: &mut Document, width: f64, height: f64) -> ObjectId {
+    let mut page = Dictionary::new();
+    page.set("Type", Object::Name(b"Page".to_vec()));
+    page.set(
+        "MediaBox",
+        Object::Array(vec![
+            Object::Integer(0),
+            Object::Integer(0),
+            Object::Real(width as f32),
+            Object::Real(height as f32),
+        ]),
+    );
+    page.set("Resources", Object::Dictionary(Dictionary::new()));
+    document.add_object(page)
+}
+
+/// Rewrites the page tree so it holds exactly `order`, as direct kids of the
+/// root `/Pages` node.
+///
+/// Inherited attributes are copied onto each page before intermediate nodes
+/// are dropped. Pages left out of `order` are deleted, references to them are
+/// replaced with `null`, and objects only they used are pruned.
+pub(crate) fn set_page_order(document: &mut Document, order: &[ObjectId]) -> lopdf::Result<()> {
+    let catalog = document.catalog()?.clone();
+    let root_id = catalog.get(b"Pages").and_then(Object::as_reference)?;
+    let nodes = page_tree_nodes(document, &catalog);
+    let previous: Vec<ObjectId> = document.get_pages().into_values().collect();
+
+    for page_id in order {
+        flatten_inherited_attributes(document, *page_id)?;
+    }
+    for node_id in nodes.iter().filter(|id| **id != root_id) {
+        document.objects.remove(node_id);
+    }
+
+    let root = document.get_dictionary_mut(root_id)?;
+    for key in INHERITABLE_PAGE_KEYS {
+        root.remove(key);
+    }
+    root.remove(b"Parent");
+    root.set(
+        "Kids",
+        Object::Array(order.iter().map(|id| Object::Reference(*id)).collect()),
+    );
+    root.set("Count", Object::Integer(order.len() as i64));
+    for page_id in order {
+        document
+            .get_dictionary_mut(*page_id)?
+            .set("Parent", Object::Reference(root_id));
+    }
+
+    let kept: HashSet<&ObjectId> = order.iter().collect();
+    let removed: HashSet<ObjectId> = previous
+        .into_iter()
+        .filter(|id| !kept.contains(id))
+        .collect();
+    if !removed.is_empty() {
+        for id in &removed {
+            document.objects.remove(id);
+        }
+        for object in document.objects.values_mut() {
+            null_references(object, &removed);
+        }
+        document.prune_objects();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_pdf;
+    use tempfile::TempDir;
+
+    /// A six-page document whose pages sit in two intermediate nodes that
+    /// supply their MediaBox and Resources.
+    fn nested_pdf(dir: &TempDir) -> String {
+        let mut document = create_test_pdf(6, "Nested", "text").unwrap();
+        let root_id = document
+            .catalog()
+            .unwrap()
+            .get(b"Pages")
+            .and_then(Object::as_reference)
+            .unwrap();
+        let pages: Vec<ObjectId> = document.get_pages().into_values().collect();
+
+        let mut kids = Vec::new();
+        for (index, group) in pages.chunks(3).enumerate() {
+            let width = if index == 0 { 612 } else { 842 };
+            let resources = document
+                .get_dictionary(group[0])
+                .unwrap()
+                .get(b"Resources")
+                .unwrap()
+                .clone();
+            let mut node = Dictionary::new();
+            node.set("Type", Object::Name(b"Pages".to_vec()));
+            node.set("Parent", Object::Reference(root_id));
+            node.set("Count", Object::Integer(3));
+            node.set(
+                "MediaBox",
+                Object::Array(vec![0.into(), 0.into(), width.into(), 792.into()]),
+            );
+            node.set("Resources", resources);
+            node.set(
+                "Kids",
+                Object::Array(group.iter().map(|id| Object::Reference(*id)).collect()),
+            );
+            let node_id = document.add_object(node);
+            for page_id in group {
+                let page = document.get_dictionary_mut(*page_id).unwrap();
+                page.remove(b"MediaBox");
+                page.remove(b"Resources");
+                page.set("Parent", Object::Reference(node_id));
+            }
+            kids.push(Object::Reference(node_id));
+        }
+        document
+            .get_dictionary_mut(root_id)
+            .unwrap()
+            .set("Kids", Object::Array(kids));
+
+        let path = dir.path().join("nested.pdf").to_string_lossy().to_string();
+        document.save(&path).unwrap();
+        path
+    }
+
+    fn page_labels(document: &Document) -> Vec<String> {
+        document
+            .get_pages()
+            .values()
+            .map(|id| {
+                let content = document.get_page_content(*id).unwrap_or_default();
+                let text = String::from_utf8_lossy(&content);
+                match text.find("Page ") {
+                    Some(start) => text[start + 5..]
+                        .chars()
+                        .take_while(char::is_ascii_digit)
+                        .collect(),
+                    None => "blank".to_string(),
+                }
+            })
+            .collect()
+    }
+
+    fn media_width(document: &Document, number: u32) -> i64 {
+        let page = document
+            .get_dictionary(document.get_pages()[&number])
+            .unwrap();
+        page.get(b"MediaBox").unwrap().as_array().unwrap()[2]
+            .as_float()
+            .unwrap() as i64
+    }
+
+    #[test]
+    fn test_delete_pages_keeps_tree_consistent() {
+        let dir = TempDir::new().unwrap();
+        let input = nested_pdf(&dir);
+        let output = dir.path().join("out.pdf").to_string_lossy().to_string();
+
+        assert_eq!(delete_pages(&input, &output, "2-4").unwrap(), 3);
+
+        let document = Document::load(&output).unwrap();
+        assert_eq!(page_labels(&document), vec!["1", "5", "6"]);
+        let root_id = document
+            .catalog()
+            .unwrap()
+            .get(b"Pages")
+            .unwrap()
+            .as_reference()
+            .unwrap();
+        let root = document.get_dictionary(root_id).unwrap();
+        assert_eq!(root.get(b"Count").unwrap().as_i64().unwrap(), 3);
+        for page_id in document.get_pages().values() {
+            let page = document.get_dictionary(*page_id).unwrap();
+            assert_eq!(
+                page.get(b"Parent").unwrap().as_reference().unwrap(),
+                root_id
+            );
+            assert!(page.has(b"Resources"));
+        }
+        // Inherited MediaBoxes survive the removal of their subtree
+        assert_eq!(media_width(&document, 1), 612);
+        assert_eq!(media_width(&document, 2), 842);
+    }
+
+    #[test]
+    fn test_reorder_and_duplicate_pages() {
+        let dir = TempDir::new().unwrap();
+        let input = nested_pdf(&dir);
+        let reordered = dir
+            .path()
+            .join("reordered.pdf")
+            .to_string_lossy()
+            .to_string();
+        let duplicated = dir
+            .path()
+            .join("duplicated.pdf")
+            .to_string_lossy()
+            .to_string();
+
+        reorder_pages(&input, &reordered, "5,2").unwrap();
+        let document = Document::load(&reordered).unwrap();
+        assert_eq!(page_labels(&document), vec!["5", "2", "1", "3", "4", "6"]);
+        assert_eq!(media_width(&document, 1), 842);
+
+        assert_eq!(
+            duplicate_pages(&input, &duplicated, "1,last", Some(2)).unwrap(),
+            10
+        );
+        let document = Document::load(&duplicated).unwrap();
+        assert_eq!(
+            page_labels(&document),
+            vec!["1", "1", "1", "2", "3", "4", "5", "6", "6", "6"]
+        );
+    }
+
+    #[test]
+    fn test_insert_pages_keeps_target_links() {
+        let dir = TempDir::new().unwrap();
+        let mut target = create_test_pdf(3, "Target", "text").unwrap();
+        let pages = target.get_pages();
+
+        // An outline entry for page 3 and a link on page 1 that jumps to page 2
+        let mut item = Dictionary::new();
+        item.set("Title", lopdf::text_string("Last page"));
+        item.set(
+            "Dest",
+            Object::Array(vec![
+                Object::Reference(pages[&3]),
+                Object::Name(b"Fit".to_vec()),
+            ]),
+        );
+        let item_id = target.add_object(item);
+        let mut outlines = Dictionary::new();
+        outlines.set("First", Object::Reference(item_id));
+        outlines.set("Last", Object::Reference(item_id));
+        let outlines_id = target.add_object(outlines);
+        target
+            .catalog_mut()
+            .unwrap()
+            .set("Outlines", Object::Reference(outlines_id));
+        let mut link = Dictionary::new();
+        link.set("Subtype", Object::Name(b"Link".to_vec()));
+        link.set(
+            "Rect",
+            Object::Array(vec![0.into(), 0.into(), 10.into(), 10.into()]),
+        );
+        link.set(
+            "Dest",
+            Object::Array(vec![
+                Object::Reference(pages[&2]),
+                Object::Name(b"Fit".to_vec()),
+            ]),
+        );
+        let link_id = target.add_object(link);
+        target
+            .get_dictionary_mut(pages[&1])
+            .unwrap()
+            .set("Annots", Object::Array(vec![Object::Reference(link_id)]));
+        let target_path = dir.path().join("target.pdf").to_string_lossy().to_string();
+        target.save(&target_path).unwrap();
+
+        let source_path = dir.path().join("source.pdf").to_string_lossy().to_string();
+        create_test_pdf(4, "Appendix", "text")
+            .unwrap()
+            .save(&source_path)
+            .unwrap();
+        let output = dir.path().join("out.pdf").to_string_lossy().to_string();
+
+        assert_eq!(
+            insert_pages(&target_path, &source_path, &output, 1, Some("2-3")).unwrap(),
+            5
+        );
+        assert!(insert_pages(&target_path, &source_path, &output, 4, None).is_err());
+
+        let document = Document::load(&output).unwrap();
+        let pages = document.get_pages();
+        assert_eq!(page_labels(&document), vec!["1", "2", "3", "2", "3"]);
+        let contents = document.get_page_content(pages[&2]).unwrap();
+        assert!(String::from_utf8_lossy(&contents).contains("Appendix - Page 2"));
+
+        let destination_page = |dest: &Object| dest.as_array().unwrap()[0].as_reference().unwrap();
+        let catalog = document.catalog().unwrap();
+        let outlines = document
+            .get_dictionary(catalog.get(b"Outlines").unwrap().as_reference().unwrap())
+            .unwrap();
+        let first = document
+            .get_dictionary(outlines.get(b"First").unwrap().as_reference().unwrap())
+            .unwrap();
+        assert_eq!(destination_page(first.get(b"Dest").unwrap()), pages[&5]);
+
+        let annots = document
+            .get_dictionary(pages[&1])
+            .unwrap()
+            .get(b"Annots")
+            .unwrap()
+            .as_array()
+            .unwrap();
+        let link = document
+            .get_dictionary(annots[0].as_reference().unwrap())
+            .unwrap();
+        assert_eq!(destination_page(link.get(b"Dest").unwrap()), pages[&4]);
+    }
+
+    #[test]
+    fn test_insert_blank_pages_and_extract() {
+        let dir = TempDir::new().unwrap();
+        let input = nested_pdf(&dir);
+        let output = dir.path().join("blank.pdf").to_string_lossy().to_string();
+        let extracted = dir.path().join("extract.pdf").to_string_lossy().to_string();
+
+        let options = BlankPageOptions {
+            paper_size: Some("A4".to_string()),
+            landscape: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            insert_blank_pages(&input, &output, &[1, 7], &options).unwrap(),
+            8
+        );
+        let document = Document::load(&output).unwrap();
+        let labels = page_labels(&document);
+        assert_eq!(labels[0], "blank");
+        assert_eq!(labels[7], "blank");
+        assert_eq!(media_width(&document, 1), 841);
+
+        assert_eq!(extract_pages(&input, &extracted, "r1,2").unwrap(), 2);
+        let document = Document::load(&extracted).unwrap();
+        assert_eq!(page_labels(&document), vec!["6", "2"]);
+    }
+
+    /// Gives every page a paragraph element in a structure tree under one
+    /// Document element, keyed in the parent tree by page index.
+    fn tag_pages(document: &mut Document) {
+        let root_id = document.new_object_id();
+        let document_id = document.new_object_id();
+        let mut paragraphs = Vec::new();
+        let mut nums = Vec::new();
+        for (index, page_id) in document.get_pages().into_values().enumerate() {
+            let mut paragraph = Dictionary::new();
+            paragraph.set("Type", Object::Name(b"StructElem".to_vec()));
+            paragraph.set("S", Object::Name(b"P".to_vec()));
+            paragraph.set("P", Object::Reference(document_id));
+            paragraph.set("Pg", Object::Reference(page_id));
+            paragraph.set("K", Object::Integer(0));
+            let paragraph_id = document.add_object(paragraph);
+            paragraphs.push(Object::Reference(paragraph_id));
+            nums.push(Object::Integer(index as i64));
+            nums.push(Object::Array(vec![Object::Reference(paragraph_id)]));
+            document
+                .get_dictionary_mut(page_id)
+                .unwrap()
+                .set("StructParents", Object::Integer(index as i64));
+        }
+        let mut element = Dictionary::new();
+        element.set("Type", Object::Name(b"StructElem".to_vec()));
+        element.set("S", Object::Name(b"Document".to_vec()));
+        element.set("P", Object::Reference(root_id));
+        element.set("K", Object::Array(paragraphs));
+        document
+            .objects
+            .insert(document_id, Object::Dictionary(element));
+        let mut parent_tree = Dictionary::new();
+        parent_tree.set("Nums", Object::Array(nums.clone()));
+        let mut root = Dictionary::new();
+        root.set("Type", Object::Name(b"StructTreeRoot".to_vec()));
+        root.set("K", Object::Reference(document_id));
+        root.set("ParentTree", Object::Dictionary(parent_tree));
+        root.set("ParentTreeNextKey", Object::Integer(nums.len() as i64 / 2));
+        document.objects.insert(root_id, Object::Dictionary(root));
+        document
+            .catalog_mut()
+            .unwrap()
+            .set("StructTreeRoot", Object::Reference(root_id));
+    }
+
+    fn structure_root(document: &Document) -> &Dictionary {
+        let root_id = document
+            .catalog()
+            .unwrap()
+            .get(b"StructTreeRoot")
+            .and_then(Object::as_reference)
+            .unwrap();
+        document.get_dictionary(root_id).unwrap()
+    }
+
+    fn parent_tree_keys(document: &Document) -> Vec<i64> {
+        let root = structure_root(document);
+        let tree = document
+            .get_dictionary(root.get(b"ParentTree").unwrap().as_reference().unwrap())
+            .unwrap();
+        tree.get(b"Nums")
+            .and_then(Object::as_array)
+            .unwrap()
+            .iter()
+            .step_by(2)
+            .map(|key| key.as_i64().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_insert_pages_carries_structure_and_page_labels() {
+        let dir = TempDir::new().unwrap();
+        let mut target = create_test_pdf(2, "Target", "text").unwrap();
+        tag_pages(&mut target);
+        let target_path = dir.path().join("target.pdf").to_string_lossy().to_string();
+        target.save(&target_path).unwrap();
+
+        // A tagged source whose pages are numbered i, ii
+        let mut source = create_test_pdf(2, "Preface", "text").unwrap();
+        tag_pages(&mut source);
+        let mut roman = Dictionary::new();
+        roman.set("S", Object::Name(b"r".to_vec()));
+        let mut labels = Dictionary::new();
+        labels.set(
+            "Nums",
+            Object::Array(vec![Object::Integer(0), Object::Dictionary(roman)]),
+        );
+        source
+            .catalog_mut()
+            .unwrap()
+            .set("PageLabels", Object::Dictionary(labels));
+        let source_path = dir.path().join("source.pdf").to_string_lossy().to_string();
+        source.save(&source_path).unwrap();
+        let output = dir.path().join("out.pdf").to_string_lossy().to_string();
+
+        insert_pages(&target_path, &source_path, &output, 1, None).unwrap();
+        let document = Document::load(&output).unwrap();
+
+        // Target page 1, preface i-ii, then target page 2 labeled 2
+        let labels = document
+            .catalog()
+            .unwrap()
+            .get(b"PageLabels")
+            .and_then(Object::as_dict)
+            .unwrap()
+            .get(b"Nums")
+            .and_then(Object::as_array)
+            .unwrap();
+        let ranges: Vec<(i64, Vec<u8>, i64)> = labels
+            .as_chunks::<2>()
+            .0
+            .iter()
+            .map(|[start, range]| {
+                let range = range.as_dict().unwrap();
+                (
+                    start.as_i64().unwrap(),
+                    range.get(b"S").and_then(Object::as_name).unwrap().to_vec(),
+                    range.get(b"St").and_then(Object::as_i64).unwrap_or(1),
+                )
+            })
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                (0, b"D".to_vec(), 1),
+                (1, b"r".to_vec(), 1),
+                (3, b"D".to_vec(), 2)
+            ]
+        );
+
+        // Both documents' elements sit under one root, with distinct keys
+        let root = structure_root(&document);
+        assert_eq!(root.get(b"K").and_then(Object::as_array).unwrap().len(), 2);
+        let mut keys = parent_tree_keys(&document);
+        keys.sort_unstable();
+        assert_eq!(keys, vec![0, 1, 2, 3]);
+        let mut struct_parents: Vec<i64> = document
+            .get_pages()
+            .values()
+            .map(|page_id| {
+                document
+                    .get_dictionary(*page_id)
+                    .unwrap()
+                    .get(b"StructParents")
+                    .and_then(Object::as_i64)
+                    .unwrap()
+            })
+            .collect();
+        struct_parents.sort_unstable();
+        assert_eq!(struct_parents, vec![0, 1, 2, 3]);
+        assert_eq!(
+            root.get(b"ParentTreeNextKey")
+                .and_then(Object::as_i64)
+                .unwrap(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_extract_pages_keeps_structure_of_kept_pages() {
+        let dir = TempDir::new().unwrap();
+        let mut input = create_test_pdf(3, "Tagged", "text").unwrap();
+        tag_pages(&mut input);
+        let input_path = dir.path().join("tagged.pdf").to_string_lossy().to_string();
+        input.save(&input_path).unwrap();
+        let output = dir.path().join("out.pdf").to_string_lossy().to_string();
+
+        extract_pages(&input_path, &output, "2").unwrap();
+        let document = Document::load(&output).unwrap();
+        let root = structure_root(&document);
+        let kids = root.get(b"K").and_then(Object::as_array).unwrap();
+        let element = document
+            .get_dictionary(kids[0].as_reference().unwrap())
+            .unwrap();
+        let paragraphs = element.get(b"K").and_then(Object::as_array).unwrap();
+        assert_eq!(paragraphs.len(), 1);
+        let paragraph = document
+            .get_dictionary(paragraphs[0].as_reference().unwrap())
+            .unwrap();
+        assert_eq!(
+            paragraph.get(b"Pg").and_then(Object::as_reference).unwrap(),
+            document.get_pages()[&1]
+        );
+        assert_eq!(parent_tree_keys(&document), vec![1]);
+    }
+
+    #[test]
+    fn test_delete_and_reorder_prune_outline_and_relabel() {
+        let dir = TempDir::new().unwrap();
+        let mut input = create_test_pdf(4, "Labeled", "text").unwrap();
+        tag_pages(&mut input);
+        let pages = input.get_pages();
+
+        // One outline item per page, and pages numbered i, ii, 1, 2
+        let outlines_id = input.new_object_id();
+        let items: Vec<ObjectId> = (1..=4)
+            .map(|number| {
+                let mut item = Dictionary::new();
+                item.set("Title", lopdf::text_string(&format!("Page {number}")));
+                item.set("Parent", Object::Reference(outlines_id));
+                item.set(
+                    "Dest",
+                    Object::Array(vec![
+                        Object::Reference(pages[&number]),
+                        Object::Name(b"Fit".to_vec()),
+                    ]),
+                );
+                input.add_object(item)
+            })
+            .collect();
+        for pair in items.windows(2) {
+            input
+                .get_dictionary_mut(pair[0])
+                .unwrap()
+                .set("Next", Object::Reference(pair[1]));
+            input
+                .get_dictionary_mut(pair[1])
+                .unwrap()
+                .set("Prev", Object::Reference(pair[0]));
+        }
+        let mut outlines = Dictionary::new();
+        outlines.set("First", Object::Reference(items[0]));
+        outlines.set("Last", Object::Reference(items[3]));
+        input
+            .objects
+            .insert(outlines_id, Object::Dictionary(outlines));
+        let mut roman = Dictionary::new();
+        roman.set("S", Object::Name(b"r".to_vec()));
+        let mut decimal = Dictionary::new();
+        decimal.set("S", Object::Name(b"D".to_vec()));
+        let mut labels = Dictionary::new();
+        labels.set(
+            "Nums",
+            Object::Array(vec![
+                Object::Integer(0),
+                Object::Dictionary(roman),
+                Object::Integer(2),
+                Object::Dictionary(decimal),
+            ]),
+        );
+        let catalog = input.catalog_mut().unwrap();
+        catalog.set("Outlines", Object::Reference(outlines_id));
+        catalog.set("PageLabels", Object::Dictionary(labels));
+        let input_path = dir.path().join("labeled.pdf").to_string_lossy().to_string();
+        input.save(&input_path).unwrap();
+
+        let outline = |document: &Document| -> Vec<(String, ObjectId)> {
+            let catalog = document.catalog().unwrap();
+            let outlines = document
+                .get_dictionary(catalog.get(b"Outlines").unwrap().as_reference().unwrap())
+                .unwrap();
+            let mut entries = Vec::new();
+            let mut next = outlines.get(b"First").and_then(Object::as_reference).ok();
+            while let Some(id) = next {
+                let item = document.get_dictionary(id).unwrap();
+                let title = lopdf::decode_text_string(item.get(b"Title").unwrap()).unwrap();
+                let page = item.get(b"Dest").unwrap().as_array().unwrap()[0]
+                    .as_reference()
+                    .unwrap();
+                entries.push((title, page));
+                next = item.get(b"Next").and_then(Object::as_reference).ok();
+            }
+            entries
+        };
+        let ranges = |document: &Document| -> Vec<(i64, Vec<u8>, i64)> {
+            document
+                .catalog()
+                .unwrap()
+                .get(b"PageLabels")
+                .and_then(Object::as_dict)
+                .unwrap()
+                .get(b"Nums")
+                .and_then(Object::as_array)
+                .unwrap()
+                .as_chunks::<2>()
+                .0
+                .iter()
+                .map(|[start, range]| {
+                    let range = range.as_dict().unwrap();
+                    (
+                        start.as_i64().unwrap(),
+                        range.get(b"S").and_then(Object::as_name).unwrap().to_vec(),
+                        range.get(b"St").and_then(Object::as_i64).unwrap_or(1),
+                    )
+                })
+                .collect()
+        };
+
+        let deleted = dir.path().join("deleted.pdf").to_string_lossy().to_string();
+        assert_eq!(delete_pages(&input_path, &deleted, "2").unwrap(), 3);
+        let document = Document::load(&deleted).unwrap();
+        let pages = document.get_pages();
+        assert_eq!(
+            outline(&document),
+            vec![
+                ("Page 1".to_string(), pages[&1]),
+                ("Page 3".to_string(), pages[&2]),
+                ("Page 4".to_string(), pages[&3]),
+            ]
+        );
+        assert_eq!(
+            ranges(&document),
+            vec![(0, b"r".to_vec(), 1), (1, b"D".to_vec(), 1)]
+        );
+        assert_eq!(parent_tree_keys(&document), vec![0, 2, 3]);
+
+        let reordered = dir
+            .path()
+            .join("reordered.pdf")
+            .to_string_lossy()
+            .to_string();
+        reorder_pages(&input_path, &reordered, "3,4").unwrap();
+        let document = Document::load(&reordered).unwrap();
+        let pages = document.get_pages();
+        assert_eq!(outline(&document)[0], ("Page 1".to_string(), pages[&3]));
+        assert_eq!(
+            ranges(&document),
+            vec![(0, b"D".to_vec(), 1), (2, b"r".to_vec(), 1)]
+        );
+    }
+}
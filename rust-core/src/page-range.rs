@@ -0,0 +1,190 @@
+use lopdf::ObjectId;
+
+/// Parses a page-selection expression against a document with `total` pages.
+///
+/// The expression is a comma-separated list of terms:
+/// * `7` - a single page; `last` is the final page and `rN` counts from the
+///   end (`r1` is the last page, `r2` the one before it)
+/// * `1-3`, `10-`, `-4`, `r3-r1` - inclusive ranges; a descending range such
+///   as `5-3` yields its pages in that order
+/// * `odd`, `even`, `all`
+/// * `!term` - removes the pages matched by `term` from the selection
+///
+/// Pages are returned in the order they were written, without duplicates.
+/// An expression made only of exclusions starts from all pages.
+pub fn parse_page_range(
+    expression: &str,
+    total: u32,
+) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+    let mut included: Vec<u32> = Vec::new();
+    let mut excluded: Vec<u32> = Vec::new();
+    let mut has_inclusions = false;
+
+    for term in expression.split(',') {
+        let term = term.trim().to_lowercase();
+        if term.is_empty() {
+            continue;
+        }
+        match term.strip_prefix('!') {
+            Some(negated) => excluded.extend(parse_term(negated.trim(), total)?),
+            None => {
+                has_inclusions = true;
+                included.extend(parse_term(&term, total)?);
+            }
+        }
+    }
+
+    if !has_inclusions {
+        included = (1..=total).collect();
+    }
+
+    let mut pages = Vec::with_capacity(included.len());
+    for page in included {
+        if !excluded.contains(&page) && !pages.contains(&page) {
+            pages.push(page);
+        }
+    }
+    Ok(pages)
+}
+
+/// Resolves the pages targeted by a page-oriented API to their object ids,
+/// in document order.
+///
+/// `page_range` takes precedence over the explicit `pages` list; page numbers
+/// in `pages` that do not exist are ignored. With neither, all pages are
+/// selected.
+pub fn select_pages(
+    document: &lopdf::Document,
+    page_range: Option<&str>,
+    pages: Option<&[u32]>,
+) -> Result<Vec<ObjectId>, Box<dyn std::error::Error>> {
+    let page_ids = document.get_pages();
+    let mut numbers = selected_page_numbers(page_ids.len() as u32, page_range, pages)?;
+    numbers.sort_unstable();
+    Ok(numbers
+        .iter()
+        .filter_map(|number| page_ids.get(number).copied())
+        .collect())
+}
+
+/// Like [`select_pages`], but returns 1-based page numbers in the order the
+/// selection was written.
+pub fn selected_page_numbers(
+    total: u32,
+    page_range: Option<&str>,
+    pages: Option<&[u32]>,
+) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+    match (page_range, pages) {
+        (Some(expression), _) => parse_page_range(expression, total),
+        (None, Some(pages)) => Ok(pages
+            .iter()
+            .copied()
+            .filter(|page| (1..=total).contains(page))
+            .collect()),
+        (None, None) => Ok((1..=total).collect()),
+    }
+}
+
+fn parse_term(term: &str, total: u32) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+    match term {
+        "all" | "*" => return Ok((1..=total).collect()),
+        "odd" => return Ok((1..=total).step_by(2).collect()),
+        "even" => return Ok((2..=total).step_by(2).collect()),
+        _ => {}
+    }
+
+    let Some((start, end)) = term.split_once('-') else {
+        return Ok(vec![parse_endpoint(term, total)?]);
+    };
+    let start = match start.trim() {
+        "" => 1,
+        start => parse_endpoint(start, total)?,
+    };
+    let end = match end.trim() {
+        "" => total,
+        end => parse_endpoint(end, total)?,
+    };
+
+    if start <= end {
+        Ok((start..=end).collect())
+    } else {
+        Ok((end..=start).rev().collect())
+    }
+}
+
+fn parse_endpoint(endpoint: &str, total: u32) -> Result<u32, Box<dyn std::error::Error>> {
+    let page = if endpoint == "last" {
+        total
+    } else if let Some(reverse) = endpoint.strip_prefix('r') {
+        let offset: u32 = reverse
+            .parse()
+            .map_err(|_| format!("Invalid page reference: '{endpoint}'"))?;
+        if offset == 0 || offset > total {
+            return Err(
+                format!("Page '{endpoint}' is out of range (document has {total} pages)").into(),
+            );
+        }
+        total + 1 - offset
+    } else {
+        endpoint
+            .parse()
+            .map_err(|_| format!("Invalid page reference: '{endpoint}'"))?
+    };
+
+    if page == 0 || page > total {
+        return Err(format!("Page {page} is out of range (document has {total} pages)").into());
+    }
+    Ok(page)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_pdf;
+
+    #[test]
+    fn test_ranges_and_open_ends() {
+        assert_eq!(
+            parse_page_range("1-3,7,10-", 12).unwrap(),
+            vec![1, 2, 3, 7, 10, 11, 12]
+        );
+        assert_eq!(parse_page_range("-2", 5).unwrap(), vec![1, 2]);
+        assert_eq!(parse_page_range("5-3", 5).unwrap(), vec![5, 4, 3]);
+    }
+
+    #[test]
+    fn test_keywords_and_reverse_pages() {
+        assert_eq!(parse_page_range("odd", 5).unwrap(), vec![1, 3, 5]);
+        assert_eq!(parse_page_range("even", 5).unwrap(), vec![2, 4]);
+        assert_eq!(parse_page_range("last", 5).unwrap(), vec![5]);
+        assert_eq!(parse_page_range("r1,r2", 5).unwrap(), vec![5, 4]);
+        assert_eq!(parse_page_range("r3-r1", 5).unwrap(), vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn test_exclusions() {
+        assert_eq!(parse_page_range("!1", 4).unwrap(), vec![2, 3, 4]);
+        assert_eq!(parse_page_range("odd,!r1", 5).unwrap(), vec![1, 3]);
+        assert_eq!(parse_page_range("1-4, !2-3", 5).unwrap(), vec![1, 4]);
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        assert!(parse_page_range("0", 3).is_err());
+        assert!(parse_page_range("4", 3).is_err());
+        assert!(parse_page_range("r4", 3).is_err());
+        assert!(parse_page_range("abc", 3).is_err());
+    }
+
+    #[test]
+    fn test_select_pages_follows_document_order() {
+        let doc = create_test_pdf(4, "test", "content").unwrap();
+        let pages = doc.get_pages();
+
+        let selected = select_pages(&doc, Some("r1,1"), None).unwrap();
+        assert_eq!(selected, vec![pages[&1], pages[&4]]);
+
+        let selected = select_pages(&doc, None, Some(&[3, 9])).unwrap();
+        assert_eq!(selected, vec![pages[&3]]);
+    }
+}
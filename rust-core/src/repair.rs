@@ -0,0 +1,800 @@
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
+
+use crate::validate::{ends_stream, find, raw_entry, stream_data_start};
+
+/// Searched after each `trailer` keyword for the trailer dictionary.
+const TRAILER_WINDOW: usize = 4096;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RepairReport {
+    pub output_file: String,
+    pub load_error: Option<String>, // Why the original could not be opened, if it could not
+    pub objects_recovered: u32,
+    pub objects_dropped: Vec<String>, // Objects cut off by truncation, e.g. "12 0 R"
+    pub stream_lengths_fixed: u32,
+    pub trailer_recovered: bool, // No usable trailer; the catalog was found by scanning
+    pub catalog_created: bool,
+    pub page_tree_created: bool,
+    pub page_counts_fixed: u32,
+    pub page_count: u32,
+}
+
+/// Rebuilds a damaged PDF from the objects found in its bytes and writes a
+/// repaired copy.
+///
+/// The file is scanned for `N G obj` ... `endobj` markers rather than read
+/// through its xref table, so broken offsets, a missing or truncated xref
+/// and a lost trailer are all recovered. Streams whose `/Length` does not
+/// match the data before `endstream` get the real length. When objects are
+/// defined more than once (incremental updates), the last definition in the
+/// file wins; members of object streams count as defined where their stream
+/// is, and are written back as plain objects.
+/// If no trailer names the catalog, the catalog is found by its `/Type`, or
+/// created over the page tree; page tree `/Count` values are recomputed.
+pub fn repair_pdf(
+    input_path: &str,
+    output_path: &str,
+) -> Result<RepairReport, Box<dyn std::error::Error>> {
+    let bytes = std::fs::read(input_path)?;
+    let load_error = Document::load_mem(&bytes).err().map(|e| e.to_string());
+
+    let scan = scan_objects(&bytes);
+    if scan.objects.is_empty() {
+        return Err("No PDF objects found".into());
+    }
+    let trailer = recover_trailer(&bytes, &scan);
+    let rebuilt = rebuild(&bytes, &scan, &trailer)?;
+    let mut document = Document::load_mem(&rebuilt)
+        .map_err(|e| format!("Could not reconstruct the document: {e}"))?;
+    document.max_id = document
+        .objects
+        .keys()
+        .map(|(number, _)| *number)
+        .max()
+        .unwrap_or(0);
+
+    let mut report = RepairReport {
+        output_file: output_path.to_string(),
+        load_error,
+        objects_recovered: scan.objects.len() as u32,
+        objects_dropped: scan
+            .dropped
+            .iter()
+            .map(|(number, generation)| format!("{number} {generation} R"))
+            .collect(),
+        stream_lengths_fixed: scan
+            .objects
+            .values()
+            .filter(|object| object.length_fixed)
+            .count() as u32,
+        trailer_recovered: trailer.root.is_none(),
+        catalog_created: false,
+        page_tree_created: false,
+        page_counts_fixed: 0,
+        page_count: 0,
+    };
+    repair_catalog(&mut document, &mut report);
+
+    let pages_id = document
+        .catalog()?
+        .get(b"Pages")
+        .and_then(Object::as_reference)?;
+    let mut visited = HashSet::new();
+    report.page_count = fix_page_counts(&mut document, pages_id, &mut visited, &mut report);
+
+    document.save(output_path)?;
+    Ok(report)
+}
+
+struct RawObject {
+    header: Vec<u8>,         // Everything between "obj" and "stream"/"endobj"
+    stream: Option<Vec<u8>>, // Stream data
+    length_fixed: bool,
+}
+
+struct Scan {
+    objects: BTreeMap<ObjectId, RawObject>,
+    dropped: Vec<ObjectId>,
+    xref_streams: Vec<Vec<u8>>, // Dictionaries of xref streams, which carry trailer keys
+}
+
+/// Walks the file from start to end, reading one object at a time so that
+/// stream data is never mistaken for object markers.
+fn scan_objects(bytes: &[u8]) -> Scan {
+    let mut scan = Scan {
+        objects: BTreeMap::new(),
+        dropped: Vec::new(),
+        xref_streams: Vec::new(),
+    };
+    let mut position = 0;
+    while let Some((id, body_start)) = next_object(bytes, position) {
+        let body = &bytes[body_start..];
+        let next = next_object(bytes, body_start).map(|(_, next_body)| next_body);
+        let end_of_object = find(body, b"endobj");
+
+        let dictionary_end = end_of_object.unwrap_or(body.len());
+        match stream_data_start(&body[..dictionary_end]) {
+            Some(data_start) => {
+                let header = stream_header(&body[..data_start]);
+                let declared =
+                    raw_entry(header, b"/Length").and_then(
+                        |(_, value, generation)| match generation {
+                            None => Some(value),
+                            Some(generation) => indirect_integer(bytes, (value as u32, generation)),
+                        },
+                    );
+                let declared = declared
+                    .filter(|length| {
+                        *length >= 0 && ends_stream(body, data_start + *length as usize)
+                    })
+                    .map(|length| length as usize);
+
+                let data_end = match declared {
+                    Some(length) => Some(data_start + length),
+                    None => find(&body[data_start..], b"endstream").map(|end| {
+                        // The end-of-line marker before endstream is not data
+                        let mut end = data_start + end;
+                        if end > data_start && body[end - 1] == b'\n' {
+                            end -= 1;
+                        }
+                        if end > data_start && body[end - 1] == b'\r' {
+                            end -= 1;
+                        }
+                        end
+                    }),
+                };
+                let Some(data_end) = data_end else {
+                    // Truncated inside the stream data
+                    scan.dropped.push(id);
+                    break;
+                };
+                let after_data = data_end + find(&body[data_end..], b"endstream").unwrap_or(0);
+                position = body_start
+                    + after_data
+                    + find(&body[after_data..], b"endobj").map_or(0, |end| end + 6);
+
+                let header = header.to_vec();
+                if contains_type(&header, b"XRef") {
+                    scan.xref_streams.push(header);
+                    continue;
+                }
+                if contains_type(&header, b"ObjStm") {
+                    if let Some(members) =
+                        object_stream_members(&header, &body[data_start..data_end])
+                    {
+                        for (id, header) in members {
+                            scan.objects.insert(
+                                id,
+                                RawObject {
+                                    header,
+                                    stream: None,
+                                    length_fixed: false,
+                                },
+                            );
+                        }
+                        continue;
+                    }
+                }
+                scan.objects.insert(
+                    id,
+                    RawObject {
+                        header,
+                        stream: Some(body[data_start..data_end].to_vec()),
+                        length_fixed: declared.is_none(),
+                    },
+                );
+            }
+            None => {
+                // A missing endobj is tolerated when another object follows
+                let end = match (end_of_object, next) {
+                    (Some(end), Some(next)) => end.min(next - body_start),
+                    (Some(end), None) => end,
+                    (None, Some(next)) => next - body_start,
+                    (None, None) => {
+                        scan.dropped.push(id);
+                        break;
+                    }
+                };
+                let mut header = &body[..end];
+                if next.is_some_and(|next| next - body_start == end) {
+                    // Back off the next object's "N G " prefix
+                    header = trim_object_prefix(header);
+                }
+                scan.objects.insert(
+                    id,
+                    RawObject {
+                        header: header.to_vec(),
+                        stream: None,
+                        length_fixed: false,
+                    },
+                );
+                position = body_start + end;
+            }
+        }
+    }
+    scan
+}
+
+/// Splits an object stream into the raw text of its members, in the order
+/// its index lists them. Returns `None` when the stream cannot be decoded,
+/// in which case it is kept as it is.
+fn object_stream_members(header: &[u8], data: &[u8]) -> Option<Vec<(ObjectId, Vec<u8>)>> {
+    let first = usize::try_from(raw_entry(header, b"/First")?.1).ok()?;
+    let count = usize::try_from(raw_entry(header, b"/N")?.1).ok()?;
+    let content = match raw_name(header, b"/Filter") {
+        Some(filter) => {
+            let mut dict = Dictionary::new();
+            dict.set("Filter", Object::Name(filter));
+            Stream::new(dict, data.to_vec())
+                .decompressed_content()
+                .ok()?
+        }
+        None => data.to_vec(),
+    };
+
+    let index = std::str::from_utf8(content.get(..first)?).ok()?;
+    let numbers: Vec<usize> = index
+        .split_ascii_whitespace()
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .ok()?;
+    let entries: Vec<(u32, usize)> = numbers
+        .as_chunks::<2>()
+        .0
+        .iter()
+        .take(count)
+        .map(|pair| Some((u32::try_from(pair[0]).ok()?, first.checked_add(pair[1])?)))
+        .collect::<Option<_>>()?;
+
+    let mut offsets: Vec<usize> = entries.iter().map(|(_, offset)| *offset).collect();
+    offsets.push(content.len());
+    offsets.sort_unstable();
+    entries
+        .into_iter()
+        .map(|(number, start)| {
+            let end = offsets.iter().copied().find(|offset| *offset > start)?;
+            Some(((number, 0), content.get(start..end)?.to_vec()))
+        })
+        .collect()
+}
+
+/// Reads a name value, such as `/Filter /FlateDecode` or a one-element
+/// `/Filter [/FlateDecode]`, from a raw dictionary.
+fn raw_name(header: &[u8], key: &[u8]) -> Option<Vec<u8>> {
+    let at = find(header, key)? + key.len();
+    let rest = &header[at..];
+    let skip = rest
+        .iter()
+        .take_while(|c| c.is_ascii_whitespace() || **c == b'[')
+        .count();
+    let name = rest[skip..].strip_prefix(b"/")?;
+    let len = name
+        .iter()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .count();
+    (len > 0).then(|| name[..len].to_vec())
+}
+
+/// Finds the next `N G obj` header at or after `from`, returning the object
+/// id and where its body starts.
+fn next_object(bytes: &[u8], from: usize) -> Option<(ObjectId, usize)> {
+    let mut at = from;
+    while let Some(found) = find(&bytes[at..], b"obj") {
+        let keyword = at + found;
+        at = keyword + 3;
+        if keyword >= 3 && &bytes[keyword - 3..keyword] == b"end" {
+            continue;
+        }
+        if bytes
+            .get(keyword + 3)
+            .is_some_and(|c| c.is_ascii_alphanumeric())
+        {
+            continue;
+        }
+
+        // Walk back over "N G "
+        let mut start = keyword;
+        let mut numbers = [0u64; 2];
+        let mut valid = true;
+        for number in numbers.iter_mut().rev() {
+            let spaces = bytes[..start]
+                .iter()
+                .rev()
+                .take_while(|c| c.is_ascii_whitespace())
+                .count();
+            let digits = bytes[..start - spaces]
+                .iter()
+                .rev()
+                .take_while(|c| c.is_ascii_digit())
+                .count();
+            if spaces == 0 || digits == 0 || digits > 10 {
+                valid = false;
+                break;
+            }
+            start -= spaces + digits;
+            *number = std::str::from_utf8(&bytes[start..start + digits])
+                .ok()
+                .and_then(|digits| digits.parse().ok())
+                .unwrap_or(u64::MAX);
+        }
+        let delimited = start == 0 || !bytes[start - 1].is_ascii_alphanumeric();
+        if valid && delimited && numbers[0] < u32::MAX as u64 && numbers[1] <= u16::MAX as u64 {
+            return Some(((numbers[0] as u32, numbers[1] as u16), at));
+        }
+    }
+    None
+}
+
+/// Strips the `stream` keyword and its end-of-line marker from the bytes
+/// before a stream's data.
+fn stream_header(before_data: &[u8]) -> &[u8] {
+    let mut end = before_data.len();
+    while end > 0 && matches!(before_data[end - 1], b'\r' | b'\n') {
+        end -= 1;
+    }
+    &before_data[..end.saturating_sub(b"stream".len())]
+}
+
+/// Removes a trailing `N G` (the start of the following object's header)
+/// from an object body that was not closed with `endobj`.
+fn trim_object_prefix(body: &[u8]) -> &[u8] {
+    let mut end = body.len();
+    for _ in 0..2 {
+        while end > 0 && body[end - 1].is_ascii_whitespace() {
+            end -= 1;
+        }
+        while end > 0 && body[end - 1].is_ascii_digit() {
+            end -= 1;
+        }
+    }
+    &body[..end]
+}
+
+fn contains_type(header: &[u8], name: &[u8]) -> bool {
+    let compact: Vec<u8> = header
+        .iter()
+        .copied()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect();
+    let mut needle = b"/Type/".to_vec();
+    needle.extend_from_slice(name);
+    find(&compact, &needle).is_some_and(|at| {
+        !compact
+            .get(at + needle.len())
+            .is_some_and(|c| c.is_ascii_alphanumeric())
+    })
+}
+
+/// Reads the integer held by an indirect object, such as a stream length
+/// written after the stream.
+fn indirect_integer(bytes: &[u8], id: ObjectId) -> Option<i64> {
+    let header = format!("{} {} obj", id.0, id.1);
+    let mut from = 0;
+    while let Some(found) = find(&bytes[from..], header.as_bytes()) {
+        let at = from + found;
+        from = at + header.len();
+        if at > 0 && bytes[at - 1].is_ascii_digit() {
+            continue;
+        }
+        let rest = &bytes[from..bytes.len().min(from + 32)];
+        let text = String::from_utf8_lossy(rest);
+        return text.split_ascii_whitespace().next()?.parse().ok();
+    }
+    None
+}
+
+#[derive(Default)]
+struct Trailer {
+    root: Option<ObjectId>,
+    info: Option<ObjectId>,
+    encrypt: Option<ObjectId>,
+    id: Option<Vec<u8>>, // Raw /ID array, needed to decrypt
+}
+
+/// Takes the trailer keys from the last `trailer` dictionary or xref stream
+/// that names an existing catalog.
+fn recover_trailer(bytes: &[u8], scan: &Scan) -> Trailer {
+    let mut candidates: Vec<&[u8]> = Vec::new();
+    let mut from = 0;
+    while let Some(found) = find(&bytes[from..], b"trailer") {
+        let at = from + found + b"trailer".len();
+        candidates.push(&bytes[at..bytes.len().min(at + TRAILER_WINDOW)]);
+        from = at;
+    }
+    candidates.extend(scan.xref_streams.iter().map(Vec::as_slice));
+    // Later trailers win, but xref streams and trailers are interleaved by
+    // position only loosely, so take the first one naming a real catalog
+    // from the end of each list
+    let existing = |dict: &[u8], key: &[u8]| {
+        raw_entry(dict, key)
+            .and_then(|(_, number, generation)| Some((u32::try_from(number).ok()?, generation?)))
+            .filter(|id| scan.objects.contains_key(id))
+    };
+    let Some(dict) = candidates.iter().rev().find(|dict| {
+        existing(dict, b"/Root").is_some_and(|root| {
+            scan.objects
+                .get(&root)
+                .is_some_and(|object| contains_type(&object.header, b"Catalog"))
+        })
+    }) else {
+        return Trailer::default();
+    };
+
+    let id = find(dict, b"/ID").and_then(|at| {
+        let end = find(&dict[at..], b"]")?;
+        Some(dict[at + 3..at + end + 1].to_vec())
+    });
+    Trailer {
+        root: existing(dict, b"/Root"),
+        info: existing(dict, b"/Info"),
+        encrypt: existing(dict, b"/Encrypt"),
+        id,
+    }
+}
+
+/// Writes the recovered objects into a fresh file with a classic xref
+/// table, so lopdf can load it normally.
+fn rebuild(
+    bytes: &[u8],
+    scan: &Scan,
+    trailer: &Trailer,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let version = bytes
+        .strip_prefix(b"%PDF-")
+        .or_else(|| find(bytes, b"%PDF-").map(|at| &bytes[at + 5..]))
+        .map(|rest| {
+            rest.iter()
+                .take_while(|c| c.is_ascii_digit() || **c == b'.')
+                .map(|c| *c as char)
+                .collect::<String>()
+        })
+        .filter(|version| !version.is_empty())
+        .unwrap_or_else(|| "1.7".to_string());
+
+    let mut output = Vec::new();
+    writeln!(output, "%PDF-{version}")?;
+    output.extend_from_slice(b"%\xe2\xe3\xcf\xd3\n");
+    let mut offsets = BTreeMap::new();
+    for (id, object) in &scan.objects {
+        offsets.insert(id.0, (output.len(), id.1));
+        writeln!(output, "{} {} obj", id.0, id.1)?;
+        match &object.stream {
+            Some(data) => {
+                output.extend_from_slice(&with_length(&object.header, data.len()));
+                output.extend_from_slice(b"\nstream\n");
+                output.extend_from_slice(data);
+                output.extend_from_slice(b"\nendstream");
+            }
+            None => output.extend_from_slice(&object.header),
+        }
+        output.extend_from_slice(b"\nendobj\n");
+    }
+
+    let size = offsets.keys().max().copied().unwrap_or(0) + 1;
+    let xref_start = output.len();
+    write!(output, "xref\n0 {size}\n0000000000 65535 f \n")?;
+    for number in 1..size {
+        match offsets.get(&number) {
+            Some((offset, generation)) => writeln!(output, "{offset:010} {generation:05} n ")?,
+            None => writeln!(output, "0000000000 00000 f ")?,
+        }
+    }
+    write!(output, "trailer\n<< /Size {size}")?;
+    if let Some(root) = trailer.root {
+        write!(output, " /Root {} {} R", root.0, root.1)?;
+    }
+    if let Some(info) = trailer.info {
+        write!(output, " /Info {} {} R", info.0, info.1)?;
+    }
+    if let Some(encrypt) = trailer.encrypt {
+        write!(output, " /Encrypt {} {} R", encrypt.0, encrypt.1)?;
+    }
+    if let Some(id) = &trailer.id {
+        output.extend_from_slice(b" /ID ");
+        output.extend_from_slice(id);
+    }
+    write!(output, " >>\nstartxref\n{xref_start}\n%%EOF\n")?;
+    Ok(output)
+}
+
+/// Sets a raw stream dictionary's `/Length` to the real data length.
+fn with_length(header: &[u8], length: usize) -> Vec<u8> {
+    match raw_entry(header, b"/Length") {
+        Some((range, ..)) => {
+            let mut fixed = header[..range.start].to_vec();
+            fixed.extend_from_slice(length.to_string().as_bytes());
+            fixed.extend_from_slice(&header[range.end..]);
+            fixed
+        }
+        None => {
+            let at = find(header, b"<<").map_or(0, |at| at + 2);
+            let mut fixed = header[..at].to_vec();
+            fixed.extend_from_slice(format!(" /Length {length} ").as_bytes());
+            fixed.extend_from_slice(&header[at..]);
+            fixed
+        }
+    }
+}
+
+/// Makes sure the trailer points at a catalog with a page tree, finding or
+/// creating them as needed.
+fn repair_catalog(document: &mut Document, report: &mut RepairReport) {
+    let type_of = |object: &Object| {
+        object
+            .as_dict()
+            .and_then(|dict| dict.get(b"Type"))
+            .and_then(Object::as_name)
+            .map(<[u8]>::to_vec)
+            .ok()
+    };
+    let find_type = |document: &Document, name: &[u8]| -> Vec<ObjectId> {
+        document
+            .objects
+            .iter()
+            .filter(|(_, object)| type_of(object).as_deref() == Some(name))
+            .map(|(id, _)| *id)
+            .collect()
+    };
+
+    let root = document
+        .trailer
+        .get(b"Root")
+        .and_then(Object::as_reference)
+        .ok()
+        .filter(|root| document.get_dictionary(*root).is_ok());
+    let root = match root.or_else(|| find_type(document, b"Catalog").last().copied()) {
+        Some(root) => root,
+        None => {
+            let mut catalog = Dictionary::new();
+            catalog.set("Type", Object::Name(b"Catalog".to_vec()));
+            report.catalog_created = true;
+            document.add_object(catalog)
+        }
+    };
+    document.trailer.set("Root", Object::Reference(root));
+
+    let has_pages = document
+        .get_dictionary(root)
+        .and_then(|catalog| catalog.get(b"Pages"))
+        .and_then(Object::as_reference)
+        .is_ok_and(|pages| document.get_dictionary(pages).is_ok());
+    if has_pages {
+        return;
+    }
+
+    // The root of the page tree is the Pages node without a parent
+    let pages_nodes = find_type(document, b"Pages");
+    let pages_id = pages_nodes
+        .iter()
+        .find(|id| {
+            document
+                .get_dictionary(**id)
+                .is_ok_and(|node| node.get(b"Parent").is_err())
+        })
+        .copied();
+    let pages_id = match pages_id {
+        Some(pages_id) => pages_id,
+        None => {
+            // Gather every page under a new node
+            let page_ids = find_type(document, b"Page");
+            let mut pages = Dictionary::new();
+            pages.set("Type", Object::Name(b"Pages".to_vec()));
+            pages.set(
+                "Kids",
+                Object::Array(page_ids.iter().map(|id| Object::Reference(*id)).collect()),
+            );
+            pages.set("Count", Object::Integer(page_ids.len() as i64));
+            let pages_id = document.add_object(pages);
+            for page_id in page_ids {
+                if let Ok(page) = document.get_dictionary_mut(page_id) {
+                    page.set("Parent", Object::Reference(pages_id));
+                }
+            }
+            report.page_tree_created = true;
+            pages_id
+        }
+    };
+    if let Ok(catalog) = document.get_dictionary_mut(root) {
+        catalog.set("Pages", Object::Reference(pages_id));
+    }
+}
+
+/// Recomputes `/Count` through the page tree, dropping kids that no longer
+/// exist; returns the number of pages below `node_id`.
+fn fix_page_counts(
+    document: &mut Document,
+    node_id: ObjectId,
+    visited: &mut HashSet<ObjectId>,
+    report: &mut RepairReport,
+) -> u32 {
+    if !visited.insert(node_id) {
+        return 0;
+    }
+    let Ok(node) = document.get_dictionary(node_id) else {
+        return 0;
+    };
+    let Ok(kids) = node.get(b"Kids").and_then(Object::as_array) else {
+        return 1;
+    };
+    let kids: Vec<ObjectId> = kids
+        .iter()
+        .filter_map(|kid| kid.as_reference().ok())
+        .filter(|kid| document.get_dictionary(*kid).is_ok())
+        .collect();
+    let count: u32 = kids
+        .iter()
+        .map(|kid| fix_page_counts(document, *kid, visited, report))
+        .sum();
+
+    if let Ok(node) = document.get_dictionary_mut(node_id) {
+        if node.get(b"Count").and_then(Object::as_i64).ok() != Some(count as i64) {
+            report.page_counts_fixed += 1;
+            node.set("Count", Object::Integer(count as i64));
+        }
+        node.set(
+            "Kids",
+            Object::Array(kids.into_iter().map(Object::Reference).collect()),
+        );
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_pdf;
+    use crate::validate::validate_pdf;
+    use tempfile::TempDir;
+
+    fn sample_bytes(dir: &TempDir, pages: u32) -> Vec<u8> {
+        let path = dir.path().join("sample.pdf");
+        create_test_pdf(pages, "Repair", "text")
+            .unwrap()
+            .save(&path)
+            .unwrap();
+        std::fs::read(path).unwrap()
+    }
+
+    #[test]
+    fn test_repair_truncated_file() {
+        let dir = TempDir::new().unwrap();
+        let mut bytes = sample_bytes(&dir, 3);
+        // Cut the file off before its xref and trailer
+        let xref = bytes
+            .windows(5)
+            .rposition(|window| window == b"/XRef")
+            .or_else(|| bytes.windows(4).rposition(|window| window == b"xref"))
+            .unwrap();
+        bytes.truncate(xref - 20);
+        let input = dir.path().join("truncated.pdf");
+        std::fs::write(&input, &bytes).unwrap();
+        let input = input.to_string_lossy().to_string();
+        let output = dir
+            .path()
+            .join("repaired.pdf")
+            .to_string_lossy()
+            .to_string();
+        assert!(Document::load(&input).is_err());
+
+        let report = repair_pdf(&input, &output).unwrap();
+        assert!(report.load_error.is_some());
+        assert!(report.trailer_recovered);
+        assert!(!report.catalog_created);
+        assert_eq!(report.page_count, 3);
+
+        let document = Document::load(&output).unwrap();
+        assert_eq!(document.get_pages().len(), 3);
+        let content = document.get_page_content(document.get_pages()[&3]).unwrap();
+        assert!(String::from_utf8_lossy(&content).contains("Repair - Page 3"));
+    }
+
+    #[test]
+    fn test_repair_offsets_and_stream_lengths() {
+        let dir = TempDir::new().unwrap();
+        let bytes = sample_bytes(&dir, 2);
+
+        // Shift every object so no xref offset is right, and understate the
+        // first content stream's /Length
+        let header_end = bytes.iter().position(|c| *c == b'\n').unwrap() + 1;
+        let mut damaged = bytes[..header_end].to_vec();
+        damaged.extend_from_slice(b"% inserted by a broken mail gateway\n");
+        damaged.extend_from_slice(&bytes[header_end..]);
+        let length_at = find(&damaged, b"/Length ").unwrap() + 8;
+        damaged[length_at] = b'1';
+        let input = dir.path().join("damaged.pdf");
+        std::fs::write(&input, &damaged).unwrap();
+        let input = input.to_string_lossy().to_string();
+        let output = dir
+            .path()
+            .join("repaired.pdf")
+            .to_string_lossy()
+            .to_string();
+        assert!(!validate_pdf(&input).unwrap().valid);
+
+        let report = repair_pdf(&input, &output).unwrap();
+        assert_eq!(report.stream_lengths_fixed, 1);
+        assert!(report.objects_dropped.is_empty());
+        assert_eq!(report.page_count, 2);
+
+        let validation = validate_pdf(&output).unwrap();
+        assert!(validation.valid, "{:?}", validation.findings);
+        let document = Document::load(&output).unwrap();
+        let content = document.get_page_content(document.get_pages()[&1]).unwrap();
+        assert!(String::from_utf8_lossy(&content).contains("Repair - Page 1"));
+    }
+
+    #[test]
+    fn test_repair_object_stream_update() {
+        let dir = TempDir::new().unwrap();
+
+        // The original revision keeps the Info dictionary in object stream
+        // 10; the update redefines it in object stream 9, whose lower number
+        // must not make the original win
+        let original = b"4 0 << /Title (Old) >>";
+        let members = format!(
+            "4 0 << /Title (New) /Keywords ({}) >>",
+            "repair ".repeat(20)
+        );
+        let mut update = Stream::new(Dictionary::new(), members.into_bytes());
+        update.compress().unwrap();
+        assert!(update.dict.has(b"Filter"));
+
+        let mut bytes = b"%PDF-1.5\n".to_vec();
+        bytes.extend_from_slice(b"1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n");
+        bytes.extend_from_slice(b"2 0 obj\n<< /Type /Pages /Kids [3 0 R] /Count 1 >>\nendobj\n");
+        bytes.extend_from_slice(
+            b"3 0 obj\n<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] >>\nendobj\n",
+        );
+        write!(
+            bytes,
+            "10 0 obj\n<< /Type /ObjStm /N 1 /First 4 /Length {} >>\nstream\n",
+            original.len()
+        )
+        .unwrap();
+        bytes.extend_from_slice(original);
+        bytes.extend_from_slice(b"\nendstream\nendobj\n");
+        bytes.extend_from_slice(b"trailer\n<< /Root 1 0 R /Info 4 0 R >>\n%%EOF\n");
+        write!(
+            bytes,
+            "9 0 obj\n<< /Type /ObjStm /N 1 /First 4 /Filter /FlateDecode /Length {} >>\nstream\n",
+            update.content.len()
+        )
+        .unwrap();
+        bytes.extend_from_slice(&update.content);
+        bytes.extend_from_slice(b"\nendstream\nendobj\n");
+        bytes.extend_from_slice(b"trailer\n<< /Root 1 0 R /Info 4 0 R >>\n%%EOF\n");
+
+        let input = dir.path().join("updated.pdf");
+        std::fs::write(&input, &bytes).unwrap();
+        let input = input.to_string_lossy().to_string();
+        let output = dir
+            .path()
+            .join("repaired.pdf")
+            .to_string_lossy()
+            .to_string();
+
+        let report = repair_pdf(&input, &output).unwrap();
+        assert_eq!(report.page_count, 1);
+
+        let document = Document::load(&output).unwrap();
+        let info = document
+            .trailer
+            .get(b"Info")
+            .and_then(Object::as_reference)
+            .unwrap();
+        let title = document
+            .get_dictionary(info)
+            .and_then(|info| info.get(b"Title"))
+            .and_then(Object::as_str)
+            .unwrap();
+        assert_eq!(title, b"New");
+        // Members are written as plain objects, without their containers
+        assert!(document
+            .objects
+            .values()
+            .all(|object| object.type_name().ok() != Some(b"ObjStm".as_slice())));
+    }
+}
@@ -0,0 +1,545 @@
+use lopdf::content::Content;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use napi_derive::napi;
+
+use crate::merge::resolve_dictionary;
+use crate::page_edit::paper_size;
+use crate::page_range::select_pages;
+use crate::rotate::{
+    normalize_page, rect_object, transform_annotation_rects, transform_rect, PAGE_BOXES,
+};
+use crate::sign::{inherited_page_attribute, page_box, page_rect, transform_page_content};
+use crate::watermark::text_width;
+
+const IDENTITY: [f64; 6] = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+#[derive(Debug, Clone, Default)]
+#[napi(object)]
+pub struct PageBoxOptions {
+    pub page_range: Option<String>,  // Pages to change (default: all)
+    pub media_box: Option<Vec<f64>>, // [llx, lly, urx, ury] in points
+    pub crop_box: Option<Vec<f64>>,
+    pub bleed_box: Option<Vec<f64>>,
+    pub trim_box: Option<Vec<f64>>,
+    pub art_box: Option<Vec<f64>>,
+}
+
+#[derive(Debug, Clone, Default)]
+#[napi(object)]
+pub struct ScaleOptions {
+    pub page_range: Option<String>, // Pages to scale (default: all)
+    pub paper_size: Option<String>, // "A3", "A4", "A5", "Letter", "Legal" or "Tabloid"
+    pub width: Option<f64>, // Target width in points; overrides paper_size together with height
+    pub height: Option<f64>,
+    pub mode: Option<String>, // "fit" (default) keeps all content visible, "fill" covers the page
+    pub landscape: Option<bool>, // Defaults to the orientation of each source page
+}
+
+/// Sets page boundary boxes on the selected pages.
+///
+/// # Returns
+/// The number of pages changed.
+pub fn set_page_boxes(
+    input_path: &str,
+    output_path: &str,
+    options: &PageBoxOptions,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    let boxes = [
+        ("MediaBox", &options.media_box),
+        ("CropBox", &options.crop_box),
+        ("BleedBox", &options.bleed_box),
+        ("TrimBox", &options.trim_box),
+        ("ArtBox", &options.art_box),
+    ];
+    let mut updates = Vec::new();
+    for (key, values) in boxes {
+        let Some(values) = values else {
+            continue;
+        };
+        let rect = <[f64; 4]>::try_from(values.as_slice())
+            .map_err(|_| format!("{key} needs 4 values, got {}", values.len()))?;
+        updates.push((key, rect));
+    }
+    if updates.is_empty() {
+        return Err("No page boxes given".into());
+    }
+
+    let mut document = Document::load(input_path)?;
+    let page_ids = select_pages(&document, options.page_range.as_deref(), None)?;
+    for page_id in &page_ids {
+        let page = document.get_dictionary_mut(*page_id)?;
+        for (key, rect) in &updates {
+            page.set(*key, rect_object(*rect));
+        }
+    }
+
+    document.save(output_path)?;
+    Ok(page_ids.len() as u32)
+}
+
+/// Sets each selected page's CropBox to the area its content covers, grown by
+/// `margin` points (default 0) and kept inside the MediaBox. Pages without
+/// visible content are left alone.
+///
+/// # Returns
+/// The number of pages cropped.
+pub fn auto_crop(
+    input_path: &str,
+    output_path: &str,
+    page_range: Option<&str>,
+    margin: Option<f64>,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    let margin = margin.unwrap_or(0.0);
+    let mut document = Document::load(input_path)?;
+    let page_ids = select_pages(&document, page_range, None)?;
+
+    let mut cropped = 0;
+    for page_id in page_ids {
+        let Some([llx, lly, urx, ury]) = content_bounds(&document, page_id)? else {
+            continue;
+        };
+        let [mllx, mlly, murx, mury] =
+            page_rect(&document, page_id, b"MediaBox").unwrap_or([0.0, 0.0, 612.0, 792.0]);
+        let crop = [
+            (llx - margin).max(mllx),
+            (lly - margin).max(mlly),
+            (urx + margin).min(murx),
+            (ury + margin).min(mury),
+        ];
+        document
+            .get_dictionary_mut(page_id)?
+            .set("CropBox", rect_object(crop));
+        cropped += 1;
+    }
+
+    document.save(output_path)?;
+    Ok(cropped)
+}
+
+/// Scales the selected pages onto a new paper size.
+///
+/// The visible area of each page is scaled by a `cm` transform prepended to
+/// its content and centered on the new MediaBox; nothing is rasterized.
+/// Rotated pages are normalized first so the target orientation is what the
+/// reader sees.
+///
+/// # Returns
+/// The number of pages scaled.
+pub fn scale_pages(
+    input_path: &str,
+    output_path: &str,
+    options: &ScaleOptions,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    let (width, height) = match (options.width, options.height) {
+        (Some(width), Some(height)) => (width, height),
+        _ => {
+            let name = options.paper_size.as_deref().unwrap_or("A4");
+            paper_size(name).ok_or_else(|| format!("Unknown paper size: '{name}'"))?
+        }
+    };
+    let fill = match options.mode.as_deref().unwrap_or("fit") {
+        "fit" => false,
+        "fill" => true,
+        other => return Err(format!("Unsupported scale mode: '{other}'").into()),
+    };
+
+    let mut document = Document::load(input_path)?;
+    let page_ids = select_pages(&document, options.page_range.as_deref(), None)?;
+    for page_id in &page_ids {
+        normalize_page(&mut document, *page_id)?;
+
+        let [llx, lly, urx, ury] = page_box(&document, *page_id);
+        let (source_width, source_height) = (urx - llx, ury - lly);
+        let landscape = options.landscape.unwrap_or(source_width > source_height);
+        let (target_width, target_height) = if landscape == (width > height) {
+            (width, height)
+        } else {
+            (height, width)
+        };
+
+        let scale_x = target_width / source_width;
+        let scale_y = target_height / source_height;
+        let scale = if fill {
+            scale_x.max(scale_y)
+        } else {
+            scale_x.min(scale_y)
+        };
+        let matrix = [
+            scale,
+            0.0,
+            0.0,
+            scale,
+            (target_width - source_width * scale) / 2.0 - llx * scale,
+            (target_height - source_height * scale) / 2.0 - lly * scale,
+        ];
+
+        let target = [0.0, 0.0, target_width, target_height];
+        let boxes: Vec<(&[u8], [f64; 4])> = PAGE_BOXES[2..]
+            .iter()
+            .filter_map(|key| Some((*key, page_rect(&document, *page_id, key)?)))
+            .collect();
+        transform_page_content(&mut document, *page_id, matrix)?;
+        transform_annotation_rects(&mut document, *page_id, matrix)?;
+
+        let page = document.get_dictionary_mut(*page_id)?;
+        page.set("MediaBox", rect_object(target));
+        page.remove(b"CropBox");
+        for (key, rect) in boxes {
+            let [a, b, c, d] = transform_rect(rect, matrix);
+            let clipped = [
+                a.max(0.0),
+                b.max(0.0),
+                c.min(target_width),
+                d.min(target_height),
+            ];
+            page.set(key, rect_object(clipped));
+        }
+    }
+
+    document.save(output_path)?;
+    Ok(page_ids.len() as u32)
+}
+
+/// Estimates the area a page's content draws on, in default user space,
+/// clipped to the MediaBox.
+///
+/// Paths, images and Form XObjects are measured exactly (up to their
+/// bounding boxes); text is measured with Helvetica metrics, which is close
+/// enough for cropping margins.
+pub(crate) fn content_bounds(
+    document: &Document,
+    page_id: ObjectId,
+) -> lopdf::Result<Option<[f64; 4]>> {
+    let content = Content::decode(&document.get_page_content(page_id)?)?;
+    let resources = match inherited_page_attribute(document, page_id, b"Resources") {
+        Some(Object::Dictionary(resources)) => resources,
+        _ => Dictionary::new(),
+    };
+    let xobjects = resolve_dictionary(document, resources.get(b"XObject").ok());
+
+    let mut bounds = Bounds::default();
+    let mut ctm = IDENTITY;
+    let mut stack = Vec::new();
+    let mut path: Vec<(f64, f64)> = Vec::new();
+    let mut text = TextState::default();
+
+    for operation in &content.operations {
+        let operands: Vec<f64> = operation
+            .operands
+            .iter()
+            .filter_map(|operand| operand.as_float().ok().map(f64::from))
+            .collect();
+        match operation.operator.as_str() {
+            "q" => stack.push(ctm),
+            "Q" => ctm = stack.pop().unwrap_or(IDENTITY),
+            "cm" if operands.len() == 6 => {
+                ctm = multiply(to_matrix(&operands), ctm);
+            }
+            "m" | "l" if operands.len() == 2 => path.push(apply(ctm, operands[0], operands[1])),
+            "c" | "v" | "y" => {
+                for [x, y] in operands.as_chunks::<2>().0 {
+                    path.push(apply(ctm, *x, *y));
+                }
+            }
+            "re" if operands.len() == 4 => {
+                let (x, y, w, h) = (operands[0], operands[1], operands[2], operands[3]);
+                for (px, py) in [(x, y), (x + w, y), (x, y + h), (x + w, y + h)] {
+                    path.push(apply(ctm, px, py));
+                }
+            }
+            "S" | "s" | "f" | "F" | "f*" | "B" | "B*" | "b" | "b*" => {
+                for (x, y) in path.drain(..) {
+                    bounds.add(x, y);
+                }
+            }
+            "n" => path.clear(),
+            "BI" => bounds.add_rect([0.0, 0.0, 1.0, 1.0], ctm),
+            "Do" => {
+                let xobject = operation
+                    .operands
+                    .first()
+                    .and_then(|name| name.as_name().ok())
+                    .and_then(|name| xobjects?.get(name).ok())
+                    .and_then(|xobject| document.dereference(xobject).ok())
+                    .and_then(|(_, xobject)| xobject.as_stream().ok());
+                let Some(xobject) = xobject else {
+                    continue;
+                };
+                match xobject.dict.get(b"Subtype").and_then(Object::as_name) {
+                    Ok(b"Image") => bounds.add_rect([0.0, 0.0, 1.0, 1.0], ctm),
+                    Ok(b"Form") => {
+                        let bbox = float_array(xobject.dict.get(b"BBox").ok());
+                        let matrix = float_array(xobject.dict.get(b"Matrix").ok())
+                            .map(|m| to_matrix(&m))
+                            .unwrap_or(IDENTITY);
+                        if let Some(bbox) = bbox.and_then(|b| <[f64; 4]>::try_from(b).ok()) {
+                            bounds.add_rect(bbox, multiply(matrix, ctm));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            "BT" => {
+                text.matrix = IDENTITY;
+                text.line = IDENTITY;
+            }
+            "Tf" => {
+                if let Some(size) = operation.operands.get(1).and_then(|s| s.as_float().ok()) {
+                    text.font_size = size as f64;
+                }
+            }
+            "TL" if operands.len() == 1 => text.leading = operands[0],
+            "Tz" if operands.len() == 1 => text.scale = operands[0] / 100.0,
+            "Td" | "TD" if operands.len() == 2 => {
+                if operation.operator == "TD" {
+                    text.leading = -operands[1];
+                }
+                text.next_line(operands[0], operands[1]);
+            }
+            "Tm" if operands.len() == 6 => {
+                text.line = to_matrix(&operands);
+                text.matrix = text.line;
+            }
+            "T*" => text.next_line(0.0, -text.leading),
+            "Tj" | "'" | "\"" => {
+                if operation.operator != "Tj" {
+                    text.next_line(0.0, -text.leading);
+                }
+                if let Some(Object::String(bytes, _)) = operation.operands.last() {
+                    text.show(bytes, 0.0, ctm, &mut bounds);
+                }
+            }
+            "TJ" => {
+                let Some(Object::Array(items)) = operation.operands.first() else {
+                    continue;
+                };
+                for item in items {
+                    match item {
+                        Object::String(bytes, _) => text.show(bytes, 0.0, ctm, &mut bounds),
+                        other => {
+                            let adjust = other.as_float().map(f64::from).unwrap_or(0.0);
+                            text.show(&[], adjust, ctm, &mut bounds);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let Some([llx, lly, urx, ury]) = bounds.rect else {
+        return Ok(None);
+    };
+    let [mllx, mlly, murx, mury] =
+        page_rect(document, page_id, b"MediaBox").unwrap_or([0.0, 0.0, 612.0, 792.0]);
+    let clipped = [llx.max(mllx), lly.max(mlly), urx.min(murx), ury.min(mury)];
+    if clipped[0] >= clipped[2] || clipped[1] >= clipped[3] {
+        return Ok(None);
+    }
+    Ok(Some(clipped))
+}
+
+#[derive(Default)]
+struct Bounds {
+    rect: Option<[f64; 4]>,
+}
+
+impl Bounds {
+    fn add(&mut self, x: f64, y: f64) {
+        self.rect = Some(match self.rect {
+            Some([llx, lly, urx, ury]) => [llx.min(x), lly.min(y), urx.max(x), ury.max(y)],
+            None => [x, y, x, y],
+        });
+    }
+
+    fn add_rect(&mut self, rect: [f64; 4], matrix: [f64; 6]) {
+        let [llx, lly, urx, ury] = transform_rect(rect, matrix);
+        self.add(llx, lly);
+        self.add(urx, ury);
+    }
+}
+
+struct TextState {
+    matrix: [f64; 6],
+    line: [f64; 6],
+    font_size: f64,
+    leading: f64,
+    scale: f64,
+}
+
+impl Default for TextState {
+    fn default() -> Self {
+        TextState {
+            matrix: IDENTITY,
+            line: IDENTITY,
+            font_size: 12.0,
+            leading: 0.0,
+            scale: 1.0,
+        }
+    }
+}
+
+impl TextState {
+    fn next_line(&mut self, tx: f64, ty: f64) {
+        self.line = multiply([1.0, 0.0, 0.0, 1.0, tx, ty], self.line);
+        self.matrix = self.line;
+    }
+
+    /// Measures a run of text, adds it to `bounds` and advances the text
+    /// matrix. `adjust` is a `TJ` kerning value in thousandths of an em.
+    fn show(&mut self, bytes: &[u8], adjust: f64, ctm: [f64; 6], bounds: &mut Bounds) {
+        let text: String = bytes.iter().map(|byte| *byte as char).collect();
+        let width =
+            (text_width(&text, self.font_size) - adjust / 1000.0 * self.font_size) * self.scale;
+        if !bytes.is_empty() {
+            // Descenders reach about a fifth of the font size below the baseline
+            let extent = [0.0, -0.2 * self.font_size, width, 0.8 * self.font_size];
+            bounds.add_rect(extent, multiply(self.matrix, ctm));
+        }
+        self.matrix = multiply([1.0, 0.0, 0.0, 1.0, width, 0.0], self.matrix);
+    }
+}
+
+/// Multiplies two PDF matrices; `multiply(m, ctm)` is the matrix `cm` with
+/// operands `m` produces.
+pub(crate) fn multiply(m: [f64; 6], n: [f64; 6]) -> [f64; 6] {
+    [
+        m[0] * n[0] + m[1] * n[2],
+        m[0] * n[1] + m[1] * n[3],
+        m[2] * n[0] + m[3] * n[2],
+        m[2] * n[1] + m[3] * n[3],
+        m[4] * n[0] + m[5] * n[2] + n[4],
+        m[4] * n[1] + m[5] * n[3] + n[5],
+    ]
+}
+
+fn apply(m: [f64; 6], x: f64, y: f64) -> (f64, f64) {
+    (m[0] * x + m[2] * y + m[4], m[1] * x + m[3] * y + m[5])
+}
+
+fn to_matrix(values: &[f64]) -> [f64; 6] {
+    <[f64; 6]>::try_from(values).unwrap_or(IDENTITY)
+}
+
+fn float_array(object: Option<&Object>) -> Option<Vec<f64>> {
+    object?
+        .as_array()
+        .ok()?
+        .iter()
+        .map(|value| value.as_float().ok().map(f64::from))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_pdf;
+    use lopdf::Stream;
+    use tempfile::TempDir;
+
+    fn save_input(dir: &TempDir, document: &mut Document) -> String {
+        let path = dir.path().join("input.pdf").to_string_lossy().to_string();
+        document.save(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_set_page_boxes() {
+        let dir = TempDir::new().unwrap();
+        let input = save_input(&dir, &mut create_test_pdf(2, "Boxes", "text").unwrap());
+        let output = dir.path().join("boxes.pdf").to_string_lossy().to_string();
+
+        let options = PageBoxOptions {
+            page_range: Some("2".to_string()),
+            crop_box: Some(vec![10.0, 10.0, 602.0, 782.0]),
+            trim_box: Some(vec![20.0, 20.0, 592.0, 772.0]),
+            ..Default::default()
+        };
+        assert_eq!(set_page_boxes(&input, &output, &options).unwrap(), 1);
+
+        let document = Document::load(&output).unwrap();
+        let pages = document.get_pages();
+        assert_eq!(page_rect(&document, pages[&1], b"CropBox"), None);
+        assert_eq!(
+            page_rect(&document, pages[&2], b"TrimBox"),
+            Some([20.0, 20.0, 592.0, 772.0])
+        );
+
+        let invalid = PageBoxOptions {
+            crop_box: Some(vec![0.0, 0.0, 100.0]),
+            ..Default::default()
+        };
+        assert!(set_page_boxes(&input, &output, &invalid).is_err());
+    }
+
+    #[test]
+    fn test_auto_crop_to_content() {
+        let dir = TempDir::new().unwrap();
+        let mut document = create_test_pdf(1, "Crop", "text").unwrap();
+        let page_id = document.get_pages()[&1];
+        let content = b"q 2 0 0 2 100 100 cm 0 0 50 25 re f Q".to_vec();
+        let content_id = document.add_object(Stream::new(Dictionary::new(), content));
+        document
+            .get_dictionary_mut(page_id)
+            .unwrap()
+            .set("Contents", Object::Reference(content_id));
+        let input = save_input(&dir, &mut document);
+        let output = dir.path().join("cropped.pdf").to_string_lossy().to_string();
+
+        assert_eq!(auto_crop(&input, &output, None, Some(10.0)).unwrap(), 1);
+
+        let document = Document::load(&output).unwrap();
+        let crop = page_rect(&document, document.get_pages()[&1], b"CropBox");
+        assert_eq!(crop, Some([90.0, 90.0, 210.0, 160.0]));
+    }
+
+    #[test]
+    fn test_text_bounds() {
+        let mut document = create_test_pdf(1, "Crop", "text").unwrap();
+        let page_id = document.get_pages()[&1];
+        let content = b"BT /F1 10 Tf 72 700 Td (Hi) Tj ET".to_vec();
+        let content_id = document.add_object(Stream::new(Dictionary::new(), content));
+        document
+            .get_dictionary_mut(page_id)
+            .unwrap()
+            .set("Contents", Object::Reference(content_id));
+
+        let [llx, lly, urx, ury] = content_bounds(&document, page_id).unwrap().unwrap();
+        assert_eq!((llx, lly, ury), (72.0, 698.0, 708.0));
+        assert!((urx - (72.0 + text_width("Hi", 10.0))).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_scale_letter_to_a4() {
+        let dir = TempDir::new().unwrap();
+        let input = save_input(&dir, &mut create_test_pdf(1, "Scale", "text").unwrap());
+        let output = dir.path().join("a4.pdf").to_string_lossy().to_string();
+
+        let options = ScaleOptions {
+            paper_size: Some("A4".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(scale_pages(&input, &output, &options).unwrap(), 1);
+
+        let document = Document::load(&output).unwrap();
+        let page_id = document.get_pages()[&1];
+        let [_, _, width, height] = page_rect(&document, page_id, b"MediaBox").unwrap();
+        assert!((width - 595.28).abs() < 0.01 && (height - 841.89).abs() < 0.01);
+
+        // Fit scales by the narrower side (595.28 / 612) and centers vertically
+        let content =
+            String::from_utf8_lossy(&document.get_page_content(page_id).unwrap()).to_string();
+        let cm: Vec<f64> = content
+            .lines()
+            .find(|line| line.ends_with(" cm"))
+            .unwrap()
+            .split_whitespace()
+            .take(6)
+            .map(|value| value.parse().unwrap())
+            .collect();
+        let scale = 595.28 / 612.0;
+        assert!((cm[0] - scale).abs() < 1e-4);
+        assert!(cm[4].abs() < 1e-3);
+        assert!((cm[5] - (841.89 - 792.0 * scale) / 2.0).abs() < 1e-2);
+    }
+}
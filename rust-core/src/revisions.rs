@@ -0,0 +1,430 @@
+use lopdf::{Document, Object, ObjectId};
+use serde::{Deserialize, Serialize};
+
+use crate::diff::object_changes;
+use crate::info::{field_attribute, form_fields, object_text};
+use crate::inspect::reference;
+use crate::metadata::pdf_date_to_iso;
+use crate::validate::{find, raw_entry};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevisionSignature {
+    pub object: String,               // Signature dictionary, e.g. "12 0 R"
+    pub field_name: Option<String>,   // Signature field whose value it is
+    pub sub_filter: Option<String>,   // e.g. "adbe.pkcs7.detached"
+    pub signer: Option<String>,       // /Name as written by the signer
+    pub signing_time: Option<String>, // /M in ISO 8601
+    pub byte_range: Vec<i64>,
+    pub signed_revision: u32, // Revision whose bytes the signature was computed over
+    pub ends_at_revision: bool, // The signed bytes end exactly where that revision ends
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RevisionInfo {
+    pub revision: u32, // 1 is the original file
+    pub start_offset: u64,
+    pub end_offset: u64,          // Just past the revision's %%EOF
+    pub xref_offset: Option<u64>, // The revision's startxref
+    pub page_count: u32,
+    pub object_count: u32,
+    pub objects_added: Vec<String>,
+    pub objects_modified: Vec<String>,
+    pub objects_removed: Vec<String>,
+    pub signatures: Vec<RevisionSignature>, // Signatures whose signed bytes include this revision
+}
+
+/// Lists the revisions of a file: the original and each incremental update
+/// appended to it.
+///
+/// The objects of each revision are compared with the previous one, so an
+/// object rewritten unchanged by an update is not listed. A signature covers
+/// a revision when its `/ByteRange` extends at least to the end of it; the
+/// signature itself may have been added in a later revision.
+pub fn list_revisions(file_path: &str) -> Result<Vec<RevisionInfo>, Box<dyn std::error::Error>> {
+    let bytes = std::fs::read(file_path)?;
+    let ends = revision_ends(&bytes);
+    if ends.is_empty() {
+        return Err("No %%EOF marker found; the file may be truncated".into());
+    }
+
+    let mut documents = Vec::new();
+    for (index, end) in ends.iter().enumerate() {
+        let document = Document::load_mem(&bytes[..*end])
+            .map_err(|e| format!("Revision {} could not be loaded: {e}", index + 1))?;
+        documents.push(document);
+    }
+
+    // Signatures are collected from every revision, since a later one may
+    // remove a signature field
+    let mut signatures: Vec<RevisionSignature> = Vec::new();
+    for document in &documents {
+        for signature in document_signatures(document, &ends) {
+            if !signatures
+                .iter()
+                .any(|known| known.byte_range == signature.byte_range)
+            {
+                signatures.push(signature);
+            }
+        }
+    }
+
+    let empty = Document::new();
+    let mut revisions = Vec::new();
+    for (index, document) in documents.iter().enumerate() {
+        let previous = index
+            .checked_sub(1)
+            .map_or(&empty, |previous| &documents[previous]);
+        let changes = object_changes(previous, document);
+        let objects = |kind: &str| {
+            changes
+                .iter()
+                .filter(|change| change.kind == kind)
+                .filter_map(|change| change.object.clone())
+                .collect()
+        };
+        let revision = index as u32 + 1;
+        let start = index.checked_sub(1).map_or(0, |previous| ends[previous]);
+        revisions.push(RevisionInfo {
+            revision,
+            start_offset: start as u64,
+            end_offset: ends[index] as u64,
+            xref_offset: startxref(&bytes[start..ends[index]]),
+            page_count: document.get_pages().len() as u32,
+            object_count: document.objects.len() as u32,
+            objects_added: objects("object_added"),
+            objects_modified: objects("object_modified"),
+            objects_removed: objects("object_removed"),
+            signatures: signatures
+                .iter()
+                .filter(|signature| signature.signed_revision >= revision)
+                .cloned()
+                .collect(),
+        });
+    }
+    Ok(revisions)
+}
+
+/// Writes the file exactly as it was at a revision: the bytes up to and
+/// including that revision's `%%EOF`, so signatures over it stay valid.
+///
+/// # Returns
+/// The number of pages in that revision.
+pub fn extract_revision(
+    file_path: &str,
+    revision: u32,
+    output_path: &str,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    let bytes = std::fs::read(file_path)?;
+    let end = revision_end(&bytes, revision, file_path)?;
+    let document = Document::load_mem(&bytes[..end])
+        .map_err(|e| format!("Revision {revision} could not be loaded: {e}"))?;
+    std::fs::write(output_path, &bytes[..end])?;
+    Ok(document.get_pages().len() as u32)
+}
+
+/// Returns the offsets just past each `%%EOF` marker (and its end-of-line),
+/// where the original file and each incremental update end.
+///
+/// Only markers that close a revision count: they must follow a `startxref`
+/// line whose offset points back at an xref table or stream. A `%%EOF` that
+/// merely appears inside stream data or a string is skipped. The marker
+/// closing the first-page section of a linearized file is not a revision
+/// either.
+pub(crate) fn revision_ends(bytes: &[u8]) -> Vec<usize> {
+    let mut ends = Vec::new();
+    let mut from = 0;
+    while let Some(found) = find(&bytes[from..], b"%%EOF") {
+        let marker = from + found;
+        let mut end = marker + 5;
+        if bytes.get(end) == Some(&b'\r') {
+            end += 1;
+        }
+        if bytes.get(end) == Some(&b'\n') {
+            end += 1;
+        }
+        from = end;
+        if declared_xref(bytes, marker).is_some_and(|offset| points_to_xref(bytes, offset, marker))
+        {
+            ends.push(end);
+        }
+    }
+
+    let header = &bytes[..bytes.len().min(1024)];
+    if let Some(at) = find(header, b"/Linearized") {
+        // The original file ends at /L; markers well before that belong to
+        // its first-page section
+        match raw_entry(&header[at..], b"/L") {
+            Some((_, length, None)) if length > 0 => {
+                let length = length as usize;
+                ends.retain(|end| end + 2 >= length);
+            }
+            _ if ends.len() > 1 => {
+                ends.remove(0);
+            }
+            _ => {}
+        }
+    }
+    ends
+}
+
+/// Reads the offset of a `startxref <n>` line directly before `marker`.
+fn declared_xref(bytes: &[u8], marker: usize) -> Option<usize> {
+    let before = bytes[..marker].trim_ascii_end();
+    let digits = before
+        .iter()
+        .rev()
+        .take_while(|c| c.is_ascii_digit())
+        .count();
+    if digits == 0 {
+        return None;
+    }
+    let keyword = before[..before.len() - digits].trim_ascii_end();
+    if keyword.len() == before.len() - digits || !keyword.ends_with(b"startxref") {
+        return None;
+    }
+    std::str::from_utf8(&before[before.len() - digits..])
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Whether `offset` holds an `xref` table or an `N G obj` cross-reference
+/// stream that starts before `marker`.
+fn points_to_xref(bytes: &[u8], offset: usize, marker: usize) -> bool {
+    let Some(section) = bytes.get(offset..marker) else {
+        return false;
+    };
+    let section = section.trim_ascii_start();
+    if section.starts_with(b"xref") {
+        return true;
+    }
+    let mut rest = section;
+    for _ in 0..2 {
+        let digits = rest.iter().take_while(|c| c.is_ascii_digit()).count();
+        if digits == 0 {
+            return false;
+        }
+        rest = rest[digits..].trim_ascii_start();
+    }
+    if !rest.starts_with(b"obj") {
+        return false;
+    }
+    let dictionary = &rest[..find(rest, b"stream").unwrap_or(rest.len())];
+    find(dictionary, b"/XRef").is_some()
+}
+
+/// Loads a file as it was at a revision, or as it is now for `None`.
+pub(crate) fn load_revision(
+    path: &str,
+    revision: Option<u32>,
+) -> Result<Document, Box<dyn std::error::Error>> {
+    let bytes = std::fs::read(path)?;
+    let end = match revision {
+        Some(revision) => revision_end(&bytes, revision, path)?,
+        None => bytes.len(),
+    };
+    Ok(Document::load_mem(&bytes[..end])?)
+}
+
+fn revision_end(bytes: &[u8], revision: u32, path: &str) -> Result<usize, String> {
+    let ends = revision_ends(bytes);
+    (revision as usize)
+        .checked_sub(1)
+        .and_then(|index| ends.get(index).copied())
+        .ok_or_else(|| {
+            format!(
+                "Revision {revision} does not exist; {path} has {} revision(s)",
+                ends.len()
+            )
+        })
+}
+
+/// Reads the offset after the last `startxref` in a revision's bytes.
+fn startxref(section: &[u8]) -> Option<u64> {
+    let at = section
+        .windows(b"startxref".len())
+        .rposition(|window| window == b"startxref")?;
+    let rest = String::from_utf8_lossy(&section[at + b"startxref".len()..]);
+    rest.split_ascii_whitespace().next()?.parse().ok()
+}
+
+/// Finds the signature dictionaries (anything with a `/ByteRange`) in a
+/// document and works out which revision each one signed.
+fn document_signatures(document: &Document, ends: &[usize]) -> Vec<RevisionSignature> {
+    let fields = form_fields(document);
+    let mut signatures = Vec::new();
+    for (id, object) in &document.objects {
+        let Ok(dict) = object.as_dict() else {
+            continue;
+        };
+        let Some(byte_range) = dict
+            .get(b"ByteRange")
+            .and_then(Object::as_array)
+            .ok()
+            .map(|range| {
+                range
+                    .iter()
+                    .filter_map(|value| value.as_i64().ok())
+                    .collect::<Vec<_>>()
+            })
+            .filter(|range| range.len() == 4)
+        else {
+            continue;
+        };
+
+        // Signers may or may not include the end-of-line after %%EOF
+        let signed_end = (byte_range[2] + byte_range[3]).max(0) as usize;
+        let signed_index = ends
+            .iter()
+            .position(|end| *end >= signed_end)
+            .unwrap_or(ends.len() - 1);
+        let text = |key: &[u8]| dict.get(key).ok().and_then(object_text);
+        signatures.push(RevisionSignature {
+            object: reference(*id),
+            field_name: field_name(document, &fields, *id),
+            sub_filter: text(b"SubFilter"),
+            signer: text(b"Name"),
+            signing_time: text(b"M").map(|date| pdf_date_to_iso(&date).unwrap_or(date)),
+            ends_at_revision: (0..=2).contains(&(ends[signed_index] as i64 - signed_end as i64)),
+            signed_revision: signed_index as u32 + 1,
+            byte_range,
+        });
+    }
+    signatures
+}
+
+fn field_name(document: &Document, fields: &[ObjectId], signature_id: ObjectId) -> Option<String> {
+    let field = fields.iter().find(|field| {
+        field_attribute(document, **field, b"V").and_then(|value| value.as_reference().ok())
+            == Some(signature_id)
+    })?;
+    document
+        .get_dictionary(*field)
+        .ok()?
+        .get(b"T")
+        .ok()
+        .and_then(object_text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_pdf;
+    use lopdf::{Dictionary, IncrementalDocument};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_list_and_extract_revisions() {
+        let dir = TempDir::new().unwrap();
+        let base_path = dir.path().join("base.pdf").to_string_lossy().to_string();
+        let signed_path = dir.path().join("signed.pdf").to_string_lossy().to_string();
+        let output = dir.path().join("rev1.pdf").to_string_lossy().to_string();
+        create_test_pdf(2, "Revisions", "text")
+            .unwrap()
+            .save(&base_path)
+            .unwrap();
+        let base = std::fs::read(&base_path).unwrap();
+
+        // Append a signature over the original bytes, leaving a hole for
+        // /Contents as signers do
+        let mut incremental = IncrementalDocument::load(&base_path).unwrap();
+        let mut signature = Dictionary::new();
+        signature.set("Type", Object::Name(b"Sig".to_vec()));
+        signature.set("SubFilter", Object::Name(b"adbe.pkcs7.detached".to_vec()));
+        signature.set("Name", Object::string_literal("Jane Doe"));
+        signature.set("M", Object::string_literal("D:20250131093000+01'00'"));
+        signature.set(
+            "ByteRange",
+            Object::Array(vec![
+                0.into(),
+                100.into(),
+                200.into(),
+                (base.len() as i64 - 200).into(),
+            ]),
+        );
+        let signature_id = incremental.new_document.add_object(signature);
+        let mut field = Dictionary::new();
+        field.set("FT", Object::Name(b"Sig".to_vec()));
+        field.set("T", Object::string_literal("Approval"));
+        field.set("V", Object::Reference(signature_id));
+        let field_id = incremental.new_document.add_object(field);
+        let mut form = Dictionary::new();
+        form.set("Fields", Object::Array(vec![Object::Reference(field_id)]));
+        let catalog_id = incremental
+            .get_prev_documents()
+            .trailer
+            .get(b"Root")
+            .and_then(Object::as_reference)
+            .unwrap();
+        incremental
+            .opt_clone_object_to_new_document(catalog_id)
+            .unwrap();
+        incremental
+            .new_document
+            .get_dictionary_mut(catalog_id)
+            .unwrap()
+            .set("AcroForm", Object::Dictionary(form));
+        incremental.save(&signed_path).unwrap();
+
+        let revisions = list_revisions(&signed_path).unwrap();
+        assert_eq!(revisions.len(), 2);
+        let (original, update) = (&revisions[0], &revisions[1]);
+        assert_eq!(original.start_offset, 0);
+        assert_eq!(update.start_offset, original.end_offset);
+        assert!(original.xref_offset.unwrap() < original.end_offset);
+        assert!(update.xref_offset.unwrap() > original.end_offset);
+        assert_eq!((original.page_count, update.page_count), (2, 2));
+        assert!(!original.objects_added.is_empty());
+        assert!(original.objects_modified.is_empty());
+
+        assert!(update.objects_added.contains(&reference(signature_id)));
+        assert!(update.objects_added.contains(&reference(field_id)));
+        assert_eq!(update.objects_modified, vec![reference(catalog_id)]);
+        assert!(update.objects_removed.is_empty());
+
+        // The signature covers the original bytes only
+        assert!(update.signatures.is_empty());
+        assert_eq!(original.signatures.len(), 1);
+        let signature = &original.signatures[0];
+        assert_eq!(signature.field_name.as_deref(), Some("Approval"));
+        assert_eq!(signature.signer.as_deref(), Some("Jane Doe"));
+        assert_eq!(
+            signature.signing_time.as_deref(),
+            Some("2025-01-31T09:30:00+01:00")
+        );
+        assert_eq!(signature.signed_revision, 1);
+        assert!(signature.ends_at_revision);
+
+        assert_eq!(extract_revision(&signed_path, 1, &output).unwrap(), 2);
+        let extracted = std::fs::read(&output).unwrap();
+        assert_eq!(extracted.len() as u64, original.end_offset);
+        assert!(base.starts_with(&extracted) || extracted.starts_with(&base));
+        assert!(Document::load(&output)
+            .unwrap()
+            .get_dictionary(catalog_id)
+            .unwrap()
+            .get(b"AcroForm")
+            .is_err());
+        assert!(extract_revision(&signed_path, 3, &output).is_err());
+    }
+
+    #[test]
+    fn test_eof_inside_a_stream_is_not_a_revision() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("eof.pdf").to_string_lossy().to_string();
+        let mut document = create_test_pdf(1, "Revisions", "text").unwrap();
+        // Embedded data that happens to contain the end of another PDF
+        document.add_object(lopdf::Stream::new(
+            Dictionary::new(),
+            b"%%EOF\nstartxref\n0\n%%EOF\n".to_vec(),
+        ));
+        document.save(&path).unwrap();
+
+        let revisions = list_revisions(&path).unwrap();
+        assert_eq!(revisions.len(), 1);
+        assert_eq!(
+            revisions[0].end_offset,
+            std::fs::metadata(&path).unwrap().len()
+        );
+    }
+}
@@ -0,0 +1,277 @@
+use lopdf::{Document, Object, ObjectId};
+use napi_derive::napi;
+
+use crate::page_range::select_pages;
+use crate::sign::{inherited_page_attribute, page_rect, transform_page_content};
+
+/// Boundary boxes that are carried through a normalizing transform.
+pub(crate) const PAGE_BOXES: [&[u8]; 5] =
+    [b"MediaBox", b"CropBox", b"BleedBox", b"TrimBox", b"ArtBox"];
+
+#[derive(Debug, Clone, Default)]
+#[napi(object)]
+pub struct RotateOptions {
+    pub page_range: Option<String>, // Pages to rotate (default: all)
+    pub mode: Option<String>,       // "add" (default) turns pages further, "set" replaces /Rotate
+}
+
+/// Returns the effective `/Rotate` of a page, normalized to 0, 90, 180 or 270.
+pub(crate) fn page_rotation(document: &Document, page_id: ObjectId) -> i64 {
+    inherited_page_attribute(document, page_id, b"Rotate")
+        .and_then(|rotate| rotate.as_i64().ok())
+        .map(|rotate| rotate.rem_euclid(360) / 90 * 90)
+        .unwrap_or(0)
+}
+
+/// Rotates pages clockwise by `angle` degrees, or sets their rotation with
+/// `mode: "set"`.
+///
+/// # Returns
+/// The number of pages rotated.
+pub fn rotate_pages(
+    input_path: &str,
+    output_path: &str,
+    angle: i32,
+    options: &RotateOptions,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    if angle % 90 != 0 {
+        return Err(format!("Rotation must be a multiple of 90 degrees, got {angle}").into());
+    }
+    let set = match options.mode.as_deref().unwrap_or("add") {
+        "add" => false,
+        "set" => true,
+        other => return Err(format!("Unsupported rotation mode: '{other}'").into()),
+    };
+
+    let mut document = Document::load(input_path)?;
+    let page_ids = select_pages(&document, options.page_range.as_deref(), None)?;
+    for page_id in &page_ids {
+        let current = if set {
+            0
+        } else {
+            page_rotation(&document, *page_id)
+        };
+        let rotation = (current + angle as i64).rem_euclid(360);
+        document
+            .get_dictionary_mut(*page_id)?
+            .set("Rotate", Object::Integer(rotation));
+    }
+
+    document.save(output_path)?;
+    Ok(page_ids.len() as u32)
+}
+
+/// Bakes each page's `/Rotate` into its content and resets it to 0, so the
+/// page displays the same but its coordinate system is upright.
+///
+/// The content is wrapped in a `cm` transform and the page boxes and
+/// annotation rectangles are mapped into the new coordinates.
+///
+/// # Returns
+/// The number of pages that were rotated.
+pub fn normalize_rotation(
+    input_path: &str,
+    output_path: &str,
+    page_range: Option<&str>,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    let mut document = Document::load(input_path)?;
+    let page_ids = select_pages(&document, page_range, None)?;
+
+    let mut normalized = 0;
+    for page_id in page_ids {
+        if normalize_page(&mut document, page_id)? {
+            normalized += 1;
+        }
+    }
+
+    document.save(output_path)?;
+    Ok(normalized)
+}
+
+/// Normalizes one page in place; returns `false` if it was already upright.
+pub(crate) fn normalize_page(document: &mut Document, page_id: ObjectId) -> lopdf::Result<bool> {
+    let rotation = page_rotation(document, page_id);
+    if rotation == 0 {
+        return Ok(false);
+    }
+
+    let [llx, lly, urx, ury] =
+        page_rect(document, page_id, b"MediaBox").unwrap_or([0.0, 0.0, 612.0, 792.0]);
+    let (width, height) = (urx - llx, ury - lly);
+    // Maps the MediaBox onto [0 0 w' h'] as the page is shown when rotated clockwise
+    let matrix = match rotation {
+        90 => [0.0, -1.0, 1.0, 0.0, -lly, width + llx],
+        180 => [-1.0, 0.0, 0.0, -1.0, width + llx, height + lly],
+        _ => [0.0, 1.0, -1.0, 0.0, height + lly, -llx],
+    };
+
+    let boxes: Vec<(&[u8], [f64; 4])> = PAGE_BOXES
+        .iter()
+        .filter_map(|key| Some((*key, page_rect(document, page_id, key)?)))
+        .collect();
+    transform_page_content(document, page_id, matrix)?;
+    transform_annotation_rects(document, page_id, matrix)?;
+
+    let page = document.get_dictionary_mut(page_id)?;
+    for (key, rect) in boxes {
+        page.set(key, rect_object(transform_rect(rect, matrix)));
+    }
+    page.set("Rotate", Object::Integer(0));
+    Ok(true)
+}
+
+/// Maps the `/Rect` of every annotation on a page through `matrix`.
+pub(crate) fn transform_annotation_rects(
+    document: &mut Document,
+    page_id: ObjectId,
+    matrix: [f64; 6],
+) -> lopdf::Result<()> {
+    let annotations: Vec<ObjectId> = match document.get_dictionary(page_id)?.get(b"Annots") {
+        Ok(annots) => document
+            .dereference(annots)?
+            .1
+            .as_array()?
+            .iter()
+            .filter_map(|annot| annot.as_reference().ok())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    for annot_id in annotations {
+        let Ok(annot) = document.get_dictionary_mut(annot_id) else {
+            continue;
+        };
+        let rect = annot
+            .get(b"Rect")
+            .and_then(Object::as_array)
+            .ok()
+            .and_then(|values| {
+                let values: Vec<f64> = values
+                    .iter()
+                    .filter_map(|v| v.as_float().ok())
+                    .map(f64::from)
+                    .collect();
+                <[f64; 4]>::try_from(values).ok()
+            });
+        if let Some(rect) = rect {
+            annot.set("Rect", rect_object(transform_rect(rect, matrix)));
+        }
+    }
+    Ok(())
+}
+
+/// Applies a PDF transformation matrix to a rectangle and returns its
+/// bounding box.
+pub(crate) fn transform_rect(rect: [f64; 4], [a, b, c, d, e, f]: [f64; 6]) -> [f64; 4] {
+    let corners = [
+        (rect[0], rect[1]),
+        (rect[2], rect[3]),
+        (rect[0], rect[3]),
+        (rect[2], rect[1]),
+    ];
+    let points = corners.map(|(x, y)| (a * x + c * y + e, b * x + d * y + f));
+    let xs = points.map(|(x, _)| x);
+    let ys = points.map(|(_, y)| y);
+    [
+        xs.iter().copied().fold(f64::INFINITY, f64::min),
+        ys.iter().copied().fold(f64::INFINITY, f64::min),
+        xs.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        ys.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+    ]
+}
+
+pub(crate) fn rect_object(rect: [f64; 4]) -> Object {
+    Object::Array(
+        rect.iter()
+            .map(|value| Object::Real(*value as f32))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_pdf;
+    use tempfile::TempDir;
+
+    fn save_input(dir: &TempDir, pages: u32) -> String {
+        let path = dir.path().join("input.pdf").to_string_lossy().to_string();
+        create_test_pdf(pages, "Rotate", "text")
+            .unwrap()
+            .save(&path)
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn test_add_and_set_rotation() {
+        let dir = TempDir::new().unwrap();
+        let input = save_input(&dir, 3);
+        let output = dir.path().join("rotated.pdf").to_string_lossy().to_string();
+
+        let options = RotateOptions {
+            page_range: Some("1-2".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(rotate_pages(&input, &output, 90, &options).unwrap(), 2);
+        assert_eq!(rotate_pages(&output, &output, -180, &options).unwrap(), 2);
+        let set = RotateOptions {
+            page_range: Some("2".to_string()),
+            mode: Some("set".to_string()),
+        };
+        rotate_pages(&output, &output, 180, &set).unwrap();
+
+        let document = Document::load(&output).unwrap();
+        let rotations: Vec<i64> = document
+            .get_pages()
+            .values()
+            .map(|id| page_rotation(&document, *id))
+            .collect();
+        assert_eq!(rotations, vec![270, 180, 0]);
+        assert!(rotate_pages(&input, &output, 45, &RotateOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_transform_rect() {
+        // A Letter page turned clockwise becomes a landscape page at the origin
+        let matrix = [0.0, -1.0, 1.0, 0.0, 0.0, 612.0];
+        assert_eq!(
+            transform_rect([0.0, 0.0, 612.0, 792.0], matrix),
+            [0.0, 0.0, 792.0, 612.0]
+        );
+        assert_eq!(
+            transform_rect([10.0, 20.0, 30.0, 40.0], matrix),
+            [20.0, 582.0, 40.0, 602.0]
+        );
+    }
+
+    #[test]
+    fn test_normalize_rotation() {
+        let dir = TempDir::new().unwrap();
+        let input = save_input(&dir, 2);
+        let rotated = dir.path().join("rotated.pdf").to_string_lossy().to_string();
+        let output = dir
+            .path()
+            .join("normalized.pdf")
+            .to_string_lossy()
+            .to_string();
+        let options = RotateOptions {
+            page_range: Some("1".to_string()),
+            ..Default::default()
+        };
+        rotate_pages(&input, &rotated, 90, &options).unwrap();
+
+        assert_eq!(normalize_rotation(&rotated, &output, None).unwrap(), 1);
+
+        let document = Document::load(&output).unwrap();
+        let page_id = document.get_pages()[&1];
+        assert_eq!(page_rotation(&document, page_id), 0);
+        assert_eq!(
+            page_rect(&document, page_id, b"MediaBox"),
+            Some([0.0, 0.0, 792.0, 612.0])
+        );
+        let content =
+            String::from_utf8_lossy(&document.get_page_content(page_id).unwrap()).to_string();
+        assert!(content.starts_with("q\n0 -1 1 0 0 612 cm"));
+        assert!(content.trim_end().ends_with('Q'));
+    }
+}
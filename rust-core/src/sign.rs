@@ -4,6 +4,7 @@ use napi_derive::napi;
 
 // Import the page count function from the page-count module
 use crate::page_count::get_page_count;
+use crate::{info, layers, merge, page_range, watermark};
 
 #[derive(Debug)]
 #[napi(object)]
@@ -13,9 +14,287 @@ pub struct SigningOptions {
     pub x_position: Option<f64>,
     pub y_position: Option<f64>,
     pub pages: Option<Vec<u32>>,
+    pub page_range: Option<String>, // e.g. "1-3,7,10-", "odd", "last", "r1", "!2"
     pub position: Option<String>, // "top-left", "top-right", "bottom-left", "bottom-right", "center"
     pub rotation: Option<f64>,
     pub opacity: Option<f64>,
+    pub layout: Option<String>, // "label" (default), "diagonal", "tile"
+    pub tile_spacing: Option<f64>,
+    pub placement: Option<String>, // "overlay" (default), "underlay"
+    pub layer_name: Option<String>,
+    pub layer_visibility: Option<String>, // "always" (default), "print", "screen"
+}
+
+/// Appends a content stream to the end of a page's `/Contents`, so it is drawn
+/// over the existing page content.
+pub(crate) fn append_page_content(
+    document: &mut lopdf::Document,
+    page_id: lopdf::ObjectId,
+    content_id: lopdf::ObjectId,
+) {
+    insert_page_content(document, page_id, content_id, false);
+}
+
+/// Prepends a content stream to the start of a page's `/Contents`, so the
+/// existing page content is drawn over it.
+pub(crate) fn prepend_page_content(
+    document: &mut lopdf::Document,
+    page_id: lopdf::ObjectId,
+    content_id: lopdf::ObjectId,
+) {
+    insert_page_content(document, page_id, content_id, true);
+}
+
+/// Wraps a page's existing content in `q <matrix> cm ... Q`, so everything
+/// already on the page is drawn through the transformation.
+pub(crate) fn transform_page_content(
+    document: &mut lopdf::Document,
+    page_id: lopdf::ObjectId,
+    matrix: [f64; 6],
+) -> lopdf::Result<()> {
+    let prefix = lopdf::content::Content {
+        operations: vec![
+            lopdf::content::Operation::new("q", vec![]),
+            lopdf::content::Operation::new("cm", watermark::matrix_operands(matrix)),
+        ],
+    };
+    let suffix = lopdf::content::Content {
+        operations: vec![lopdf::content::Operation::new("Q", vec![])],
+    };
+    // Content streams are concatenated, so the prefix must end on a token boundary
+    let mut prefix_data = prefix.encode()?;
+    prefix_data.push(b'\n');
+    let prefix_id = document.add_object(lopdf::Stream::new(lopdf::Dictionary::new(), prefix_data));
+    let suffix_id = document.add_object(lopdf::Stream::new(
+        lopdf::Dictionary::new(),
+        suffix.encode()?,
+    ));
+    prepend_page_content(document, page_id, prefix_id);
+    append_page_content(document, page_id, suffix_id);
+    Ok(())
+}
+
+fn insert_page_content(
+    document: &mut lopdf::Document,
+    page_id: lopdf::ObjectId,
+    content_id: lopdf::ObjectId,
+    at_start: bool,
+) {
+    if let Ok(lopdf::Object::Dictionary(page_dict)) = document.get_object_mut(page_id) {
+        let mut contents = match page_dict.get(b"Contents") {
+            Ok(lopdf::Object::Array(contents)) => contents.clone(),
+            // If Contents is a single reference, convert it to an array
+            Ok(old_contents @ lopdf::Object::Reference(_)) => vec![old_contents.clone()],
+            // If no Contents, create a new array
+            _ => Vec::new(),
+        };
+        if at_start {
+            contents.insert(0, lopdf::Object::Reference(content_id));
+        } else {
+            contents.push(lopdf::Object::Reference(content_id));
+        }
+        page_dict.set("Contents", lopdf::Object::Array(contents));
+    }
+}
+
+/// Looks up a page attribute, following the `/Parent` chain for inheritable
+/// keys such as `/MediaBox`, `/CropBox`, `/Resources` and `/Rotate`.
+pub(crate) fn inherited_page_attribute(
+    document: &lopdf::Document,
+    page_id: lopdf::ObjectId,
+    key: &[u8],
+) -> Option<lopdf::Object> {
+    let mut node_id = page_id;
+    // Guard against cyclic /Parent links in malformed files
+    for _ in 0..64 {
+        let node = document.get_dictionary(node_id).ok()?;
+        if let Ok(value) = node.get(key) {
+            return match value {
+                lopdf::Object::Reference(id) => document.get_object(*id).ok().cloned(),
+                other => Some(other.clone()),
+            };
+        }
+        node_id = node
+            .get(b"Parent")
+            .and_then(lopdf::Object::as_reference)
+            .ok()?;
+    }
+    None
+}
+
+/// Reads a page boundary box (`/MediaBox`, `/CropBox`, ...) as
+/// `[llx, lly, urx, ury]`, following inheritance.
+pub(crate) fn page_rect(
+    document: &lopdf::Document,
+    page_id: lopdf::ObjectId,
+    key: &[u8],
+) -> Option<[f64; 4]> {
+    let values = inherited_page_attribute(document, page_id, key)?;
+    let values = values.as_array().ok()?;
+    if values.len() != 4 {
+        return None;
+    }
+    let mut rect = [0.0; 4];
+    for (slot, value) in rect.iter_mut().zip(values) {
+        *slot = value.as_float().ok()? as f64;
+    }
+    // Normalize so the lower-left corner comes first
+    Some([
+        rect[0].min(rect[2]),
+        rect[1].min(rect[3]),
+        rect[0].max(rect[2]),
+        rect[1].max(rect[3]),
+    ])
+}
+
+/// Returns the visible area of a page as `[llx, lly, urx, ury]`, using the
+/// CropBox when present and falling back to the MediaBox (or US Letter).
+pub(crate) fn page_box(document: &lopdf::Document, page_id: lopdf::ObjectId) -> [f64; 4] {
+    page_rect(document, page_id, b"CropBox")
+        .or_else(|| page_rect(document, page_id, b"MediaBox"))
+        .unwrap_or([0.0, 0.0, 612.0, 792.0])
+}
+
+/// Registers a named resource (font, graphics state, XObject, ...) on a page.
+///
+/// Inherited or shared `/Resources` dictionaries are copied onto the page
+/// first, so other pages are left untouched.
+pub(crate) fn add_page_resource(
+    document: &mut lopdf::Document,
+    page_id: lopdf::ObjectId,
+    category: &str,
+    name: &str,
+    value: lopdf::Object,
+) -> lopdf::Result<()> {
+    let mut resources = match inherited_page_attribute(document, page_id, b"Resources") {
+        Some(lopdf::Object::Dictionary(dict)) => dict,
+        _ => lopdf::Dictionary::new(),
+    };
+
+    let mut entries = match resources.get(category.as_bytes()) {
+        Ok(lopdf::Object::Dictionary(dict)) => dict.clone(),
+        Ok(lopdf::Object::Reference(id)) => document.get_dictionary(*id)?.clone(),
+        _ => lopdf::Dictionary::new(),
+    };
+    entries.set(name, value);
+    resources.set(category, lopdf::Object::Dictionary(entries));
+
+    document
+        .get_dictionary_mut(page_id)?
+        .set("Resources", lopdf::Object::Dictionary(resources));
+    Ok(())
+}
+
+/// Collects the resource dictionaries a page draws with: its own (possibly
+/// inherited) `/Resources`, those of the Form XObjects it uses, however
+/// deeply nested, and those of its annotations' appearance streams.
+pub(crate) fn page_resource_dictionaries(
+    document: &lopdf::Document,
+    page_id: lopdf::ObjectId,
+) -> Vec<lopdf::Dictionary> {
+    let mut pending = Vec::new();
+    if let Some(lopdf::Object::Dictionary(resources)) =
+        inherited_page_attribute(document, page_id, b"Resources")
+    {
+        pending.push(resources);
+    }
+
+    // Appearance streams are either direct (/N stream) or keyed by state
+    let mut forms: Vec<lopdf::ObjectId> = Vec::new();
+    for annot in info::page_annotations(document, page_id) {
+        let Some(appearances) = merge::resolve_dictionary(document, annot.get(b"AP").ok()) else {
+            continue;
+        };
+        for (_, appearance) in appearances.iter() {
+            match appearance {
+                lopdf::Object::Reference(id) => match document.get_object(*id) {
+                    Ok(lopdf::Object::Dictionary(states)) => forms.extend(
+                        states
+                            .iter()
+                            .filter_map(|(_, state)| state.as_reference().ok()),
+                    ),
+                    Ok(_) => forms.push(*id),
+                    Err(_) => {}
+                },
+                lopdf::Object::Dictionary(states) => forms.extend(
+                    states
+                        .iter()
+                        .filter_map(|(_, state)| state.as_reference().ok()),
+                ),
+                _ => {}
+            }
+        }
+    }
+
+    let mut visited = std::collections::HashSet::new();
+    let mut dictionaries = Vec::new();
+    loop {
+        if let Some(resources) = pending.pop() {
+            if let Some(xobjects) =
+                merge::resolve_dictionary(document, resources.get(b"XObject").ok())
+            {
+                forms.extend(
+                    xobjects
+                        .iter()
+                        .filter_map(|(_, xobject)| xobject.as_reference().ok()),
+                );
+            }
+            dictionaries.push(resources);
+        } else if let Some(form_id) = forms.pop() {
+            // Shared forms are visited once; this also breaks cycles
+            if !visited.insert(form_id) {
+                continue;
+            }
+            let Ok(form) = document
+                .get_object(form_id)
+                .and_then(lopdf::Object::as_stream)
+            else {
+                continue;
+            };
+            if form
+                .dict
+                .get(b"Subtype")
+                .and_then(lopdf::Object::as_name)
+                .ok()
+                != Some(b"Form")
+            {
+                continue;
+            }
+            if let Some(resources) =
+                merge::resolve_dictionary(document, form.dict.get(b"Resources").ok())
+            {
+                pending.push(resources.clone());
+            }
+        } else {
+            return dictionaries;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+#[napi(object)]
+pub struct SanitizeOptions {
+    pub remove_metadata: Option<bool>, // Info dictionary and XMP (default: true)
+    pub remove_javascript: Option<bool>, // Document JavaScript, OpenAction and JavaScript actions (default: true)
+    pub remove_attachments: Option<bool>, // Embedded files and file attachment annotations (default: true)
+    pub remove_hidden_layers: Option<bool>, // Optional content groups that are off by default (default: true)
+    pub remove_piece_info: Option<bool>, // Private application data in /PieceInfo (default: true)
+    pub annotation_types: Option<Vec<String>>, // Annotation subtypes to remove, e.g. ["Text", "Highlight"]
+}
+
+/// What [`sanitize`] removed.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct SanitizeReport {
+    pub output_file: String,
+    pub info_removed: bool,
+    pub xmp_removed: bool, // From the catalog or any page, image, font or form
+    pub javascript_removed: u32, // Name-tree scripts and JavaScript actions
+    pub open_action_removed: bool,
+    pub attachments_removed: u32,
+    pub layers_removed: Vec<String>,
+    pub annotations_removed: u32,
+    pub piece_info_removed: u32,
+    pub objects_pruned: u32,
 }
 
 /// Gets page count information for a PDF before signing
@@ -193,9 +472,15 @@ pub fn sign_pdf_with_options(
         x_position: None,
         y_position: None,
         pages: None,
+        page_range: None,
         position: Some("bottom-right".to_string()),
         rotation: Some(0.0),
         opacity: Some(1.0),
+        layout: None,
+        tile_spacing: None,
+        placement: None,
+        layer_name: None,
+        layer_visibility: None,
     });
 
     // Load the PDF document
@@ -215,22 +500,15 @@ pub fn sign_pdf_with_options(
         ));
     }
 
-    // Determine which pages to sign
-    let target_pages: Vec<lopdf::ObjectId> = if let Some(page_nums) = &opts.pages {
-        // Get all pages as ordered list
-        let page_ids: Vec<lopdf::ObjectId> = pages.values().copied().collect();
-        let mut filtered_pages = Vec::new();
-
-        for page_num in page_nums {
-            if *page_num > 0 && (*page_num as usize) <= page_ids.len() {
-                filtered_pages.push(page_ids[(*page_num - 1) as usize]);
-            }
-        }
-
-        filtered_pages
-    } else {
-        pages.values().copied().collect()
-    };
+    // Determine which pages to sign, in document order
+    let target_pages =
+        page_range::select_pages(&document, opts.page_range.as_deref(), opts.pages.as_deref())
+            .map_err(|e| {
+                napi::Error::new(
+                    napi::Status::GenericFailure,
+                    format!("Invalid page selection: {e}"),
+                )
+            })?;
 
     // Get positioning values
     let font_size = opts.font_size.unwrap_or(12.0);
@@ -245,35 +523,87 @@ pub fn sign_pdf_with_options(
         ), // default bottom-right
     };
 
+    let underlay = opts.placement.as_deref() == Some("underlay");
+
+    // Put the stamp into a named optional content group when requested
+    let layer_id = match opts.layer_name.as_deref() {
+        Some(name) => Some(
+            layers::ensure_layer(&mut document, name, opts.layer_visibility.as_deref()).map_err(
+                |e| {
+                    napi::Error::new(
+                        napi::Status::GenericFailure,
+                        format!("Failed to create layer: {e}"),
+                    )
+                },
+            )?,
+        ),
+        None => None,
+    };
+    let in_layer = |document: &mut lopdf::Document,
+                    page_id: lopdf::ObjectId,
+                    operations: Vec<lopdf::content::Operation>| {
+        match layer_id {
+            Some(layer_id) => layers::wrap_in_layer(document, page_id, layer_id, operations),
+            None => Ok(operations),
+        }
+        .map_err(|e| {
+            napi::Error::new(
+                napi::Status::GenericFailure,
+                format!("Failed to add layer content: {e}"),
+            )
+        })
+    };
+
     // Sign each target page
     for page_id in target_pages {
+        // Full-page layouts build their own content and resources
+        if let Some(layout @ ("diagonal" | "tile")) = opts.layout.as_deref() {
+            let operations = match layout {
+                "diagonal" => {
+                    watermark::diagonal_operations(&document, page_id, &signature_text, &opts)
+                }
+                _ => watermark::tile_operations(&document, page_id, &signature_text, &opts),
+            };
+            let operations = in_layer(&mut document, page_id, operations)?;
+            watermark::stamp_page(&mut document, page_id, operations, &opts, underlay).map_err(
+                |e| {
+                    napi::Error::new(
+                        napi::Status::GenericFailure,
+                        format!("Failed to stamp watermark: {e}"),
+                    )
+                },
+            )?;
+            continue;
+        }
+
         // Create text content for this page
+        let operations = vec![
+            lopdf::content::Operation::new("BT", vec![]),
+            lopdf::content::Operation::new(
+                "Tf",
+                vec![
+                    lopdf::Object::Name(b"Helvetica".to_vec()),
+                    lopdf::Object::Real(font_size as f32),
+                ],
+            ),
+            lopdf::content::Operation::new(
+                "Td",
+                vec![
+                    lopdf::Object::Real(x_pos as f32),
+                    lopdf::Object::Real(y_pos as f32),
+                ],
+            ),
+            lopdf::content::Operation::new(
+                "Tj",
+                vec![lopdf::Object::String(
+                    signature_text.clone().into_bytes(),
+                    lopdf::StringFormat::Literal,
+                )],
+            ),
+            lopdf::content::Operation::new("ET", vec![]),
+        ];
         let text_object = lopdf::content::Content {
-            operations: vec![
-                lopdf::content::Operation::new("BT", vec![]),
-                lopdf::content::Operation::new(
-                    "Tf",
-                    vec![
-                        lopdf::Object::Name(b"Helvetica".to_vec()),
-                        lopdf::Object::Real(font_size as f32),
-                    ],
-                ),
-                lopdf::content::Operation::new(
-                    "Td",
-                    vec![
-                        lopdf::Object::Real(x_pos as f32),
-                        lopdf::Object::Real(y_pos as f32),
-                    ],
-                ),
-                lopdf::content::Operation::new(
-                    "Tj",
-                    vec![lopdf::Object::String(
-                        signature_text.clone().into_bytes(),
-                        lopdf::StringFormat::Literal,
-                    )],
-                ),
-                lopdf::content::Operation::new("ET", vec![]),
-            ],
+            operations: in_layer(&mut document, page_id, operations)?,
         };
 
         // Add content to page
@@ -289,28 +619,10 @@ pub fn sign_pdf_with_options(
         let content_id = document.add_object(content_stream);
 
         // Add to page contents
-        if let Ok(lopdf::Object::Dictionary(page_dict)) = document.get_object_mut(page_id) {
-            match page_dict.get_mut(b"Contents") {
-                Ok(lopdf::Object::Array(contents)) => {
-                    contents.push(lopdf::Object::Reference(content_id));
-                }
-                Ok(lopdf::Object::Reference(_)) => {
-                    let old_contents = page_dict.get(b"Contents").unwrap().clone();
-                    page_dict.set(
-                        "Contents",
-                        lopdf::Object::Array(vec![
-                            old_contents,
-                            lopdf::Object::Reference(content_id),
-                        ]),
-                    );
-                }
-                _ => {
-                    page_dict.set(
-                        "Contents",
-                        lopdf::Object::Array(vec![lopdf::Object::Reference(content_id)]),
-                    );
-                }
-            }
+        if underlay {
+            prepend_page_content(&mut document, page_id, content_id);
+        } else {
+            append_page_content(&mut document, page_id, content_id);
         }
     }
 
@@ -438,6 +750,270 @@ pub fn sign_pdf_legacy(file_path: String, signature_text: String) -> Result<()>
     Ok(())
 }
 
+/// Strips metadata and hidden content from a PDF before it is shared, and
+/// writes the result to `output_path`.
+///
+/// Everything is removed by default except annotations, which are only
+/// removed for the subtypes listed in `annotation_types`. Objects left
+/// unreferenced afterwards are dropped.
+pub fn sanitize(
+    input_path: &str,
+    output_path: &str,
+    options: &SanitizeOptions,
+) -> std::result::Result<SanitizeReport, Box<dyn std::error::Error>> {
+    let mut document = lopdf::Document::load(input_path)?;
+    let mut report = SanitizeReport {
+        output_file: output_path.to_string(),
+        ..Default::default()
+    };
+
+    if options.remove_hidden_layers.unwrap_or(true) {
+        let hidden = hidden_layers(&document);
+        report.layers_removed = hidden
+            .iter()
+            .filter_map(|id| document.get_dictionary(*id).ok())
+            .filter_map(|ocg| ocg.get(b"Name").ok())
+            .filter_map(|name| lopdf::decode_text_string(name).ok())
+            .collect();
+        if !hidden.is_empty() {
+            layers::remove_layers(&mut document, &hidden)?;
+        }
+    }
+
+    let mut removed_annotations = std::collections::HashSet::new();
+    let remove_attachments = options.remove_attachments.unwrap_or(true);
+    let subtypes: Vec<&[u8]> = options
+        .annotation_types
+        .iter()
+        .flatten()
+        .map(|subtype| subtype.as_bytes())
+        .chain(remove_attachments.then_some(b"FileAttachment".as_slice()))
+        .collect();
+    if !subtypes.is_empty() {
+        for page_id in document.get_pages().into_values() {
+            let annots = match document.get_dictionary(page_id)?.get(b"Annots") {
+                Ok(annots) => document.dereference(annots)?.1.as_array()?.clone(),
+                Err(_) => continue,
+            };
+            let remove: Vec<lopdf::ObjectId> = annots
+                .iter()
+                .filter_map(|annot| annot.as_reference().ok())
+                .filter(|id| {
+                    document
+                        .get_dictionary(*id)
+                        .and_then(|annot| annot.get(b"Subtype"))
+                        .and_then(lopdf::Object::as_name)
+                        .is_ok_and(|subtype| subtypes.contains(&subtype))
+                })
+                .collect();
+            if remove.is_empty() {
+                continue;
+            }
+            for id in &remove {
+                let subtype = document
+                    .get_dictionary(*id)?
+                    .get(b"Subtype")
+                    .and_then(lopdf::Object::as_name)?;
+                if subtype == b"FileAttachment" && remove_attachments {
+                    report.attachments_removed += 1;
+                } else {
+                    report.annotations_removed += 1;
+                }
+                removed_annotations.insert(*id);
+            }
+            // Popups belong to the annotation they were opened from
+            let kept: Vec<lopdf::Object> = annots
+                .into_iter()
+                .filter(|annot| {
+                    let Ok(id) = annot.as_reference() else {
+                        return true;
+                    };
+                    let parent = document
+                        .get_dictionary(id)
+                        .and_then(|annot| annot.get(b"Parent"))
+                        .and_then(lopdf::Object::as_reference);
+                    !removed_annotations.contains(&id)
+                        && !parent.is_ok_and(|parent| removed_annotations.contains(&parent))
+                })
+                .collect();
+            document
+                .get_dictionary_mut(page_id)?
+                .set("Annots", lopdf::Object::Array(kept));
+        }
+    }
+
+    let catalog_id = document
+        .trailer
+        .get(b"Root")
+        .and_then(lopdf::Object::as_reference)?;
+    let mut catalog = document.catalog()?.clone();
+    let mut names = merge::resolve_dictionary(&document, catalog.get(b"Names").ok()).cloned();
+
+    if options.remove_metadata.unwrap_or(true) {
+        report.info_removed = document.trailer.remove(b"Info").is_some();
+        report.xmp_removed = catalog.remove(b"Metadata").is_some();
+        // Pages, images, fonts and forms may carry their own XMP
+        for (id, object) in document.objects.iter_mut() {
+            let dict = match object {
+                lopdf::Object::Dictionary(dict) if *id != catalog_id => dict,
+                lopdf::Object::Stream(stream) => &mut stream.dict,
+                _ => continue,
+            };
+            report.xmp_removed |= dict.remove(b"Metadata").is_some();
+        }
+    }
+    if remove_attachments {
+        if let Some(names) = names.as_mut() {
+            if let Some(tree) =
+                merge::resolve_dictionary(&document, names.get(b"EmbeddedFiles").ok())
+            {
+                report.attachments_removed +=
+                    merge::name_tree_entries(&document, tree).len() as u32;
+            }
+            names.remove(b"EmbeddedFiles");
+        }
+        catalog.remove(b"AF");
+    }
+    if options.remove_javascript.unwrap_or(true) {
+        if let Some(names) = names.as_mut() {
+            if let Some(tree) = merge::resolve_dictionary(&document, names.get(b"JavaScript").ok())
+            {
+                report.javascript_removed += merge::name_tree_entries(&document, tree).len() as u32;
+            }
+            names.remove(b"JavaScript");
+        }
+        report.open_action_removed = catalog.remove(b"OpenAction").is_some();
+
+        let scripts: std::collections::HashSet<lopdf::ObjectId> = document
+            .objects
+            .iter()
+            .filter(|(_, object)| object.as_dict().is_ok_and(is_javascript_action))
+            .map(|(id, _)| *id)
+            .collect();
+        for (id, object) in document.objects.iter_mut() {
+            // The catalog is handled through its working copy
+            if *id != catalog_id {
+                report.javascript_removed += strip_javascript(object, &scripts);
+            }
+        }
+        report.javascript_removed += strip_javascript_dict(&mut catalog, &scripts);
+    }
+    if let Some(names) = names {
+        catalog.set("Names", lopdf::Object::Dictionary(names));
+    }
+    if options.remove_piece_info.unwrap_or(true) {
+        report.piece_info_removed += u32::from(catalog.remove(b"PieceInfo").is_some());
+        for (id, object) in document.objects.iter_mut() {
+            let dict = match object {
+                lopdf::Object::Dictionary(dict) if *id != catalog_id => dict,
+                lopdf::Object::Stream(stream) => &mut stream.dict,
+                _ => continue,
+            };
+            report.piece_info_removed += u32::from(dict.remove(b"PieceInfo").is_some());
+        }
+    }
+    document
+        .objects
+        .insert(catalog_id, lopdf::Object::Dictionary(catalog));
+
+    report.objects_pruned = document.prune_objects().len() as u32;
+    document.save(output_path)?;
+    Ok(report)
+}
+
+/// Returns the optional content groups that are hidden in the default
+/// configuration.
+fn hidden_layers(document: &lopdf::Document) -> Vec<lopdf::ObjectId> {
+    let properties = layers::oc_properties(document);
+    let ids = |object: Option<&lopdf::Object>| -> Vec<lopdf::ObjectId> {
+        merge::resolve_array(document, object)
+            .map(|array| {
+                array
+                    .iter()
+                    .filter_map(|id| id.as_reference().ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let all = ids(properties.get(b"OCGs").ok());
+    let Some(config) = merge::resolve_dictionary(document, properties.get(b"D").ok()) else {
+        return Vec::new();
+    };
+    let hidden: Vec<lopdf::ObjectId> = if config
+        .get(b"BaseState")
+        .and_then(lopdf::Object::as_name)
+        .ok()
+        == Some(b"OFF")
+    {
+        let on = ids(config.get(b"ON").ok());
+        all.into_iter().filter(|id| !on.contains(id)).collect()
+    } else {
+        let off = ids(config.get(b"OFF").ok());
+        all.into_iter().filter(|id| off.contains(id)).collect()
+    };
+
+    // Print-only layers (such as print watermarks) are off on screen but are
+    // still part of the document as it is printed
+    hidden
+        .into_iter()
+        .filter(|id| {
+            let print_state = document
+                .get_dictionary(*id)
+                .and_then(|ocg| ocg.get(b"Usage"))
+                .and_then(lopdf::Object::as_dict)
+                .and_then(|usage| usage.get(b"Print"))
+                .and_then(lopdf::Object::as_dict)
+                .and_then(|print| print.get(b"PrintState"))
+                .and_then(lopdf::Object::as_name);
+            print_state.ok() != Some(b"ON")
+        })
+        .collect()
+}
+
+fn is_javascript_action(dict: &lopdf::Dictionary) -> bool {
+    dict.get(b"S").and_then(lopdf::Object::as_name).ok() == Some(b"JavaScript")
+}
+
+/// Removes additional-actions (`/AA`) and JavaScript actions from an object
+/// and the dictionaries nested in it; returns how many were removed.
+fn strip_javascript(
+    object: &mut lopdf::Object,
+    scripts: &std::collections::HashSet<lopdf::ObjectId>,
+) -> u32 {
+    match object {
+        lopdf::Object::Dictionary(dict) => strip_javascript_dict(dict, scripts),
+        lopdf::Object::Stream(stream) => strip_javascript_dict(&mut stream.dict, scripts),
+        lopdf::Object::Array(items) => items
+            .iter_mut()
+            .map(|item| strip_javascript(item, scripts))
+            .sum(),
+        _ => 0,
+    }
+}
+
+fn strip_javascript_dict(
+    dict: &mut lopdf::Dictionary,
+    scripts: &std::collections::HashSet<lopdf::ObjectId>,
+) -> u32 {
+    let mut removed = u32::from(dict.remove(b"AA").is_some());
+    for key in [b"A".as_slice(), b"OpenAction", b"Next"] {
+        let is_script = match dict.get(key) {
+            Ok(lopdf::Object::Reference(id)) => scripts.contains(id),
+            Ok(lopdf::Object::Dictionary(action)) => is_javascript_action(action),
+            _ => false,
+        };
+        if is_script {
+            dict.remove(key);
+            removed += 1;
+        }
+    }
+    removed
+        + dict
+            .iter_mut()
+            .map(|(_, value)| strip_javascript(value, scripts))
+            .sum::<u32>()
+}
+
 // Internal versions for testing
 pub fn get_pdf_info_before_signing_internal(
     file_path: String,
@@ -500,3 +1076,284 @@ pub fn sign_pdf_internal(
     document.save(&file_path)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_pdf;
+    use lopdf::{Dictionary, Object, StringFormat};
+    use tempfile::TempDir;
+
+    fn string(text: &str) -> Object {
+        Object::String(text.as_bytes().to_vec(), StringFormat::Literal)
+    }
+
+    /// A two-page document carrying metadata, a document script, an open
+    /// action, an attachment, a hidden layer, notes and PieceInfo.
+    fn cluttered_pdf(path: &str) {
+        let mut document = create_test_pdf(2, "Draft", "text").unwrap();
+        let pages = document.get_pages();
+
+        let mut info = Dictionary::new();
+        info.set("Author", string("Jane Doe"));
+        let info_id = document.add_object(info);
+        document.trailer.set("Info", Object::Reference(info_id));
+
+        let mut script = Dictionary::new();
+        script.set("S", Object::Name(b"JavaScript".to_vec()));
+        script.set("JS", string("app.alert('hi')"));
+        let script_id = document.add_object(script);
+        let mut javascript = Dictionary::new();
+        javascript.set(
+            "Names",
+            Object::Array(vec![string("init"), Object::Reference(script_id)]),
+        );
+        let mut file_spec = Dictionary::new();
+        file_spec.set("Type", Object::Name(b"Filespec".to_vec()));
+        file_spec.set("F", string("notes.txt"));
+        let file_spec_id = document.add_object(file_spec);
+        let mut embedded = Dictionary::new();
+        embedded.set(
+            "Names",
+            Object::Array(vec![string("notes.txt"), Object::Reference(file_spec_id)]),
+        );
+        let mut names = Dictionary::new();
+        names.set("JavaScript", Object::Dictionary(javascript));
+        names.set("EmbeddedFiles", Object::Dictionary(embedded));
+        let catalog = document.catalog_mut().unwrap();
+        catalog.set("Names", Object::Dictionary(names));
+        catalog.set("OpenAction", Object::Reference(script_id));
+
+        // A hidden review layer and a visible one on page 1
+        let hidden = layers::ensure_layer(&mut document, "Review", None).unwrap();
+        layers::ensure_layer(&mut document, "Visible", None).unwrap();
+        let mut properties = layers::oc_properties(&document);
+        if let Ok(Object::Dictionary(config)) = properties.get_mut(b"D") {
+            config.set("OFF", Object::Array(vec![Object::Reference(hidden)]));
+        }
+        document
+            .catalog_mut()
+            .unwrap()
+            .set("OCProperties", Object::Dictionary(properties));
+        let operations = vec![lopdf::content::Operation::new("n", vec![])];
+        let operations =
+            layers::wrap_in_layer(&mut document, pages[&1], hidden, operations).unwrap();
+        let mut data = lopdf::content::Content { operations }.encode().unwrap();
+        data.push(b'\n');
+        let content_id = document.add_object(lopdf::Stream::new(Dictionary::new(), data));
+        append_page_content(&mut document, pages[&1], content_id);
+
+        // A sticky note with its popup, and a link that stays
+        let mut note = Dictionary::new();
+        note.set("Type", Object::Name(b"Annot".to_vec()));
+        note.set("Subtype", Object::Name(b"Text".to_vec()));
+        note.set("Contents", string("Fix this"));
+        let note_id = document.add_object(note);
+        let mut popup = Dictionary::new();
+        popup.set("Type", Object::Name(b"Annot".to_vec()));
+        popup.set("Subtype", Object::Name(b"Popup".to_vec()));
+        popup.set("Parent", Object::Reference(note_id));
+        let popup_id = document.add_object(popup);
+        let mut link = Dictionary::new();
+        link.set("Type", Object::Name(b"Annot".to_vec()));
+        link.set("Subtype", Object::Name(b"Link".to_vec()));
+        let link_id = document.add_object(link);
+        let page = document.get_dictionary_mut(pages[&2]).unwrap();
+        page.set(
+            "Annots",
+            Object::Array(vec![
+                Object::Reference(note_id),
+                Object::Reference(popup_id),
+                Object::Reference(link_id),
+            ]),
+        );
+        page.set("PieceInfo", Object::Dictionary(Dictionary::new()));
+
+        // Page-level XMP with the editing history
+        let mut xmp_dict = Dictionary::new();
+        xmp_dict.set("Type", Object::Name(b"Metadata".to_vec()));
+        xmp_dict.set("Subtype", Object::Name(b"XML".to_vec()));
+        let xmp_id = document.add_object(lopdf::Stream::new(
+            xmp_dict,
+            b"<x:xmpmeta><xmpMM:History/></x:xmpmeta>".to_vec(),
+        ));
+        document
+            .get_dictionary_mut(pages[&2])
+            .unwrap()
+            .set("Metadata", Object::Reference(xmp_id));
+
+        document.save(path).unwrap();
+    }
+
+    #[test]
+    fn test_sanitize_removes_everything_by_default() {
+        let dir = TempDir::new().unwrap();
+        let input = dir.path().join("draft.pdf").to_string_lossy().to_string();
+        let output = dir.path().join("clean.pdf").to_string_lossy().to_string();
+        cluttered_pdf(&input);
+
+        let options = SanitizeOptions {
+            annotation_types: Some(vec!["Text".to_string()]),
+            ..Default::default()
+        };
+        let report = sanitize(&input, &output, &options).unwrap();
+        assert!(report.info_removed);
+        assert!(report.xmp_removed);
+        assert!(report.open_action_removed);
+        assert_eq!(report.javascript_removed, 1);
+        assert_eq!(report.attachments_removed, 1);
+        assert_eq!(report.layers_removed, vec!["Review".to_string()]);
+        assert_eq!(report.annotations_removed, 1);
+        assert_eq!(report.piece_info_removed, 1);
+        assert!(report.objects_pruned > 0);
+
+        let document = lopdf::Document::load(&output).unwrap();
+        assert!(document.trailer.get(b"Info").is_err());
+        let catalog = document.catalog().unwrap();
+        assert!(catalog.get(b"OpenAction").is_err());
+        let names = catalog.get(b"Names").and_then(Object::as_dict).unwrap();
+        assert!(names.get(b"JavaScript").is_err());
+        assert!(names.get(b"EmbeddedFiles").is_err());
+        assert!(layers::find_layers(&document, "Review").is_empty());
+        assert_eq!(layers::find_layers(&document, "Visible").len(), 1);
+
+        let pages = document.get_pages();
+        let content = document.get_page_content(pages[&1]).unwrap();
+        assert!(!String::from_utf8_lossy(&content).contains("/OC"));
+        let page = document.get_dictionary(pages[&2]).unwrap();
+        assert!(page.get(b"PieceInfo").is_err());
+        assert!(page.get(b"Metadata").is_err());
+        let annots = page.get(b"Annots").and_then(Object::as_array).unwrap();
+        assert_eq!(annots.len(), 1);
+        let remaining = document
+            .get_dictionary(annots[0].as_reference().unwrap())
+            .unwrap();
+        assert_eq!(
+            remaining.get(b"Subtype").unwrap().as_name().unwrap(),
+            b"Link"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_removes_hidden_forms_and_annotations() {
+        let dir = TempDir::new().unwrap();
+        let input = dir.path().join("draft.pdf").to_string_lossy().to_string();
+        let output = dir.path().join("clean.pdf").to_string_lossy().to_string();
+        cluttered_pdf(&input);
+
+        let mut document = lopdf::Document::load(&input).unwrap();
+        let page_id = document.get_pages()[&2];
+        let hidden = layers::find_layers(&document, "Review")[0];
+        let form = |document: &mut lopdf::Document, dict: Dictionary, data: &[u8]| {
+            let mut form_dict = dict;
+            form_dict.set("Type", Object::Name(b"XObject".to_vec()));
+            form_dict.set("Subtype", Object::Name(b"Form".to_vec()));
+            document.add_object(lopdf::Stream::new(form_dict, data.to_vec()))
+        };
+
+        // A form drawn only in the hidden layer, and a visible form with
+        // hidden marked content inside it
+        let mut hidden_dict = Dictionary::new();
+        hidden_dict.set("OC", Object::Reference(hidden));
+        let hidden_form = form(&mut document, hidden_dict, b"BT (Internal notes) Tj ET");
+        let mut properties = Dictionary::new();
+        properties.set("Rev", Object::Reference(hidden));
+        let mut form_resources = Dictionary::new();
+        form_resources.set("Properties", Object::Dictionary(properties));
+        let mut visible_dict = Dictionary::new();
+        visible_dict.set("Resources", Object::Dictionary(form_resources));
+        let visible_form = form(
+            &mut document,
+            visible_dict,
+            b"/OC /Rev BDC BT (Reviewer comment) Tj ET EMC BT (Approved) Tj ET",
+        );
+        for (name, id) in [("Fm0", hidden_form), ("Fm1", visible_form)] {
+            add_page_resource(
+                &mut document,
+                page_id,
+                "XObject",
+                name,
+                Object::Reference(id),
+            )
+            .unwrap();
+        }
+        let content_id = document.add_object(lopdf::Stream::new(
+            Dictionary::new(),
+            b"\n/Fm0 Do /Fm1 Do".to_vec(),
+        ));
+        append_page_content(&mut document, page_id, content_id);
+
+        // A stamp visible only through a membership dictionary over the layer
+        let mut ocmd = Dictionary::new();
+        ocmd.set("Type", Object::Name(b"OCMD".to_vec()));
+        ocmd.set("OCGs", Object::Array(vec![Object::Reference(hidden)]));
+        let ocmd_id = document.add_object(ocmd);
+        let mut stamp = Dictionary::new();
+        stamp.set("Type", Object::Name(b"Annot".to_vec()));
+        stamp.set("Subtype", Object::Name(b"Stamp".to_vec()));
+        stamp.set("OC", Object::Reference(ocmd_id));
+        let stamp_id = document.add_object(stamp);
+        if let Ok(Object::Array(annots)) = document
+            .get_dictionary_mut(page_id)
+            .unwrap()
+            .get_mut(b"Annots")
+        {
+            annots.push(Object::Reference(stamp_id));
+        }
+        document.save(&input).unwrap();
+
+        let report = sanitize(&input, &output, &SanitizeOptions::default()).unwrap();
+        assert_eq!(report.layers_removed, vec!["Review".to_string()]);
+
+        let document = lopdf::Document::load(&output).unwrap();
+        for id in [hidden, hidden_form, ocmd_id, stamp_id] {
+            assert!(document.get_object(id).is_err());
+        }
+        let page_id = document.get_pages()[&2];
+        let content =
+            String::from_utf8_lossy(&document.get_page_content(page_id).unwrap()).to_string();
+        assert!(!content.contains("/Fm0 Do"));
+        assert!(content.contains("/Fm1 Do"));
+        let form = document
+            .get_object(visible_form)
+            .and_then(Object::as_stream)
+            .unwrap();
+        let data = form.decompressed_content().unwrap_or(form.content.clone());
+        let data = String::from_utf8_lossy(&data);
+        assert!(!data.contains("Reviewer comment"));
+        assert!(data.contains("Approved"));
+        let annots = document
+            .get_dictionary(page_id)
+            .unwrap()
+            .get(b"Annots")
+            .and_then(Object::as_array)
+            .unwrap();
+        assert!(!annots.contains(&Object::Reference(stamp_id)));
+    }
+
+    #[test]
+    fn test_sanitize_respects_options() {
+        let dir = TempDir::new().unwrap();
+        let input = dir.path().join("draft.pdf").to_string_lossy().to_string();
+        let output = dir.path().join("clean.pdf").to_string_lossy().to_string();
+        cluttered_pdf(&input);
+
+        let options = SanitizeOptions {
+            remove_metadata: Some(false),
+            remove_hidden_layers: Some(false),
+            ..Default::default()
+        };
+        let report = sanitize(&input, &output, &options).unwrap();
+        assert!(!report.info_removed);
+        assert!(report.layers_removed.is_empty());
+        assert_eq!(report.annotations_removed, 0);
+
+        let document = lopdf::Document::load(&output).unwrap();
+        assert!(document.trailer.get(b"Info").is_ok());
+        assert_eq!(layers::find_layers(&document, "Review").len(), 1);
+        let page = document.get_dictionary(document.get_pages()[&2]).unwrap();
+        assert!(page.get(b"Metadata").is_ok());
+        let annots = page.get(b"Annots").and_then(Object::as_array).unwrap();
+        assert_eq!(annots.len(), 3);
+    }
+}
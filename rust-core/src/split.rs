@@ -0,0 +1,439 @@
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::bates::file_key;
+use crate::merge::{file_stem, link_target, named_destinations, resolve_dictionary, PdfAssembler};
+use crate::page_range::parse_page_range;
+use crate::stamp::expand_placeholders;
+use crate::validate::collect_references;
+
+#[derive(Debug, Clone, Default)]
+#[napi(object)]
+pub struct SplitOptions {
+    pub mode: Option<String>, // "every" (default), "ranges" or "bookmarks"
+    pub every: Option<u32>,   // Pages per output in "every" mode (default: 1)
+    pub ranges: Option<Vec<String>>, // One page-range expression per output in "ranges" mode
+    pub output_dir: Option<String>, // Defaults to the directory of the input
+    pub pattern: Option<String>, // Output file name template (default: "{name}_{index}.pdf")
+}
+
+/// One file written by [`split_pdf`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SplitPart {
+    pub output_file: String,
+    pub pages: Vec<u32>,
+    pub title: Option<String>, // Outline entry title in "bookmarks" mode
+}
+
+/// Splits a PDF into several files.
+///
+/// Output names are built from `options.pattern`, which accepts `{name}` (the
+/// input file stem), `{index}` (1-based, zero-padded to the number of parts),
+/// `{start}`, `{end}` and `{title}`. Each part only keeps the objects its
+/// pages reference. Nothing is written when two parts would share a file name
+/// or a part would replace the input.
+pub fn split_pdf(
+    input_path: &str,
+    options: &SplitOptions,
+) -> Result<Vec<SplitPart>, Box<dyn std::error::Error>> {
+    let document = Document::load(input_path)?;
+    let total = document.get_pages().len() as u32;
+    if total == 0 {
+        return Err("PDF has no pages".into());
+    }
+
+    let groups: Vec<(Vec<u32>, Option<String>)> = match options.mode.as_deref().unwrap_or("every") {
+        "every" => {
+            let size = options.every.unwrap_or(1).max(1) as usize;
+            let pages: Vec<u32> = (1..=total).collect();
+            pages
+                .chunks(size)
+                .map(|chunk| (chunk.to_vec(), None))
+                .collect()
+        }
+        "ranges" => {
+            let ranges = options.ranges.as_deref().unwrap_or_default();
+            if ranges.is_empty() {
+                return Err("No page ranges given".into());
+            }
+            let mut groups = Vec::with_capacity(ranges.len());
+            for range in ranges {
+                let pages = parse_page_range(range, total)?;
+                if pages.is_empty() {
+                    return Err(format!("Page range '{range}' selects no pages").into());
+                }
+                groups.push((pages, None));
+            }
+            groups
+        }
+        "bookmarks" => bookmark_sections(&document, total)?
+            .into_iter()
+            .map(|(pages, title)| (pages, Some(title)))
+            .collect(),
+        other => return Err(format!("Unsupported split mode: '{other}'").into()),
+    };
+
+    let name = file_stem(input_path);
+    let output_dir = match &options.output_dir {
+        Some(dir) => Path::new(dir).to_path_buf(),
+        None => Path::new(input_path)
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default(),
+    };
+    let pattern = options.pattern.as_deref().unwrap_or("{name}_{index}.pdf");
+    let digits = groups.len().to_string().len();
+
+    let input = file_key(input_path);
+    let mut output_files: HashMap<PathBuf, usize> = HashMap::new();
+    let mut parts = Vec::with_capacity(groups.len());
+    for (index, (pages, title)) in groups.into_iter().enumerate() {
+        let file_name = expand_placeholders(pattern, |placeholder| match placeholder {
+            "name" => Some(name.clone()),
+            "index" => Some(format!("{:0digits$}", index + 1)),
+            "start" => Some(pages[0].to_string()),
+            "end" => Some(pages[pages.len() - 1].to_string()),
+            "title" => Some(sanitize_file_name(title.as_deref().unwrap_or(""))),
+            _ => None,
+        });
+        let output_file = output_dir.join(file_name).to_string_lossy().to_string();
+        let key = file_key(&output_file);
+        if key == input {
+            return Err(format!(
+                "Part {} would overwrite the input '{input_path}'; change the pattern or \
+                 output directory",
+                index + 1
+            )
+            .into());
+        }
+        if let Some(earlier) = output_files.insert(key, index + 1) {
+            return Err(format!(
+                "Parts {earlier} and {} would both be written to '{output_file}'; \
+                 add {{index}} or {{start}} to the pattern",
+                index + 1
+            )
+            .into());
+        }
+        parts.push(SplitPart {
+            output_file,
+            pages,
+            title,
+        });
+    }
+
+    let page_ids = document.get_pages();
+    for part in &parts {
+        let selected: HashSet<ObjectId> = part.pages.iter().map(|page| page_ids[page]).collect();
+        let mut assembler = PdfAssembler::new();
+        let imported = assembler.import(
+            part_source(&document, &selected),
+            &part.pages,
+            input_path,
+            None,
+        )?;
+        let mut output = assembler.finish(&imported)?;
+        output.save(&part.output_file)?;
+    }
+    Ok(parts)
+}
+
+/// Copies the objects `selected` pages can reach into a document of their
+/// own, so each part is assembled from its share of the input rather than
+/// from a copy of all of it.
+///
+/// Other pages are kept as bare page objects for the page tree to stay
+/// intact, but nothing they reference is copied.
+fn part_source(document: &Document, selected: &HashSet<ObjectId>) -> Document {
+    let pages: HashSet<ObjectId> = document.page_iter().collect();
+    let mut source = Document::with_version(document.version.clone());
+    source.trailer = document.trailer.clone();
+    source.max_id = document.max_id;
+
+    let mut pending = Vec::new();
+    collect_references(&Object::Dictionary(document.trailer.clone()), &mut pending);
+    while let Some(id) = pending.pop() {
+        if source.objects.contains_key(&id) {
+            continue;
+        }
+        let Ok(object) = document.get_object(id) else {
+            continue;
+        };
+        if pages.contains(&id) && !selected.contains(&id) {
+            let mut page = Dictionary::new();
+            page.set("Type", Object::Name(b"Page".to_vec()));
+            if let Ok(parent) = object.as_dict().and_then(|dict| dict.get(b"Parent")) {
+                page.set("Parent", parent.clone());
+            }
+            source.objects.insert(id, Object::Dictionary(page));
+            continue;
+        }
+        collect_references(object, &mut pending);
+        source.objects.insert(id, object.clone());
+    }
+    source
+}
+
+/// Pages of a document section and the title of the outline entry that starts it.
+type Section = (Vec<u32>, String);
+
+/// Splits the document at each top-level outline entry, in page order.
+/// Pages before the first entry belong to the first section.
+fn bookmark_sections(
+    document: &Document,
+    total: u32,
+) -> Result<Vec<Section>, Box<dyn std::error::Error>> {
+    let catalog = document.catalog()?;
+    let named: HashMap<Vec<u8>, Object> =
+        named_destinations(document, catalog).into_iter().collect();
+    let page_numbers: HashMap<_, _> = document
+        .get_pages()
+        .into_iter()
+        .map(|(number, id)| (id, number))
+        .collect();
+
+    let mut starts: Vec<(u32, String)> = Vec::new();
+    let mut visited = HashSet::new();
+    let mut next = resolve_dictionary(document, catalog.get(b"Outlines").ok())
+        .and_then(|outlines| outlines.get(b"First").and_then(Object::as_reference).ok());
+    while let Some(id) = next {
+        // Cyclic /Next links in a malformed outline
+        if !visited.insert(id) {
+            break;
+        }
+        let Ok(item) = document.get_dictionary(id) else {
+            break;
+        };
+        let page = link_target(document, item, &named).and_then(|page| page_numbers.get(&page));
+        if let Some(&page) = page {
+            let title = item
+                .get(b"Title")
+                .ok()
+                .and_then(|title| lopdf::decode_text_string(title).ok())
+                .unwrap_or_default();
+            starts.push((page, title));
+        }
+        next = item.get(b"Next").and_then(Object::as_reference).ok();
+    }
+    if starts.is_empty() {
+        return Err("PDF has no top-level bookmarks that point to pages".into());
+    }
+
+    starts.sort_by_key(|(page, _)| *page);
+    // Entries sharing a start page would produce empty sections
+    starts.dedup_by_key(|(page, _)| *page);
+    starts[0].0 = 1;
+
+    let mut sections = Vec::with_capacity(starts.len());
+    for (index, (start, title)) in starts.iter().enumerate() {
+        let end = starts
+            .get(index + 1)
+            .map(|(next_start, _)| next_start - 1)
+            .unwrap_or(total);
+        sections.push(((*start..=end).collect(), title.clone()));
+    }
+    Ok(sections)
+}
+
+pub(crate) fn sanitize_file_name(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_pdf;
+    use tempfile::TempDir;
+
+    fn save_input(dir: &TempDir, document: &mut Document) -> String {
+        let path = dir.path().join("book.pdf").to_string_lossy().to_string();
+        document.save(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_split_every_n_pages() {
+        let dir = TempDir::new().unwrap();
+        let input = save_input(&dir, &mut create_test_pdf(5, "Book", "text").unwrap());
+
+        let options = SplitOptions {
+            every: Some(2),
+            pattern: Some("{name}-{index}-p{start}-{end}.pdf".to_string()),
+            ..Default::default()
+        };
+        let parts = split_pdf(&input, &options).unwrap();
+        assert_eq!(parts.len(), 3);
+        assert!(parts[0].output_file.ends_with("book-1-p1-2.pdf"));
+        assert_eq!(parts[2].pages, vec![5]);
+
+        let last = Document::load(&parts[2].output_file).unwrap();
+        assert_eq!(last.get_pages().len(), 1);
+        let content = last.get_page_content(last.get_pages()[&1]).unwrap();
+        assert!(String::from_utf8_lossy(&content).contains("Book - Page 5"));
+        // Only the page, its content stream and font, the page tree and catalog remain
+        let objects = last
+            .objects
+            .values()
+            .filter(|object| {
+                object.as_stream().map_or(true, |stream| {
+                    stream.dict.get_type().ok() != Some(b"XRef".as_slice())
+                })
+            })
+            .count();
+        assert_eq!(objects, 5);
+    }
+
+    #[test]
+    fn test_split_by_ranges() {
+        let dir = TempDir::new().unwrap();
+        let input = save_input(&dir, &mut create_test_pdf(6, "Book", "text").unwrap());
+
+        let options = SplitOptions {
+            mode: Some("ranges".to_string()),
+            ranges: Some(vec!["1-2".to_string(), "odd".to_string()]),
+            ..Default::default()
+        };
+        let parts = split_pdf(&input, &options).unwrap();
+        assert_eq!(parts[1].pages, vec![1, 3, 5]);
+        let odd = Document::load(&parts[1].output_file).unwrap();
+        assert_eq!(odd.get_pages().len(), 3);
+    }
+
+    #[test]
+    fn test_split_by_bookmarks() {
+        let dir = TempDir::new().unwrap();
+        let mut document = create_test_pdf(5, "Book", "text").unwrap();
+        let pages = document.get_pages();
+
+        let mut item_ids = Vec::new();
+        for (title, page) in [("Part One", 2), ("Part Two", 4)] {
+            let mut item = Dictionary::new();
+            item.set("Title", lopdf::text_string(title));
+            item.set(
+                "Dest",
+                Object::Array(vec![
+                    Object::Reference(pages[&page]),
+                    Object::Name(b"Fit".to_vec()),
+                ]),
+            );
+            item_ids.push(document.add_object(item));
+        }
+        document
+            .get_dictionary_mut(item_ids[0])
+            .unwrap()
+            .set("Next", Object::Reference(item_ids[1]));
+        let mut outlines = Dictionary::new();
+        outlines.set("First", Object::Reference(item_ids[0]));
+        outlines.set("Last", Object::Reference(item_ids[1]));
+        let outlines_id = document.add_object(outlines);
+        document
+            .catalog_mut()
+            .unwrap()
+            .set("Outlines", Object::Reference(outlines_id));
+        let input = save_input(&dir, &mut document);
+
+        let options = SplitOptions {
+            mode: Some("bookmarks".to_string()),
+            pattern: Some("{index} {title}.pdf".to_string()),
+            ..Default::default()
+        };
+        let parts = split_pdf(&input, &options).unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].pages, vec![1, 2, 3]);
+        assert_eq!(parts[1].pages, vec![4, 5]);
+        assert!(parts[1].output_file.ends_with("2 Part Two.pdf"));
+    }
+
+    #[test]
+    fn test_split_rejects_clashing_file_names() {
+        let dir = TempDir::new().unwrap();
+        let input = save_input(&dir, &mut create_test_pdf(3, "Book", "text").unwrap());
+
+        let options = SplitOptions {
+            pattern: Some("{name}-part.pdf".to_string()),
+            ..Default::default()
+        };
+        let error = split_pdf(&input, &options).unwrap_err().to_string();
+        assert!(error.contains("Parts 1 and 2"), "{error}");
+        assert!(!dir.path().join("book-part.pdf").exists());
+    }
+
+    #[test]
+    fn test_split_never_overwrites_the_input() {
+        let dir = TempDir::new().unwrap();
+        let input = save_input(&dir, &mut create_test_pdf(3, "Book", "text").unwrap());
+        let original = std::fs::read(&input).unwrap();
+
+        let single_range = SplitOptions {
+            mode: Some("ranges".to_string()),
+            ranges: Some(vec!["2".to_string()]),
+            pattern: Some("{name}.pdf".to_string()),
+            ..Default::default()
+        };
+        let whole = SplitOptions {
+            every: Some(5),
+            pattern: Some("{name}.pdf".to_string()),
+            ..Default::default()
+        };
+        for options in [single_range, whole] {
+            let error = split_pdf(&input, &options).unwrap_err().to_string();
+            assert!(error.contains("overwrite the input"), "{error}");
+        }
+        assert_eq!(std::fs::read(&input).unwrap(), original);
+
+        // Placeholders in the file stem are not expanded again
+        let dir = TempDir::new().unwrap();
+        let input = dir.path().join("{index}.pdf").to_string_lossy().to_string();
+        create_test_pdf(2, "Book", "text")
+            .unwrap()
+            .save(&input)
+            .unwrap();
+        let parts = split_pdf(&input, &SplitOptions::default()).unwrap();
+        assert!(parts[0].output_file.ends_with("{index}_1.pdf"));
+    }
+
+    #[test]
+    fn test_split_by_bookmarks_stops_at_outline_cycle() {
+        let dir = TempDir::new().unwrap();
+        let mut document = create_test_pdf(2, "Book", "text").unwrap();
+
+        // Two entries without destinations that point at each other
+        let first = document.add_object(Dictionary::new());
+        let second = document.add_object(Dictionary::new());
+        document
+            .get_dictionary_mut(first)
+            .unwrap()
+            .set("Next", Object::Reference(second));
+        document
+            .get_dictionary_mut(second)
+            .unwrap()
+            .set("Next", Object::Reference(first));
+        let mut outlines = Dictionary::new();
+        outlines.set("First", Object::Reference(first));
+        let outlines_id = document.add_object(outlines);
+        document
+            .catalog_mut()
+            .unwrap()
+            .set("Outlines", Object::Reference(outlines_id));
+        let input = save_input(&dir, &mut document);
+
+        let options = SplitOptions {
+            mode: Some("bookmarks".to_string()),
+            ..Default::default()
+        };
+        let error = split_pdf(&input, &options).unwrap_err().to_string();
+        assert!(error.contains("no top-level bookmarks"), "{error}");
+    }
+}
@@ -0,0 +1,359 @@
+use lopdf::content::{Content, Operation};
+use lopdf::{Dictionary, Object, ObjectId, Stream, StringFormat};
+use napi_derive::napi;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::page_range::selected_page_numbers;
+use crate::sign::{add_page_resource, append_page_content, page_box};
+use crate::watermark::{
+    encode_text, matrix_operands, parse_color, stamp_font, text_width, STAMP_FONT,
+};
+
+/// Text for the left, center and right slots of a header or footer.
+///
+/// Slots accept the template variables `{page}`, `{total}`, `{filename}`,
+/// `{date}` and `{title}`.
+#[derive(Debug, Clone, Default)]
+#[napi(object)]
+pub struct RunningText {
+    pub left: Option<String>,
+    pub center: Option<String>,
+    pub right: Option<String>,
+    pub pages: Option<Vec<u32>>, // 1-based page numbers; all pages when omitted
+    pub page_range: Option<String>, // Page-range expression; takes precedence over `pages`
+}
+
+#[derive(Debug, Clone, Default)]
+#[napi(object)]
+pub struct HeaderFooterOptions {
+    pub header: Option<RunningText>,
+    pub footer: Option<RunningText>,
+    pub font_size: Option<f64>,
+    pub color: Option<String>,
+    pub margin: Option<f64>, // Distance from the page edge in points (default: 36)
+}
+
+/// Values substituted into header and footer templates.
+#[derive(Debug, Clone)]
+pub struct TemplateContext {
+    pub page: u32,
+    pub total: u32,
+    pub filename: String,
+    pub date: String,
+    pub title: String,
+}
+
+/// A line of text to draw at an absolute position on a page.
+pub(crate) struct TextPlacement {
+    pub text: String,
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Expands `{page}`, `{total}`, `{filename}`, `{date}` and `{title}` in a
+/// template. Unknown placeholders are left as-is.
+pub fn expand_template(template: &str, context: &TemplateContext) -> String {
+    expand_placeholders(template, |name| match name {
+        "page" => Some(context.page.to_string()),
+        "total" => Some(context.total.to_string()),
+        "filename" => Some(context.filename.clone()),
+        "date" => Some(context.date.clone()),
+        "title" => Some(context.title.clone()),
+        _ => None,
+    })
+}
+
+/// Replaces each `{name}` in `template` for which `value` returns a value,
+/// in a single pass, so placeholders inside substituted values are kept as
+/// they are.
+pub(crate) fn expand_placeholders(
+    template: &str,
+    value: impl Fn(&str) -> Option<String>,
+) -> String {
+    let mut expanded = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        expanded.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        match after
+            .find('}')
+            .and_then(|close| Some((close, value(&after[..close])?)))
+        {
+            Some((close, replacement)) => {
+                expanded.push_str(&replacement);
+                rest = &after[close + 1..];
+            }
+            None => {
+                expanded.push('{');
+                rest = after;
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Stamps running headers and footers onto a PDF in place.
+///
+/// # Returns
+/// The number of pages that received a header or footer.
+pub fn add_header_footer(
+    file_path: &str,
+    options: &HeaderFooterOptions,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    let mut document = lopdf::Document::load(file_path)?;
+    let total = document.get_pages().len() as u32;
+
+    let filename = Path::new(file_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let title = document_title(&document).unwrap_or_default();
+    let date = today();
+
+    let font_size = options.font_size.unwrap_or(10.0);
+    let margin = options.margin.unwrap_or(36.0);
+    let color = options
+        .color
+        .as_deref()
+        .and_then(parse_color)
+        .unwrap_or((0.0, 0.0, 0.0));
+
+    let header_pages = running_text_pages(&options.header, total)?;
+    let footer_pages = running_text_pages(&options.footer, total)?;
+
+    let mut stamped = 0;
+    for (page_number, page_id) in document.get_pages() {
+        let context = TemplateContext {
+            page: page_number,
+            total,
+            filename: filename.clone(),
+            date: date.clone(),
+            title: title.clone(),
+        };
+        let [llx, lly, urx, ury] = page_box(&document, page_id);
+
+        let mut placements = Vec::new();
+        let rows = [
+            (&options.header, &header_pages, ury - margin - font_size),
+            (&options.footer, &footer_pages, lly + margin),
+        ];
+        for (running_text, pages, y) in rows {
+            let Some(running_text) = running_text else {
+                continue;
+            };
+            if !pages.contains(&page_number) {
+                continue;
+            }
+            let slots = [
+                (&running_text.left, Slot::Left),
+                (&running_text.center, Slot::Center),
+                (&running_text.right, Slot::Right),
+            ];
+            for (template, slot) in slots {
+                let Some(template) = template else {
+                    continue;
+                };
+                let text = expand_template(template, &context);
+                let width = text_width(&text, font_size);
+                let x = match slot {
+                    Slot::Left => llx + margin,
+                    Slot::Center => (llx + urx - width) / 2.0,
+                    Slot::Right => urx - margin - width,
+                };
+                placements.push(TextPlacement { text, x, y });
+            }
+        }
+
+        if !placements.is_empty() {
+            stamp_text(&mut document, page_id, &placements, font_size, color)?;
+            stamped += 1;
+        }
+    }
+
+    document.save(file_path)?;
+    Ok(stamped)
+}
+
+enum Slot {
+    Left,
+    Center,
+    Right,
+}
+
+fn running_text_pages(
+    running_text: &Option<RunningText>,
+    total: u32,
+) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+    match running_text {
+        Some(running_text) => selected_page_numbers(
+            total,
+            running_text.page_range.as_deref(),
+            running_text.pages.as_deref(),
+        ),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Draws lines of Helvetica text on a page, over the existing content.
+pub(crate) fn stamp_text(
+    document: &mut lopdf::Document,
+    page_id: ObjectId,
+    placements: &[TextPlacement],
+    font_size: f64,
+    (r, g, b): (f64, f64, f64),
+) -> lopdf::Result<()> {
+    let font_id = stamp_font(document);
+    add_page_resource(
+        document,
+        page_id,
+        "Font",
+        STAMP_FONT,
+        Object::Reference(font_id),
+    )?;
+
+    let mut operations = vec![
+        Operation::new("q", vec![]),
+        Operation::new(
+            "rg",
+            vec![
+                Object::Real(r as f32),
+                Object::Real(g as f32),
+                Object::Real(b as f32),
+            ],
+        ),
+        Operation::new("BT", vec![]),
+        Operation::new(
+            "Tf",
+            vec![
+                Object::Name(STAMP_FONT.into()),
+                Object::Real(font_size as f32),
+            ],
+        ),
+    ];
+    for placement in placements {
+        operations.push(Operation::new(
+            "Tm",
+            matrix_operands([1.0, 0.0, 0.0, 1.0, placement.x, placement.y]),
+        ));
+        operations.push(Operation::new(
+            "Tj",
+            vec![Object::String(
+                encode_text(&placement.text),
+                StringFormat::Literal,
+            )],
+        ));
+    }
+    operations.push(Operation::new("ET", vec![]));
+    operations.push(Operation::new("Q", vec![]));
+
+    let content_data = Content { operations }.encode()?;
+    let content_id = document.add_object(Stream::new(Dictionary::new(), content_data));
+    append_page_content(document, page_id, content_id);
+    Ok(())
+}
+
+/// Reads `/Title` from the document information dictionary.
+pub(crate) fn document_title(document: &lopdf::Document) -> Option<String> {
+    let info = match document.trailer.get(b"Info").ok()? {
+        Object::Reference(id) => document.get_dictionary(*id).ok()?,
+        Object::Dictionary(dict) => dict,
+        _ => return None,
+    };
+    lopdf::decode_text_string(info.get(b"Title").ok()?).ok()
+}
+
+/// Today's UTC date as `YYYY-MM-DD`.
+pub(crate) fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Converts days since 1970-01-01 into a proleptic Gregorian (year, month, day).
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_pdf;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_expand_template() {
+        let context = TemplateContext {
+            page: 3,
+            total: 12,
+            filename: "report.pdf".to_string(),
+            date: "2025-01-31".to_string(),
+            title: "Annual Report".to_string(),
+        };
+        assert_eq!(
+            expand_template("Page {page} of {total}", &context),
+            "Page 3 of 12"
+        );
+        assert_eq!(
+            expand_template("{title} - {filename} ({date}) {unknown}", &context),
+            "Annual Report - report.pdf (2025-01-31) {unknown}"
+        );
+
+        // Values are not expanded again
+        let context = TemplateContext {
+            filename: "{title}.pdf".to_string(),
+            title: "Total {page}".to_string(),
+            ..context
+        };
+        assert_eq!(
+            expand_template("{filename}: {title} {{page}", &context),
+            "{title}.pdf: Total {page} {3"
+        );
+    }
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(20_089), (2025, 1, 1));
+    }
+
+    #[test]
+    fn test_footer_skips_cover_page() {
+        let mut doc = create_test_pdf(3, "test", "content").unwrap();
+        let temp_file = NamedTempFile::new().unwrap();
+        let path = temp_file.path().to_string_lossy().to_string();
+        doc.save(&path).unwrap();
+
+        let options = HeaderFooterOptions {
+            footer: Some(RunningText {
+                right: Some("Page {page} of {total}".to_string()),
+                page_range: Some("!1".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(add_header_footer(&path, &options).unwrap(), 2);
+
+        let doc = lopdf::Document::load(&path).unwrap();
+        let pages = doc.get_pages();
+        let cover = doc.get_page_content(pages[&1]).unwrap();
+        assert!(!String::from_utf8_lossy(&cover).contains("of 3"));
+        let second = doc.get_page_content(pages[&2]).unwrap();
+        assert!(String::from_utf8_lossy(&second).contains("(Page 2 of 3) Tj"));
+    }
+}
@@ -0,0 +1,735 @@
+use lopdf::content::Content;
+use lopdf::xref::XrefEntry;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::ops::Range;
+
+use crate::inspect::reference;
+use crate::merge::resolve_dictionary;
+use crate::sign::inherited_page_attribute;
+
+/// Resource categories checked for operators that name a resource.
+const RESOURCE_OPERATORS: [(&str, &[u8], &str); 3] = [
+    ("Tf", b"Font", "Font"),
+    ("Do", b"XObject", "XObject"),
+    ("gs", b"ExtGState", "Graphics state"),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Finding {
+    pub severity: String, // "error" or "warning"
+    pub code: String,     // Stable identifier, e.g. "dangling_reference"
+    pub message: String,
+    pub object: Option<String>, // "12 0 R" when the finding concerns one object
+    pub page: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub file: String,
+    pub valid: bool, // No errors (warnings are allowed)
+    pub error_count: u32,
+    pub warning_count: u32,
+    pub findings: Vec<Finding>,
+}
+
+/// Checks the structure of a PDF and reports what is wrong with it.
+///
+/// Errors are problems that break the file for strict readers: xref offsets
+/// that do not point at their object, objects that cannot be parsed, stream
+/// `/Length` values that do not match the data, a wrong page `/Count`,
+/// missing required keys, and resources used in content streams that the
+/// page does not define. Dangling references and other recoverable issues
+/// are warnings. A file lopdf cannot open at all yields a single error.
+pub fn validate_pdf(file_path: &str) -> Result<ValidationReport, Box<dyn std::error::Error>> {
+    let bytes = std::fs::read(file_path)?;
+    let mut validator = Validator::default();
+    match Document::load_mem(&bytes) {
+        Ok(document) => {
+            validator.check_xref(&document, &bytes);
+            validator.check_references(&document);
+            validator.check_structure(&document);
+            validator.check_resources(&document);
+        }
+        Err(e) => validator.push(
+            "error",
+            "unreadable_file",
+            format!("File could not be parsed: {e} (repair_pdf may recover it)"),
+            None,
+            None,
+        ),
+    }
+
+    let count = |severity: &str| {
+        validator
+            .findings
+            .iter()
+            .filter(|finding| finding.severity == severity)
+            .count() as u32
+    };
+    let error_count = count("error");
+    Ok(ValidationReport {
+        file: file_path.to_string(),
+        valid: error_count == 0,
+        error_count,
+        warning_count: count("warning"),
+        findings: validator.findings,
+    })
+}
+
+#[derive(Default)]
+struct Validator {
+    findings: Vec<Finding>,
+}
+
+impl Validator {
+    fn push(
+        &mut self,
+        severity: &str,
+        code: &str,
+        message: String,
+        object: Option<ObjectId>,
+        page: Option<u32>,
+    ) {
+        self.findings.push(Finding {
+            severity: severity.to_string(),
+            code: code.to_string(),
+            message,
+            object: object.map(reference),
+            page,
+        });
+    }
+
+    /// Compares the xref table against the raw file: each in-use entry must
+    /// point at its `N G obj` header, and each stream's `/Length` must end
+    /// right before `endstream`.
+    fn check_xref(&mut self, document: &Document, bytes: &[u8]) {
+        let mut referenced = Vec::new();
+        collect_references(
+            &Object::Dictionary(document.trailer.clone()),
+            &mut referenced,
+        );
+        for object in document.objects.values() {
+            collect_references(object, &mut referenced);
+        }
+        let referenced: HashSet<ObjectId> = referenced.into_iter().collect();
+
+        for (number, entry) in &document.reference_table.entries {
+            let XrefEntry::Normal { offset, generation } = entry else {
+                continue;
+            };
+            let id = (*number, *generation);
+            let offset = *offset as usize;
+            if object_header_at(bytes, offset) != Some(id) {
+                // An entry nothing refers to is harmless to readers
+                self.push(
+                    if referenced.contains(&id) {
+                        "error"
+                    } else {
+                        "warning"
+                    },
+                    "xref_offset",
+                    format!(
+                        "Xref offset {offset} for {} does not point at the object",
+                        reference(id)
+                    ),
+                    Some(id),
+                    None,
+                );
+                continue;
+            }
+
+            // The raw bytes are checked rather than the loaded object: lopdf
+            // rewrites object streams as it unpacks them, and keeps only the
+            // dictionary (or nothing) when the data does not fit /Length
+            let object = &bytes[offset..];
+            match raw_stream_span(object) {
+                Some((start, actual)) => {
+                    let declared = declared_length(document, &object[..start]);
+                    let fits = declared.is_some_and(|declared| {
+                        declared >= 0 && ends_stream(object, start + declared as usize)
+                    });
+                    if !fits {
+                        self.push(
+                            "error",
+                            "stream_length",
+                            match declared {
+                                Some(declared) => format!(
+                                    "Stream {} declares /Length {declared} but has {actual} bytes",
+                                    reference(id)
+                                ),
+                                None => format!("Stream {} has no valid /Length", reference(id)),
+                            },
+                            Some(id),
+                            None,
+                        );
+                    }
+                }
+                // lopdf removes the encryption dictionary once it has
+                // decrypted the file
+                None if document.get_object(id).is_err() && document.encryption_state.is_none() => {
+                    self.push(
+                        "error",
+                        "unreadable_object",
+                        format!("Object {} could not be parsed", reference(id)),
+                        Some(id),
+                        None,
+                    )
+                }
+                None => {}
+            }
+        }
+    }
+
+    /// Reports references to objects that do not exist. Readers treat them
+    /// as null, which is legal but usually means something was lost.
+    fn check_references(&mut self, document: &Document) {
+        let mut sources: Vec<(Option<ObjectId>, &Object)> = document
+            .objects
+            .iter()
+            .map(|(id, object)| (Some(*id), object))
+            .collect();
+        let trailer = Object::Dictionary(document.trailer.clone());
+        sources.push((None, &trailer));
+        for (source, object) in sources {
+            let mut references = Vec::new();
+            collect_references(object, &mut references);
+            let mut reported = HashSet::new();
+            for target in references {
+                if document.objects.contains_key(&target) || !reported.insert(target) {
+                    continue;
+                }
+                let from = source.map_or("the trailer".to_string(), reference);
+                self.push(
+                    "warning",
+                    "dangling_reference",
+                    format!("{from} refers to missing object {}", reference(target)),
+                    source,
+                    None,
+                );
+            }
+        }
+    }
+
+    /// Checks the catalog and page tree: required keys, `/Count` against the
+    /// number of leaf pages, and `/Parent` links.
+    fn check_structure(&mut self, document: &Document) {
+        if document.trailer.get(b"Size").is_err() {
+            self.push(
+                "warning",
+                "missing_key",
+                "Trailer has no /Size".to_string(),
+                None,
+                None,
+            );
+        }
+        let Ok(root_id) = document.trailer.get(b"Root").and_then(Object::as_reference) else {
+            self.push(
+                "error",
+                "missing_key",
+                "Trailer has no /Root".to_string(),
+                None,
+                None,
+            );
+            return;
+        };
+        let Ok(catalog) = document.get_dictionary(root_id) else {
+            self.push(
+                "error",
+                "missing_key",
+                format!(
+                    "Catalog {} is missing or not a dictionary",
+                    reference(root_id)
+                ),
+                Some(root_id),
+                None,
+            );
+            return;
+        };
+        if catalog.get(b"Type").and_then(Object::as_name).ok() != Some(b"Catalog") {
+            self.push(
+                "error",
+                "missing_key",
+                "Catalog has no /Type /Catalog".to_string(),
+                Some(root_id),
+                None,
+            );
+        }
+        let Ok(pages_id) = catalog.get(b"Pages").and_then(Object::as_reference) else {
+            self.push(
+                "error",
+                "missing_key",
+                "Catalog has no /Pages".to_string(),
+                Some(root_id),
+                None,
+            );
+            return;
+        };
+
+        let mut visited = HashSet::new();
+        let mut page_number = 0;
+        self.check_page_node(document, pages_id, None, &mut visited, &mut page_number);
+    }
+
+    /// Checks a page tree node and its descendants; returns the number of
+    /// leaf pages below it.
+    fn check_page_node(
+        &mut self,
+        document: &Document,
+        id: ObjectId,
+        parent: Option<ObjectId>,
+        visited: &mut HashSet<ObjectId>,
+        page_number: &mut u32,
+    ) -> u32 {
+        if !visited.insert(id) {
+            self.push(
+                "error",
+                "page_tree_cycle",
+                format!("Page tree node {} is reached more than once", reference(id)),
+                Some(id),
+                None,
+            );
+            return 0;
+        }
+        let Ok(node) = document.get_dictionary(id) else {
+            // Dangling kids are reported by check_references
+            return 0;
+        };
+        let node_type = node.get(b"Type").and_then(Object::as_name).ok();
+        let actual_parent = node.get(b"Parent").and_then(Object::as_reference).ok();
+        if parent.is_some() && actual_parent != parent {
+            self.push(
+                "warning",
+                "page_parent",
+                format!("{} does not point back to its parent", reference(id)),
+                Some(id),
+                None,
+            );
+        }
+
+        let Ok(kids) = node.get(b"Kids").and_then(Object::as_array) else {
+            *page_number += 1;
+            let page = Some(*page_number);
+            if node_type != Some(b"Page") {
+                self.push(
+                    "error",
+                    "missing_key",
+                    format!("Page {} has no /Type /Page", reference(id)),
+                    Some(id),
+                    page,
+                );
+            }
+            if inherited_page_attribute(document, id, b"MediaBox").is_none() {
+                self.push(
+                    "error",
+                    "missing_key",
+                    format!("Page {} has no /MediaBox, even inherited", reference(id)),
+                    Some(id),
+                    page,
+                );
+            }
+            if inherited_page_attribute(document, id, b"Resources").is_none() {
+                self.push(
+                    "warning",
+                    "missing_key",
+                    format!("Page {} has no /Resources, even inherited", reference(id)),
+                    Some(id),
+                    page,
+                );
+            }
+            return 1;
+        };
+
+        if node_type != Some(b"Pages") {
+            self.push(
+                "error",
+                "missing_key",
+                format!("Page tree node {} has no /Type /Pages", reference(id)),
+                Some(id),
+                None,
+            );
+        }
+        let leaves: u32 = kids
+            .iter()
+            .filter_map(|kid| kid.as_reference().ok())
+            .map(|kid| self.check_page_node(document, kid, Some(id), visited, page_number))
+            .sum();
+        match node.get(b"Count").and_then(Object::as_i64) {
+            Ok(count) if count == leaves as i64 => {}
+            Ok(count) => self.push(
+                "error",
+                "page_count",
+                format!(
+                    "Page tree node {} has /Count {count} but {leaves} pages",
+                    reference(id)
+                ),
+                Some(id),
+                None,
+            ),
+            Err(_) => self.push(
+                "error",
+                "missing_key",
+                format!("Page tree node {} has no /Count", reference(id)),
+                Some(id),
+                None,
+            ),
+        }
+        leaves
+    }
+
+    /// Reports fonts, XObjects and graphics states that content streams use
+    /// but that are not defined in the resources in effect, including inside
+    /// Form XObjects.
+    fn check_resources(&mut self, document: &Document) {
+        for (number, page_id) in document.get_pages() {
+            let resources = match inherited_page_attribute(document, page_id, b"Resources") {
+                Some(Object::Dictionary(resources)) => resources,
+                _ => Dictionary::new(),
+            };
+            let Ok(content) = document.get_page_content(page_id) else {
+                continue;
+            };
+            let mut visited = HashSet::new();
+            self.check_content(
+                document,
+                &content,
+                &resources,
+                page_id,
+                number,
+                &mut visited,
+            );
+        }
+    }
+
+    fn check_content(
+        &mut self,
+        document: &Document,
+        data: &[u8],
+        resources: &Dictionary,
+        owner: ObjectId,
+        page: u32,
+        visited: &mut HashSet<ObjectId>,
+    ) {
+        let content = match Content::decode(data) {
+            Ok(content) => content,
+            Err(e) => {
+                self.push(
+                    "warning",
+                    "content_syntax",
+                    format!("Content of {} could not be parsed: {e}", reference(owner)),
+                    Some(owner),
+                    Some(page),
+                );
+                return;
+            }
+        };
+
+        let mut reported = HashSet::new();
+        for operation in &content.operations {
+            let Some((_, category, label)) = RESOURCE_OPERATORS
+                .iter()
+                .find(|(operator, ..)| *operator == operation.operator)
+            else {
+                continue;
+            };
+            let Some(name) = operation
+                .operands
+                .first()
+                .and_then(|name| name.as_name().ok())
+            else {
+                continue;
+            };
+            let entry = resolve_dictionary(document, resources.get(category).ok())
+                .and_then(|entries| entries.get(name).ok());
+            let Some(entry) = entry else {
+                if reported.insert(name.to_vec()) {
+                    self.push(
+                        "error",
+                        "missing_resource",
+                        format!(
+                            "{label} /{} used on page {page} is not defined in /Resources",
+                            String::from_utf8_lossy(name)
+                        ),
+                        Some(owner),
+                        Some(page),
+                    );
+                }
+                continue;
+            };
+
+            // Forms are checked against their own resources, falling back to
+            // the page's for old files that omit them
+            let Ok(form_id) = entry.as_reference() else {
+                continue;
+            };
+            let Ok(form) = document.get_object(form_id).and_then(Object::as_stream) else {
+                continue;
+            };
+            if operation.operator != "Do"
+                || form.dict.get(b"Subtype").and_then(Object::as_name).ok() != Some(b"Form")
+                || !visited.insert(form_id)
+            {
+                continue;
+            }
+            let form_resources = resolve_dictionary(document, form.dict.get(b"Resources").ok())
+                .cloned()
+                .unwrap_or_else(|| resources.clone());
+            let data = form
+                .decompressed_content()
+                .unwrap_or_else(|_| form.content.clone());
+            self.check_content(document, &data, &form_resources, form_id, page, visited);
+        }
+    }
+}
+
+/// Parses an `N G obj` header at `offset`, allowing leading whitespace.
+fn object_header_at(bytes: &[u8], offset: usize) -> Option<ObjectId> {
+    let text = bytes.get(offset..)?;
+    let text = &text[..text.len().min(64)];
+    let text = String::from_utf8_lossy(text);
+    let mut parts = text.trim_start().split_ascii_whitespace();
+    let number = parts.next()?.parse().ok()?;
+    let generation = parts.next()?.parse().ok()?;
+    // "obj" may be followed directly by a delimiter such as "<<"
+    parts
+        .next()?
+        .starts_with("obj")
+        .then_some((number, generation))
+}
+
+/// Returns where an object's stream data starts and how long it really is,
+/// by looking for the `stream` keyword and the following `endstream`.
+fn raw_stream_span(object: &[u8]) -> Option<(usize, usize)> {
+    let end_of_object = find(object, b"endobj").unwrap_or(object.len());
+    let start = stream_data_start(&object[..end_of_object])?;
+    let end = start + find(&object[start..], b"endstream")?;
+    // The end-of-line marker before endstream is not part of the data
+    let mut data_end = end;
+    if data_end > start && object[data_end - 1] == b'\n' {
+        data_end -= 1;
+    }
+    if data_end > start && object[data_end - 1] == b'\r' {
+        data_end -= 1;
+    }
+    Some((start, data_end - start))
+}
+
+/// Finds the `stream` keyword that follows an object's dictionary and
+/// returns where the data starts, after the keyword's end-of-line marker.
+pub(crate) fn stream_data_start(object: &[u8]) -> Option<usize> {
+    // The keyword follows the dictionary and ends the line
+    let keyword = (1..object.len()).find(|&at| {
+        object[at..].starts_with(b"stream")
+            && matches!(object[at - 1], b'>' | b' ' | b'\r' | b'\n')
+            && matches!(object.get(at + 6), Some(b'\r' | b'\n'))
+    })?;
+    let mut start = keyword + b"stream".len();
+    if object.get(start) == Some(&b'\r') {
+        start += 1;
+    }
+    if object.get(start) == Some(&b'\n') {
+        start += 1;
+    }
+    Some(start)
+}
+
+/// Whether stream data ending at `end` is followed by `endstream`, after an
+/// optional end-of-line marker.
+pub(crate) fn ends_stream(object: &[u8], end: usize) -> bool {
+    let Some(rest) = object.get(end..) else {
+        return false;
+    };
+    let rest = rest.strip_prefix(b"\r").unwrap_or(rest);
+    let rest = rest.strip_prefix(b"\n").unwrap_or(rest);
+    rest.starts_with(b"endstream")
+}
+
+/// Locates an entry such as `/Length` or `/Root` in a raw dictionary and
+/// reads its integer value. Returns the byte range of the value, the number,
+/// and the generation when the value is an indirect `N G R` reference.
+pub(crate) fn raw_entry(header: &[u8], key: &[u8]) -> Option<(Range<usize>, i64, Option<u16>)> {
+    let token = |at: usize| {
+        let start = at
+            + header[at..]
+                .iter()
+                .take_while(|c| c.is_ascii_whitespace())
+                .count();
+        let len = header[start..]
+            .iter()
+            .take_while(|c| c.is_ascii_alphanumeric() || **c == b'-')
+            .count();
+        (start, start + len)
+    };
+    let at = (0..header.len()).find(|&at| {
+        // Skip longer keys such as /Length1 in font file streams
+        header[at..].starts_with(key)
+            && !header
+                .get(at + key.len())
+                .is_some_and(|c| c.is_ascii_alphanumeric())
+    })?;
+    let (start, end) = token(at + key.len());
+    let value = std::str::from_utf8(&header[start..end])
+        .ok()?
+        .parse()
+        .ok()?;
+
+    let (generation_start, generation_end) = token(end);
+    let (r_start, r_end) = token(generation_end);
+    let generation = std::str::from_utf8(&header[generation_start..generation_end])
+        .ok()
+        .and_then(|generation| generation.parse().ok());
+    match generation {
+        Some(generation) if &header[r_start..r_end] == b"R" => {
+            Some((start..r_end, value, Some(generation)))
+        }
+        _ => Some((start..end, value, None)),
+    }
+}
+
+/// Reads `/Length` from the raw dictionary of a stream object, following an
+/// indirect `N G R` value through the loaded document.
+fn declared_length(document: &Document, header: &[u8]) -> Option<i64> {
+    match raw_entry(header, b"/Length")? {
+        (_, value, None) => Some(value),
+        (_, number, Some(generation)) => document
+            .get_object((u32::try_from(number).ok()?, generation))
+            .and_then(Object::as_i64)
+            .ok(),
+    }
+}
+
+pub(crate) fn collect_references(object: &Object, references: &mut Vec<ObjectId>) {
+    match object {
+        Object::Reference(id) => references.push(*id),
+        Object::Array(items) => items
+            .iter()
+            .for_each(|item| collect_references(item, references)),
+        Object::Dictionary(dict) => dict
+            .iter()
+            .for_each(|(_, value)| collect_references(value, references)),
+        Object::Stream(stream) => stream
+            .dict
+            .iter()
+            .for_each(|(_, value)| collect_references(value, references)),
+        _ => {}
+    }
+}
+
+pub(crate) fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_pdf;
+    use tempfile::TempDir;
+
+    fn codes(report: &ValidationReport) -> Vec<&str> {
+        report
+            .findings
+            .iter()
+            .map(|finding| finding.code.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_pdf_has_no_errors() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("valid.pdf").to_string_lossy().to_string();
+        create_test_pdf(3, "Valid", "text")
+            .unwrap()
+            .save(&path)
+            .unwrap();
+
+        let report = validate_pdf(&path).unwrap();
+        assert!(report.valid, "{:?}", report.findings);
+        assert_eq!(report.error_count, 0);
+    }
+
+    #[test]
+    fn test_reports_unregistered_stamp_font() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("signed.pdf").to_string_lossy().to_string();
+        create_test_pdf(1, "Signed", "text")
+            .unwrap()
+            .save(&path)
+            .unwrap();
+        crate::sign::sign_pdf_with_options(path.clone(), "Approved".to_string(), None).unwrap();
+
+        let report = validate_pdf(&path).unwrap();
+        let finding = report
+            .findings
+            .iter()
+            .find(|finding| finding.code == "missing_resource")
+            .unwrap();
+        assert_eq!(finding.severity, "error");
+        assert_eq!(finding.page, Some(1));
+        assert!(finding.message.contains("Font /Helvetica"));
+        assert!(!report.valid);
+    }
+
+    #[test]
+    fn test_reports_structural_errors() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("broken.pdf").to_string_lossy().to_string();
+        let mut document = create_test_pdf(2, "Broken", "text").unwrap();
+        let pages = document.get_pages();
+        let page_two = document.get_dictionary_mut(pages[&2]).unwrap();
+        page_two.remove(b"MediaBox");
+        page_two.set("Annots", Object::Array(vec![Object::Reference((999, 0))]));
+        let pages_id = document
+            .catalog()
+            .unwrap()
+            .get(b"Pages")
+            .and_then(Object::as_reference)
+            .unwrap();
+        document
+            .get_dictionary_mut(pages_id)
+            .unwrap()
+            .set("Count", Object::Integer(5));
+        document.save(&path).unwrap();
+
+        // Overstate the first content stream's /Length without moving any
+        // offsets
+        let mut bytes = std::fs::read(&path).unwrap();
+        let content_id = document
+            .get_dictionary(pages[&1])
+            .unwrap()
+            .get(b"Contents")
+            .and_then(Object::as_reference)
+            .unwrap();
+        let length = document
+            .get_object(content_id)
+            .and_then(Object::as_stream)
+            .unwrap()
+            .content
+            .len();
+        let header = format!("{} 0 obj", content_id.0);
+        let object_at = find(&bytes, header.as_bytes()).unwrap();
+        let declared = format!("/Length {length}");
+        let length_at = object_at + find(&bytes[object_at..], declared.as_bytes()).unwrap();
+        let overstated = format!("/Length {}", length + 9);
+        assert_eq!(overstated.len(), declared.len());
+        bytes[length_at..length_at + declared.len()].copy_from_slice(overstated.as_bytes());
+        std::fs::write(&path, bytes).unwrap();
+
+        let report = validate_pdf(&path).unwrap();
+        let codes = codes(&report);
+        assert!(codes.contains(&"page_count"), "{codes:?}");
+        assert!(codes.contains(&"missing_key"), "{codes:?}");
+        assert!(codes.contains(&"dangling_reference"), "{codes:?}");
+        assert!(codes.contains(&"stream_length"), "{codes:?}");
+        let missing_media_box = report
+            .findings
+            .iter()
+            .find(|finding| finding.message.contains("/MediaBox"))
+            .unwrap();
+        assert_eq!(missing_media_box.page, Some(2));
+        assert!(!report.valid);
+        assert!(report.warning_count >= 1);
+    }
+}
@@ -0,0 +1,408 @@
+use lopdf::content::{Content, Operation};
+use lopdf::{Dictionary, Object, ObjectId, Stream, StringFormat};
+
+use crate::sign::{add_page_resource, append_page_content, page_box, prepend_page_content};
+use crate::SigningOptions;
+
+/// Resource name used for the Helvetica font registered by stamping code
+pub(crate) const STAMP_FONT: &str = "SoloHelvetica";
+
+/// Helvetica advance widths (1/1000 em) for the printable ASCII range 32..=126
+#[rustfmt::skip]
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 222, 333, 333, 389, 584, 278, 333, 278, 278, // ' ' - '/'
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, // '0' - '9'
+    278, 278, 584, 584, 584, 556, 1015, // ':' - '@'
+    667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, // 'A' - 'M'
+    722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, // 'N' - 'Z'
+    278, 278, 278, 469, 556, 222, // '[' - '`'
+    556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, // 'a' - 'm'
+    556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, // 'n' - 'z'
+    334, 260, 334, 584, // '{' - '~'
+];
+
+/// Width of `text` in points when set in Helvetica at `font_size`.
+pub fn text_width(text: &str, font_size: f64) -> f64 {
+    let units: u32 = text
+        .chars()
+        .map(|c| match c as u32 {
+            code @ 32..=126 => HELVETICA_WIDTHS[(code - 32) as usize] as u32,
+            _ => 556,
+        })
+        .sum();
+    units as f64 * font_size / 1000.0
+}
+
+/// Encodes text for the WinAnsi-encoded stamp font; characters outside
+/// Latin-1 are replaced with `?`.
+pub(crate) fn encode_text(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| if (c as u32) < 256 { c as u8 } else { b'?' })
+        .collect()
+}
+
+/// Parses a color name or `#rrggbb` hex value into RGB components in 0..=1.
+pub fn parse_color(color: &str) -> Option<(f64, f64, f64)> {
+    let color = color.trim().to_lowercase();
+    if let Some(hex) = color.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return Some((
+            channel(0)? as f64 / 255.0,
+            channel(2)? as f64 / 255.0,
+            channel(4)? as f64 / 255.0,
+        ));
+    }
+
+    match color.as_str() {
+        "black" => Some((0.0, 0.0, 0.0)),
+        "white" => Some((1.0, 1.0, 1.0)),
+        "gray" | "grey" => Some((0.5, 0.5, 0.5)),
+        "red" => Some((1.0, 0.0, 0.0)),
+        "green" => Some((0.0, 0.5, 0.0)),
+        "blue" => Some((0.0, 0.0, 1.0)),
+        "yellow" => Some((1.0, 1.0, 0.0)),
+        "orange" => Some((1.0, 0.65, 0.0)),
+        "purple" => Some((0.5, 0.0, 0.5)),
+        _ => None,
+    }
+}
+
+/// Builds a single line of text running corner to corner across the page.
+///
+/// Unless a font size is given, the text is sized to span 80% of the page
+/// diagonal.
+pub(crate) fn diagonal_operations(
+    document: &lopdf::Document,
+    page_id: ObjectId,
+    text: &str,
+    opts: &SigningOptions,
+) -> Vec<Operation> {
+    let [llx, lly, urx, ury] = page_box(document, page_id);
+    let (width, height) = (urx - llx, ury - lly);
+    let diagonal = width.hypot(height);
+    let angle = height.atan2(width);
+
+    let font_size = opts.font_size.unwrap_or_else(|| {
+        let unit_width = text_width(text, 1.0);
+        if unit_width > 0.0 {
+            diagonal * 0.8 / unit_width
+        } else {
+            12.0
+        }
+    });
+
+    // Center the text on the page, shifting the baseline down by roughly
+    // half the cap height so the glyphs sit on the diagonal itself
+    let (cos, sin) = (angle.cos(), angle.sin());
+    let half_width = text_width(text, font_size) / 2.0;
+    let baseline_shift = font_size * 0.35;
+    let tx = llx + width / 2.0 - cos * half_width + sin * baseline_shift;
+    let ty = lly + height / 2.0 - sin * half_width - cos * baseline_shift;
+
+    vec![
+        Operation::new("BT", vec![]),
+        Operation::new(
+            "Tf",
+            vec![
+                Object::Name(STAMP_FONT.into()),
+                Object::Real(font_size as f32),
+            ],
+        ),
+        Operation::new("Tm", matrix_operands([cos, sin, -sin, cos, tx, ty])),
+        Operation::new(
+            "Tj",
+            vec![Object::String(encode_text(text), StringFormat::Literal)],
+        ),
+        Operation::new("ET", vec![]),
+    ]
+}
+
+/// Builds a rotated grid of repeated text covering the whole page.
+///
+/// Rows are offset by half a tile so the pattern reads as a brick layout.
+/// The grid angle comes from `rotation` (default 45 degrees) and the gap
+/// between tiles from `tile_spacing` (default 72pt).
+pub(crate) fn tile_operations(
+    document: &lopdf::Document,
+    page_id: ObjectId,
+    text: &str,
+    opts: &SigningOptions,
+) -> Vec<Operation> {
+    let [llx, lly, urx, ury] = page_box(document, page_id);
+    let (width, height) = (urx - llx, ury - lly);
+    let font_size = opts.font_size.unwrap_or(24.0).max(1.0);
+    let spacing = opts.tile_spacing.unwrap_or(72.0).max(0.0);
+    let angle = opts.rotation.unwrap_or(45.0).to_radians();
+
+    // Empty text with no spacing would make every tile land on the same spot
+    let step_x = (text_width(text, font_size) + spacing).max(font_size);
+    let step_y = font_size + spacing;
+    // The rotated grid must cover the page in every direction
+    let reach = width.hypot(height) / 2.0;
+
+    let mut operations = vec![
+        Operation::new(
+            "cm",
+            matrix_operands([1.0, 0.0, 0.0, 1.0, llx + width / 2.0, lly + height / 2.0]),
+        ),
+        Operation::new(
+            "cm",
+            matrix_operands([
+                angle.cos(),
+                angle.sin(),
+                -angle.sin(),
+                angle.cos(),
+                0.0,
+                0.0,
+            ]),
+        ),
+        Operation::new("BT", vec![]),
+        Operation::new(
+            "Tf",
+            vec![
+                Object::Name(STAMP_FONT.into()),
+                Object::Real(font_size as f32),
+            ],
+        ),
+    ];
+
+    let encoded = encode_text(text);
+    let rows = (reach / step_y).ceil() as i64;
+    let columns = (reach / step_x).ceil() as i64 + 1;
+    for row in -rows..=rows {
+        let offset = if row % 2 == 0 { 0.0 } else { step_x / 2.0 };
+        for column in -columns..=columns {
+            let x = column as f64 * step_x + offset - step_x / 2.0;
+            let y = row as f64 * step_y;
+            operations.push(Operation::new(
+                "Tm",
+                matrix_operands([1.0, 0.0, 0.0, 1.0, x, y]),
+            ));
+            operations.push(Operation::new(
+                "Tj",
+                vec![Object::String(encoded.clone(), StringFormat::Literal)],
+            ));
+        }
+    }
+    operations.push(Operation::new("ET", vec![]));
+
+    operations
+}
+
+/// Wraps watermark operations in their own graphics state, registers the font
+/// and transparency resources they need, and adds them to the page either
+/// over (overlay) or behind (underlay) the existing content.
+pub(crate) fn stamp_page(
+    document: &mut lopdf::Document,
+    page_id: ObjectId,
+    operations: Vec<Operation>,
+    opts: &SigningOptions,
+    underlay: bool,
+) -> lopdf::Result<()> {
+    let (r, g, b) = opts
+        .color
+        .as_deref()
+        .and_then(parse_color)
+        .unwrap_or((0.5, 0.5, 0.5));
+    let opacity = opts.opacity.unwrap_or(0.3).clamp(0.0, 1.0);
+
+    let font_id = stamp_font(document);
+    add_page_resource(
+        document,
+        page_id,
+        "Font",
+        STAMP_FONT,
+        Object::Reference(font_id),
+    )?;
+
+    let mut graphics_state = Dictionary::new();
+    graphics_state.set("Type", Object::Name(b"ExtGState".to_vec()));
+    graphics_state.set("ca", Object::Real(opacity as f32));
+    graphics_state.set("CA", Object::Real(opacity as f32));
+    let graphics_state_id = document.add_object(graphics_state);
+    // Named after the object, so an earlier watermark keeps its own opacity
+    let graphics_state_name = format!("SoloWatermarkGS{}", graphics_state_id.0);
+    add_page_resource(
+        document,
+        page_id,
+        "ExtGState",
+        &graphics_state_name,
+        Object::Reference(graphics_state_id),
+    )?;
+
+    let mut wrapped = vec![
+        Operation::new("q", vec![]),
+        Operation::new("gs", vec![Object::Name(graphics_state_name.into_bytes())]),
+        Operation::new(
+            "rg",
+            vec![
+                Object::Real(r as f32),
+                Object::Real(g as f32),
+                Object::Real(b as f32),
+            ],
+        ),
+    ];
+    wrapped.extend(operations);
+    wrapped.push(Operation::new("Q", vec![]));
+
+    let content_data = Content {
+        operations: wrapped,
+    }
+    .encode()?;
+    let content_id = document.add_object(Stream::new(Dictionary::new(), content_data));
+
+    if underlay {
+        prepend_page_content(document, page_id, content_id);
+    } else {
+        append_page_content(document, page_id, content_id);
+    }
+    Ok(())
+}
+
+/// Returns the id of the shared Helvetica font used by stamps, adding it to
+/// the document on first use.
+pub(crate) fn stamp_font(document: &mut lopdf::Document) -> ObjectId {
+    let existing = document.objects.iter().find_map(|(id, object)| {
+        let dict = object.as_dict().ok()?;
+        let is_stamp_font = dict.get(b"Type").and_then(Object::as_name).ok() == Some(b"Font")
+            && dict.get(b"BaseFont").and_then(Object::as_name).ok() == Some(b"Helvetica")
+            && dict.get(b"Encoding").and_then(Object::as_name).ok() == Some(b"WinAnsiEncoding");
+        is_stamp_font.then_some(*id)
+    });
+
+    existing.unwrap_or_else(|| {
+        let mut font = Dictionary::new();
+        font.set("Type", Object::Name(b"Font".to_vec()));
+        font.set("Subtype", Object::Name(b"Type1".to_vec()));
+        font.set("BaseFont", Object::Name(b"Helvetica".to_vec()));
+        font.set("Encoding", Object::Name(b"WinAnsiEncoding".to_vec()));
+        document.add_object(font)
+    })
+}
+
+pub(crate) fn matrix_operands(matrix: [f64; 6]) -> Vec<Object> {
+    // Adding zero turns -0.0 into 0.0 so it is not written as "-0"
+    matrix
+        .iter()
+        .map(|v| Object::Real((*v + 0.0) as f32))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sign::sign_pdf_with_options;
+    use crate::test_utils::create_test_pdf;
+    use tempfile::NamedTempFile;
+
+    fn watermark_options(layout: &str, placement: &str) -> SigningOptions {
+        SigningOptions {
+            font_size: None,
+            color: Some("red".to_string()),
+            x_position: None,
+            y_position: None,
+            pages: None,
+            page_range: None,
+            position: None,
+            rotation: None,
+            opacity: Some(0.25),
+            layout: Some(layout.to_string()),
+            tile_spacing: None,
+            placement: Some(placement.to_string()),
+            layer_name: None,
+            layer_visibility: None,
+        }
+    }
+
+    #[test]
+    fn test_text_width_uses_helvetica_metrics() {
+        assert_eq!(text_width("", 12.0), 0.0);
+        // 'W' is 944 units wide, 'i' is 222
+        assert!((text_width("Wi", 10.0) - 11.66).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("red"), Some((1.0, 0.0, 0.0)));
+        assert_eq!(parse_color("#ffffff"), Some((1.0, 1.0, 1.0)));
+        assert_eq!(parse_color("#fff"), None);
+        assert_eq!(parse_color("chartreuse"), None);
+    }
+
+    #[test]
+    fn test_diagonal_fits_page_diagonal() {
+        let doc = create_test_pdf(1, "test", "content").unwrap();
+        let page_id = *doc.get_pages().get(&1).unwrap();
+        let opts = watermark_options("diagonal", "overlay");
+        let operations = diagonal_operations(&doc, page_id, "CONFIDENTIAL", &opts);
+
+        let font_size = operations[1].operands[1].as_float().unwrap() as f64;
+        let expected = 612f64.hypot(792.0) * 0.8;
+        assert!((text_width("CONFIDENTIAL", font_size) - expected).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_underlay_tile_is_drawn_first() {
+        let mut doc = create_test_pdf(2, "test", "content").unwrap();
+        let temp_file = NamedTempFile::new().unwrap();
+        let path = temp_file.path().to_string_lossy().to_string();
+        doc.save(&path).unwrap();
+
+        sign_pdf_with_options(
+            path.clone(),
+            "DRAFT".to_string(),
+            Some(watermark_options("tile", "underlay")),
+        )
+        .unwrap();
+
+        let doc = lopdf::Document::load(&path).unwrap();
+        for page_id in doc.get_pages().values() {
+            let contents = doc.get_page_contents(*page_id);
+            assert_eq!(contents.len(), 2);
+            let first = doc.get_object(contents[0]).unwrap().as_stream().unwrap();
+            let text = String::from_utf8_lossy(&first.content);
+            assert!(text.contains("/SoloWatermarkGS"));
+            assert!(text.contains("(DRAFT) Tj"));
+
+            let fonts = doc.get_page_fonts(*page_id).unwrap();
+            assert!(fonts.contains_key(STAMP_FONT.as_bytes()));
+        }
+    }
+
+    #[test]
+    fn test_tile_with_empty_text_and_no_spacing_is_bounded() {
+        let doc = create_test_pdf(1, "test", "content").unwrap();
+        let page_id = *doc.get_pages().get(&1).unwrap();
+        let mut opts = watermark_options("tile", "overlay");
+        opts.tile_spacing = Some(0.0);
+        let operations = tile_operations(&doc, page_id, "", &opts);
+        assert!(operations.len() < 10_000);
+    }
+
+    #[test]
+    fn test_second_watermark_keeps_first_opacity() {
+        let doc = create_test_pdf(1, "test", "content").unwrap();
+        let temp_file = NamedTempFile::new().unwrap();
+        let path = temp_file.path().to_string_lossy().to_string();
+        doc.clone().save(&path).unwrap();
+
+        for (text, opacity) in [("DRAFT", 0.25), ("COPY", 0.75)] {
+            let mut opts = watermark_options("diagonal", "overlay");
+            opts.opacity = Some(opacity);
+            sign_pdf_with_options(path.clone(), text.to_string(), Some(opts)).unwrap();
+        }
+
+        let doc = lopdf::Document::load(&path).unwrap();
+        let page_id = *doc.get_pages().get(&1).unwrap();
+        let (resources, _) = doc.get_page_resources(page_id).unwrap();
+        let graphics_states = resources
+            .unwrap()
+            .get(b"ExtGState")
+            .and_then(Object::as_dict)
+            .unwrap();
+        assert_eq!(graphics_states.len(), 2);
+    }
+}